@@ -0,0 +1,203 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::frequency::Frequency;
+use crate::interval::Interval;
+use crate::key_mappings::KeyMappings;
+use crate::keyboard_mapping::KeyboardMapping;
+use crate::midi_note::MidiNote;
+use crate::reference::Reference;
+use crate::scale::Scale;
+use crate::scl_file::SclFile;
+use crate::tuning_format::{Tuning, TuningReader, TuningWriter};
+use crate::types::KeyNumber;
+use anyhow::{anyhow, bail, Result};
+use std::fmt::Write;
+
+// Live places degree 0 of octave 3 on middle C
+const ZERO_KEY: u8 = 60;
+const ZERO_OCTAVE: i64 = 3;
+
+/// Reads an Ableton `.ascl` file: a Scala scale whose `@ABL REFERENCE_PITCH`
+/// directive gives the frequency of one degree of the scale
+pub struct AsclReader;
+
+impl TuningReader for AsclReader {
+    fn read_tuning(&self, bytes: &[u8]) -> Result<Tuning> {
+        let s = String::from_utf8_lossy(bytes);
+        let scl_file = s.parse::<SclFile>()?;
+        let size = scl_file.scale().intervals().len() as i64;
+
+        let zero_key = KeyNumber::constant::<ZERO_KEY>();
+        let mut reference = Reference::new(
+            zero_key,
+            zero_key,
+            MidiNote::ALL[ZERO_KEY as usize].frequency(),
+        );
+
+        for line in s.lines() {
+            let Some(directive) = line
+                .trim()
+                .strip_prefix('!')
+                .and_then(|l| l.trim().strip_prefix("@ABL"))
+            else {
+                continue;
+            };
+
+            let parts = directive.split_whitespace().collect::<Vec<_>>();
+            if let ["REFERENCE_PITCH", octave, degree, frequency] = parts.as_slice() {
+                let invalid = || anyhow!("Invalid REFERENCE_PITCH directive: {directive}");
+                let octave = octave.parse::<i64>().map_err(|_| invalid())?;
+                let degree = degree.parse::<i64>().map_err(|_| invalid())?;
+                let frequency = frequency.parse::<f64>().map_err(|_| invalid())?;
+                let reference_key =
+                    u8::try_from(ZERO_KEY as i64 + (octave - ZERO_OCTAVE) * size + degree)
+                        .ok()
+                        .and_then(|k| KeyNumber::try_from(k).ok())
+                        .ok_or_else(|| anyhow!("REFERENCE_PITCH is outside the MIDI key range"))?;
+                reference = Reference::new(zero_key, reference_key, Frequency(frequency));
+            }
+        }
+
+        Ok(Tuning::new(
+            scl_file.description(),
+            scl_file.scale().clone(),
+            KeyboardMapping::new_full_linear(&reference)?,
+        ))
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &["@ABL directives other than REFERENCE_PITCH, such as NOTE_NAMES, are dropped"]
+    }
+}
+
+/// Writes an Ableton `.ascl` file with degree 0 of the scale on middle C
+pub struct AsclWriter;
+
+impl TuningWriter for AsclWriter {
+    fn write_tuning(&self, tuning: &Tuning) -> Result<String> {
+        let keyboard_mapping = tuning.keyboard_mapping();
+        if !matches!(keyboard_mapping.key_mappings(), KeyMappings::Linear) {
+            bail!(".ascl files only support linear keyboard mappings")
+        }
+
+        let intervals = tuning.scale().intervals();
+        let size = intervals.len() as i64;
+        let zero_key = keyboard_mapping.reference().zero_key().to_u8() as i64;
+        let degree = (ZERO_KEY as i64 - zero_key).rem_euclid(size) as usize;
+        let scale = if degree == 0 {
+            tuning.scale().clone()
+        } else {
+            rotate(intervals, degree)?
+        };
+
+        // Frequency computed over the full keyboard since .ascl has no key range
+        let full_tuning = Tuning::new(
+            tuning.description(),
+            tuning.scale().clone(),
+            KeyboardMapping::new_full_linear(keyboard_mapping.reference())?,
+        );
+        let Some(frequency) = full_tuning.frequencies()?[ZERO_KEY as usize] else {
+            bail!("Key {ZERO_KEY} is not mapped")
+        };
+
+        let mut s = SclFile::new(None, tuning.description(), scale).to_string();
+        writeln!(s, "!")?;
+        writeln!(
+            s,
+            "! @ABL REFERENCE_PITCH {ZERO_OCTAVE} 0 {frequency:.6}",
+            frequency = frequency.0
+        )?;
+        Ok(s)
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &[
+            "Only linear keyboard mappings can be written",
+            "Key range limits of the keyboard mapping are dropped",
+            "Scales whose degree 0 is not on middle C are rotated, converting their intervals to cents",
+        ]
+    }
+}
+
+// Rotates a scale so that it starts at the given degree, keeping its equave
+fn rotate(intervals: &[Interval], degree: usize) -> Result<Scale> {
+    let size = intervals.len();
+    let cents = std::iter::once(0f64)
+        .chain(intervals.iter().map(|i| 1200f64 * i.as_ratio().0.log2()))
+        .collect::<Vec<_>>();
+    let equave = cents[size];
+    let cents_at = |i: usize| {
+        if i > size {
+            cents[i - size] + equave
+        } else {
+            cents[i]
+        }
+    };
+
+    let mut rotated = (1..size)
+        .map(|i| Interval::from_cents(cents_at(degree + i) - cents[degree]))
+        .collect::<Vec<_>>();
+    rotated.push(intervals[size - 1].clone());
+    Scale::new(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ascl_file::{AsclReader, AsclWriter};
+    use crate::resources::include_resource_str;
+    use crate::tuning_format::{SclReader, TuningReader, TuningWriter};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(440f64, 69, "! @ABL REFERENCE_PITCH 3 9 440")]
+    #[case(220f64, 57, "! @ABL REFERENCE_PITCH 2 9 220")]
+    #[case(261.625565f64, 60, "")]
+    fn read(#[case] expected: f64, #[case] key: usize, #[case] directive: &str) -> Result<()> {
+        let s = format!(
+            "{scl}{directive}\n",
+            scl = include_resource_str!("carlos_super.scl")
+        );
+        let tuning = AsclReader.read_tuning(s.as_bytes())?;
+        let actual = tuning.frequencies()?[key].expect("Must be mapped").0;
+        assert!((expected - actual).abs() < 0.0001f64);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(include_resource_str!("carlos_super.scl"))]
+    #[case(include_resource_str!("22edo2.scl"))]
+    fn round_trip(#[case] scl: &str) -> Result<()> {
+        // Default mapping has its zero key at 69 so the scale is rotated
+        let tuning = SclReader::new(None).read_tuning(scl.as_bytes())?;
+        let s = AsclWriter.write_tuning(&tuning)?;
+        let actual = AsclReader.read_tuning(s.as_bytes())?;
+
+        for (expected, actual) in tuning.frequencies()?.iter().zip(actual.frequencies()?) {
+            let expected = expected.expect("Must be mapped").0;
+            let actual = actual.expect("Must be mapped").0;
+            assert!((expected - actual).abs() < 0.001f64);
+        }
+        Ok(())
+    }
+}
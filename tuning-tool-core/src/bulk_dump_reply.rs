@@ -22,9 +22,10 @@
 
 use crate::checksum_calculator::ChecksumCalculator;
 use crate::consts::{
-    BULK_DUMP_REPLY, BULK_DUMP_REPLY_CHECKSUM_COUNT, EOX, MIDI_TUNING, SYSEX,
-    UNIVERSAL_NON_REAL_TIME,
+    BULK_DUMP_REPLY, BULK_DUMP_REPLY_CHECKSUM_COUNT, BULK_DUMP_REPLY_MESSAGE_SIZE, EOX,
+    MIDI_TUNING, SYSEX, UNIVERSAL_NON_REAL_TIME,
 };
+use crate::midi_message_builder::MidiMessageBuilder;
 use crate::mts_entry::MtsEntry;
 use crate::note_number::NoteNumber;
 use crate::preset_name::PresetName;
@@ -44,10 +45,12 @@ macro_rules! read_u8 {
 
 const ENTRIES_LEN: usize = 128;
 
-pub(crate) type MtsEntries = [MtsEntry; ENTRIES_LEN];
+/// Entry for each key, or `None` to leave the key unchanged
+pub type MtsEntries = [Option<MtsEntry>; ENTRIES_LEN];
 
+/// MTS bulk tuning dump reply (non-real-time) message
 #[derive(Debug)]
-pub(crate) struct BulkDumpReply {
+pub struct BulkDumpReply {
     device_id: DeviceId,
     preset: Preset,
     name: PresetName,
@@ -55,8 +58,7 @@ pub(crate) struct BulkDumpReply {
 }
 
 impl BulkDumpReply {
-    #[cfg(test)]
-    pub(crate) fn new(
+    pub fn new(
         device_id: DeviceId,
         preset: Preset,
         name: PresetName,
@@ -70,25 +72,25 @@ impl BulkDumpReply {
         })
     }
 
-    pub(crate) const fn device_id(&self) -> DeviceId {
+    pub const fn device_id(&self) -> DeviceId {
         self.device_id
     }
 
-    pub(crate) const fn preset(&self) -> Preset {
+    pub const fn preset(&self) -> Preset {
         self.preset
     }
 
-    pub(crate) fn name(&self) -> &PresetName {
+    pub fn name(&self) -> &PresetName {
         &self.name
     }
 
-    pub(crate) fn entries(&self) -> &MtsEntries {
+    pub fn entries(&self) -> &MtsEntries {
         &self.entries
     }
 }
 
 impl BulkDumpReply {
-    pub(crate) fn from_bytes<R: Read>(bytes: Bytes<R>) -> Result<Self> {
+    pub fn from_bytes<R: Read>(bytes: Bytes<R>) -> Result<Self> {
         let mut calc = ChecksumCalculator::new();
 
         let mut iter = bytes.filter_map(Result::<_, _>::ok).peekable();
@@ -118,25 +120,20 @@ impl BulkDumpReply {
 
         let entries: MtsEntries = (0..ENTRIES_LEN)
             .map(|_| {
-                let note_number = read::<NoteNumber, _>(&mut iter)?;
-                let msb = read::<Msb, _>(&mut iter)?;
-                let lsb = read::<Lsb, _>(&mut iter)?;
+                let note_number = calc.update(read::<NoteNumber, _>(&mut iter)?);
+                let msb = calc.update(read::<Msb, _>(&mut iter)?);
+                let lsb = calc.update(read::<Lsb, _>(&mut iter)?);
                 Ok(MtsEntry {
                     note_number,
                     msb,
                     lsb,
-                })
+                }
+                .to_option())
             })
             .collect::<Result<Vec<_>>>()?
             .try_into()
             .expect("Vector must have exactly 128 elements");
 
-        for e in &entries {
-            _ = calc.update(e.note_number);
-            _ = calc.update(e.msb);
-            _ = calc.update(e.lsb);
-        }
-
         let checksum = read::<Checksum, _>(&mut iter)?;
 
         if read_u8!(iter) != EOX {
@@ -153,11 +150,7 @@ impl BulkDumpReply {
         })
     }
 
-    #[cfg(test)]
-    pub(crate) fn to_vec(&self) -> Result<Vec<MidiValue>> {
-        use crate::consts::BULK_DUMP_REPLY_MESSAGE_SIZE;
-        use crate::midi_message_builder::MidiMessageBuilder;
-
+    pub fn to_vec(&self) -> Result<Vec<MidiValue>> {
         let mut calc = ChecksumCalculator::new();
         let mut values = MidiMessageBuilder::with_required_len(BULK_DUMP_REPLY_MESSAGE_SIZE);
         values.push(calc.update(UNIVERSAL_NON_REAL_TIME));
@@ -169,6 +162,7 @@ impl BulkDumpReply {
         values.extend_from_slice(calc.update_from_slice(self.name.as_array()));
 
         for e in &self.entries {
+            let e = e.unwrap_or(MtsEntry::NO_CHANGE);
             values.push(calc.update(e.note_number));
             values.push(calc.update(e.msb));
             values.push(calc.update(e.lsb));
@@ -179,10 +173,7 @@ impl BulkDumpReply {
         values.finalize()
     }
 
-    #[cfg(test)]
-    pub(crate) fn to_bytes_with_start_and_end(&self) -> Result<Vec<u8>> {
-        use crate::consts::BULK_DUMP_REPLY_MESSAGE_SIZE;
-
+    pub fn to_bytes_with_start_and_end(&self) -> Result<Vec<u8>> {
         let vec = self.to_vec()?;
         let inner_bytes = MidiValue::to_u8_slice(&vec);
         let mut bytes = Vec::with_capacity(inner_bytes.len() + 2);
@@ -235,9 +226,11 @@ mod tests {
                 .expect("Must succeed")
                 .iter()
                 .map(|mapping| {
-                    Frequency(mapping.frequency.as_f64())
-                        .to_mts_entry()
-                        .expect("Must succeed")
+                    Some(
+                        Frequency(mapping.frequency.as_f64())
+                            .to_mts_entry()
+                            .expect("Must succeed"),
+                    )
                 })
                 .collect::<Vec<_>>()
                 .try_into()
@@ -267,6 +260,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn no_change() -> Result<()> {
+        let reply = BulkDumpReply::from_bytes(include_resource_bytes!("carlos_super.syx").bytes())?;
+        let mut entries = *reply.entries();
+        entries[0] = None;
+
+        let bytes = BulkDumpReply::new(
+            reply.device_id(),
+            reply.preset(),
+            "carlos_super".parse()?,
+            entries,
+        )?
+        .to_bytes_with_start_and_end()?;
+        assert_eq!(&[0x7f, 0x7f, 0x7f], &bytes[22..25]);
+
+        let reply = BulkDumpReply::from_bytes(bytes.bytes())?;
+        assert!(reply.entries()[0].is_none());
+        assert_eq!(entries[1..], reply.entries()[1..]);
+        Ok(())
+    }
+
     #[test]
     fn reference_key_0_min() {
         verify_bytes!(
@@ -24,37 +24,33 @@ use crate::types::Checksum;
 use anyhow::{bail, Result};
 use tuning_tool_lib::u7::U7;
 
-pub(crate) struct ChecksumCalculator {
+pub struct ChecksumCalculator {
     count: usize,
     value: Checksum,
 }
 
 impl ChecksumCalculator {
-    pub(crate) const fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             count: 0,
             value: Checksum::MAX,
         }
     }
 
-    pub(crate) fn update<U: Copy + U7>(&mut self, value: U) -> U {
+    pub fn update<U: Copy + U7>(&mut self, value: U) -> U {
         self.count += 1;
         self.value = Checksum::from_u8_lossy(self.value.to_u8() ^ value.to_u8());
         value
     }
 
-    pub(crate) fn update_from_slice<'a, U: Copy + U7>(&mut self, values: &'a [U]) -> &'a [U] {
+    pub fn update_from_slice<'a, U: Copy + U7>(&mut self, values: &'a [U]) -> &'a [U] {
         for value in values {
             _ = self.update(*value);
         }
         values
     }
 
-    pub(crate) fn verify(
-        self,
-        expected_checksum: Checksum,
-        expected_count: Option<usize>,
-    ) -> Result<()> {
+    pub fn verify(self, expected_checksum: Checksum, expected_count: Option<usize>) -> Result<()> {
         let checksum = self.finalize(expected_count)?;
         if checksum != expected_checksum {
             bail!("Checksum validation failed")
@@ -62,7 +58,7 @@ impl ChecksumCalculator {
         Ok(())
     }
 
-    pub(crate) fn finalize(self, expected_count: Option<usize>) -> Result<Checksum> {
+    pub fn finalize(self, expected_count: Option<usize>) -> Result<Checksum> {
         if let Some(expected_count) = expected_count {
             assert_eq!(expected_count, self.count);
             if expected_count != self.count {
@@ -75,3 +71,9 @@ impl ChecksumCalculator {
         Ok(self.value)
     }
 }
+
+impl Default for ChecksumCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
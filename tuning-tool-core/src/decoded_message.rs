@@ -0,0 +1,790 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::checksum_calculator::ChecksumCalculator;
+use crate::consts::{EOX, MIDI_TUNING, SYSEX, UNIVERSAL_NON_REAL_TIME, UNIVERSAL_REAL_TIME};
+use crate::frequency::Frequency;
+use crate::mts_entry::MtsEntry;
+use crate::note_change_entry::NoteChangeEntry;
+use crate::note_number::NoteNumber;
+use crate::read::read;
+use crate::types::{Checksum, DeviceId, Lsb, MidiValue, Msb, Preset};
+use anyhow::{anyhow, bail, Error, Result};
+use midly::live::LiveEvent;
+use midly::MidiMessage;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+const BULK_DUMP_REQUEST: u8 = 0x00;
+const BULK_DUMP_REPLY: u8 = 0x01;
+const NOTE_CHANGE: u8 = 0x02;
+const BULK_DUMP_REQUEST_WITH_BANK: u8 = 0x03;
+const BULK_DUMP_REPLY_WITH_BANK: u8 = 0x04;
+const SCALE_OCTAVE_DUMP_1_BYTE: u8 = 0x05;
+const SCALE_OCTAVE_DUMP_2_BYTE: u8 = 0x06;
+const NOTE_CHANGE_WITH_BANK: u8 = 0x07;
+const SCALE_OCTAVE_1_BYTE: u8 = 0x08;
+const SCALE_OCTAVE_2_BYTE: u8 = 0x09;
+
+const EOF: u8 = 0x7b;
+const WAIT: u8 = 0x7c;
+const CANCEL: u8 = 0x7d;
+const NAK: u8 = 0x7e;
+const ACK: u8 = 0x7f;
+
+const NAME_LEN: usize = 16;
+const BULK_DUMP_ENTRY_COUNT: usize = 128;
+const SCALE_OCTAVE_LEN: usize = 12;
+
+/// Broad category of a decoded MIDI message, used for filtering
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MessageKind {
+    NoteChange,
+    BulkDump,
+    DumpRequest,
+    ScaleOctave,
+    Handshake,
+    SysEx,
+    Channel,
+    System,
+}
+
+impl MessageKind {
+    pub const ALL: [MessageKind; 8] = [
+        Self::NoteChange,
+        Self::BulkDump,
+        Self::DumpRequest,
+        Self::ScaleOctave,
+        Self::Handshake,
+        Self::SysEx,
+        Self::Channel,
+        Self::System,
+    ];
+}
+
+impl Display for MessageKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::NoteChange => write!(f, "note-change"),
+            Self::BulkDump => write!(f, "bulk-dump"),
+            Self::DumpRequest => write!(f, "dump-request"),
+            Self::ScaleOctave => write!(f, "scale-octave"),
+            Self::Handshake => write!(f, "handshake"),
+            Self::SysEx => write!(f, "sysex"),
+            Self::Channel => write!(f, "channel"),
+            Self::System => write!(f, "system"),
+        }
+    }
+}
+
+impl FromStr for MessageKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        for kind in Self::ALL {
+            if s == kind.to_string() {
+                return Ok(kind);
+            }
+        }
+        bail!("Invalid message kind {s}")
+    }
+}
+
+/// Universal non-real-time handshaking message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Handshake {
+    EndOfFile,
+    Wait,
+    Cancel,
+    Nak,
+    Ack,
+}
+
+impl Display for Handshake {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::EndOfFile => write!(f, "EOF"),
+            Self::Wait => write!(f, "WAIT"),
+            Self::Cancel => write!(f, "CANCEL"),
+            Self::Nak => write!(f, "NAK"),
+            Self::Ack => write!(f, "ACK"),
+        }
+    }
+}
+
+/// MIDI message decoded from raw bytes as received from an input port or
+/// read from a .syx file
+#[derive(Clone, Debug)]
+pub enum DecodedMessage {
+    NoteChange {
+        real_time: bool,
+        device_id: DeviceId,
+        bank: Option<MidiValue>,
+        preset: Preset,
+        entries: Vec<NoteChangeEntry>,
+    },
+    DumpRequest {
+        device_id: DeviceId,
+        bank: Option<MidiValue>,
+        preset: Preset,
+    },
+    BulkDump {
+        device_id: DeviceId,
+        bank: Option<MidiValue>,
+        preset: Preset,
+        name: String,
+        entries: Vec<Option<MtsEntry>>,
+        checksum_valid: bool,
+    },
+    ScaleOctaveDump {
+        device_id: DeviceId,
+        bank: MidiValue,
+        preset: Preset,
+        name: String,
+        cents: Vec<f64>,
+        checksum_valid: bool,
+    },
+    ScaleOctave {
+        real_time: bool,
+        device_id: DeviceId,
+        channels: Vec<u8>,
+        cents: Vec<f64>,
+    },
+    Handshake {
+        device_id: DeviceId,
+        handshake: Handshake,
+        packet: MidiValue,
+    },
+    SysEx {
+        manufacturer_id: Vec<u8>,
+        device_id: Option<DeviceId>,
+        len: usize,
+    },
+    Channel {
+        channel: u8,
+        message: MidiMessage,
+    },
+    System(String),
+}
+
+impl DecodedMessage {
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            Some(&SYSEX) => Self::decode_sysex(bytes),
+            Some(_) => Self::decode_live(bytes),
+            None => bail!("Empty message"),
+        }
+    }
+
+    pub const fn kind(&self) -> MessageKind {
+        match self {
+            Self::NoteChange { .. } => MessageKind::NoteChange,
+            Self::DumpRequest { .. } => MessageKind::DumpRequest,
+            Self::BulkDump { .. } => MessageKind::BulkDump,
+            Self::ScaleOctaveDump { .. } | Self::ScaleOctave { .. } => MessageKind::ScaleOctave,
+            Self::Handshake { .. } => MessageKind::Handshake,
+            Self::SysEx { .. } => MessageKind::SysEx,
+            Self::Channel { .. } => MessageKind::Channel,
+            Self::System(_) => MessageKind::System,
+        }
+    }
+
+    fn decode_live(bytes: &[u8]) -> Result<Self> {
+        match LiveEvent::parse(bytes).map_err(|e| anyhow!("Invalid MIDI message: {e}"))? {
+            LiveEvent::Midi { channel, message } => Ok(Self::Channel {
+                channel: channel.as_int(),
+                message,
+            }),
+            LiveEvent::Common(message) => Ok(Self::System(format!("{message:?}"))),
+            LiveEvent::Realtime(message) => Ok(Self::System(format!("{message:?}"))),
+        }
+    }
+
+    fn decode_sysex(bytes: &[u8]) -> Result<Self> {
+        let Some((&EOX, body)) = bytes[1..].split_last() else {
+            bail!("EOX not found")
+        };
+
+        let universal = match body {
+            [sub_id, ..] if *sub_id == UNIVERSAL_NON_REAL_TIME.to_u8() => Some(false),
+            [sub_id, ..] if *sub_id == UNIVERSAL_REAL_TIME.to_u8() => Some(true),
+            _ => None,
+        };
+
+        match (universal, body) {
+            (Some(false), [_, device_id, handshake @ WAIT..=ACK, packet]) => Ok(Self::Handshake {
+                device_id: DeviceId::try_from(*device_id)?,
+                handshake: match *handshake {
+                    WAIT => Handshake::Wait,
+                    CANCEL => Handshake::Cancel,
+                    NAK => Handshake::Nak,
+                    _ => Handshake::Ack,
+                },
+                packet: MidiValue::try_from(*packet)?,
+            }),
+            (Some(false), [_, device_id, EOF, packet]) => Ok(Self::Handshake {
+                device_id: DeviceId::try_from(*device_id)?,
+                handshake: Handshake::EndOfFile,
+                packet: MidiValue::try_from(*packet)?,
+            }),
+            (Some(real_time), [_, _, sub_id_1, sub_id_2, ..])
+                if *sub_id_1 == MIDI_TUNING.to_u8() =>
+            {
+                Self::decode_midi_tuning(real_time, *sub_id_2, body)
+            }
+            _ => Self::decode_other_sysex(universal.is_some(), body),
+        }
+    }
+
+    fn decode_midi_tuning(real_time: bool, sub_id_2: u8, body: &[u8]) -> Result<Self> {
+        let mut calc = ChecksumCalculator::new();
+        let mut iter = body.iter().copied();
+        _ = calc.update(read::<MidiValue, _>(&mut iter)?);
+        let device_id = calc.update(read::<DeviceId, _>(&mut iter)?);
+        _ = calc.update(read::<MidiValue, _>(&mut iter)?);
+        _ = calc.update(read::<MidiValue, _>(&mut iter)?);
+
+        let message = match (real_time, sub_id_2) {
+            (false, BULK_DUMP_REQUEST) => Self::DumpRequest {
+                device_id,
+                bank: None,
+                preset: read(&mut iter)?,
+            },
+            (false, BULK_DUMP_REQUEST_WITH_BANK) => Self::DumpRequest {
+                device_id,
+                bank: Some(read(&mut iter)?),
+                preset: read(&mut iter)?,
+            },
+            (false, BULK_DUMP_REPLY | BULK_DUMP_REPLY_WITH_BANK) => {
+                let bank = if sub_id_2 == BULK_DUMP_REPLY_WITH_BANK {
+                    Some(calc.update(read::<MidiValue, _>(&mut iter)?))
+                } else {
+                    None
+                };
+                let preset = calc.update(read::<Preset, _>(&mut iter)?);
+                let name = read_name(&mut iter, &mut calc)?;
+                let entries = (0..BULK_DUMP_ENTRY_COUNT)
+                    .map(|_| {
+                        Ok(MtsEntry {
+                            note_number: calc.update(read::<NoteNumber, _>(&mut iter)?),
+                            msb: calc.update(read::<Msb, _>(&mut iter)?),
+                            lsb: calc.update(read::<Lsb, _>(&mut iter)?),
+                        }
+                        .to_option())
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let checksum = read::<Checksum, _>(&mut iter)?;
+                Self::BulkDump {
+                    device_id,
+                    bank,
+                    preset,
+                    name,
+                    entries,
+                    checksum_valid: calc.finalize(None)? == checksum,
+                }
+            }
+            (false, SCALE_OCTAVE_DUMP_1_BYTE | SCALE_OCTAVE_DUMP_2_BYTE) => {
+                let bank = calc.update(read::<MidiValue, _>(&mut iter)?);
+                let preset = calc.update(read::<Preset, _>(&mut iter)?);
+                let name = read_name(&mut iter, &mut calc)?;
+                let cents = read_scale_octave_cents(
+                    &mut iter,
+                    sub_id_2 == SCALE_OCTAVE_DUMP_2_BYTE,
+                    &mut calc,
+                )?;
+                let checksum = read::<Checksum, _>(&mut iter)?;
+                Self::ScaleOctaveDump {
+                    device_id,
+                    bank,
+                    preset,
+                    name,
+                    cents,
+                    checksum_valid: calc.finalize(None)? == checksum,
+                }
+            }
+            (true, NOTE_CHANGE) | (_, NOTE_CHANGE_WITH_BANK) => {
+                let bank = if sub_id_2 == NOTE_CHANGE_WITH_BANK {
+                    Some(read(&mut iter)?)
+                } else {
+                    None
+                };
+                let preset = read(&mut iter)?;
+                let count = read::<MidiValue, _>(&mut iter)?.to_u8();
+                let entries = (0..count)
+                    .map(|_| {
+                        Ok(NoteChangeEntry {
+                            key_number: read(&mut iter)?,
+                            mts: MtsEntry {
+                                note_number: read(&mut iter)?,
+                                msb: read(&mut iter)?,
+                                lsb: read(&mut iter)?,
+                            },
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Self::NoteChange {
+                    real_time,
+                    device_id,
+                    bank,
+                    preset,
+                    entries,
+                }
+            }
+            (_, SCALE_OCTAVE_1_BYTE | SCALE_OCTAVE_2_BYTE) => {
+                let ff = read::<MidiValue, _>(&mut iter)?.to_u8();
+                let gg = read::<MidiValue, _>(&mut iter)?.to_u8();
+                let hh = read::<MidiValue, _>(&mut iter)?.to_u8();
+                let mask = (ff as u16) << 14 | (gg as u16) << 7 | hh as u16;
+                let channels = (0..16).filter(|i| mask & (1 << i) != 0).collect();
+                let cents = read_scale_octave_cents(
+                    &mut iter,
+                    sub_id_2 == SCALE_OCTAVE_2_BYTE,
+                    &mut ChecksumCalculator::new(),
+                )?;
+                Self::ScaleOctave {
+                    real_time,
+                    device_id,
+                    channels,
+                    cents,
+                }
+            }
+            _ => bail!("Unsupported MIDI tuning message {sub_id_2:02X}"),
+        };
+
+        if iter.next().is_some() {
+            bail!("Unexpected data at end of MIDI tuning message")
+        }
+
+        Ok(message)
+    }
+
+    fn decode_other_sysex(universal: bool, body: &[u8]) -> Result<Self> {
+        let id_len = match body.first() {
+            Some(0) => 3,
+            Some(_) => 1,
+            None => bail!("Missing manufacturer ID"),
+        };
+        if body.len() < id_len {
+            bail!("Truncated manufacturer ID")
+        }
+
+        let device_id = if universal {
+            body.get(1).map(|b| DeviceId::try_from(*b)).transpose()?
+        } else {
+            None
+        };
+
+        Ok(Self::SysEx {
+            manufacturer_id: body[..id_len].to_vec(),
+            device_id,
+            len: body.len() + 2,
+        })
+    }
+}
+
+impl Display for DecodedMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        fn fmt_bank(bank: &Option<MidiValue>) -> String {
+            bank.map(|b| format!(" bank {b}")).unwrap_or_default()
+        }
+
+        fn fmt_checksum(checksum_valid: bool) -> &'static str {
+            if checksum_valid {
+                "checksum OK"
+            } else {
+                "checksum INVALID"
+            }
+        }
+
+        fn fmt_cents(f: &mut Formatter<'_>, cents: &[f64]) -> FmtResult {
+            const NAMES: [&str; SCALE_OCTAVE_LEN] = [
+                "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+            ];
+            for (name, cents) in NAMES.iter().zip(cents) {
+                write!(f, "\n  {name:<2}: {cents:+.2} cents")?;
+            }
+            Ok(())
+        }
+
+        match self {
+            Self::NoteChange {
+                real_time,
+                device_id,
+                bank,
+                preset,
+                entries,
+            } => {
+                write!(
+                    f,
+                    "Note change ({timing}) device {device_id}{bank} preset {preset}: {count} key(s)",
+                    timing = if *real_time { "real-time" } else { "non-real-time" },
+                    bank = fmt_bank(bank),
+                    count = entries.len()
+                )?;
+                for e in entries {
+                    let key = e.key_number.to_u8();
+                    write!(f, "\n  {}", TunedKey::new(key, e.mts.to_option()))?;
+                }
+                Ok(())
+            }
+            Self::DumpRequest {
+                device_id,
+                bank,
+                preset,
+            } => write!(
+                f,
+                "Bulk dump request device {device_id}{bank} preset {preset}",
+                bank = fmt_bank(bank)
+            ),
+            Self::BulkDump {
+                device_id,
+                bank,
+                preset,
+                name,
+                entries,
+                checksum_valid,
+            } => {
+                write!(
+                    f,
+                    "Bulk dump device {device_id}{bank} preset {preset} \"{name}\" ({checksum})",
+                    bank = fmt_bank(bank),
+                    checksum = fmt_checksum(*checksum_valid)
+                )?;
+                for (key, e) in entries.iter().enumerate() {
+                    write!(f, "\n  {}", TunedKey::new(key as u8, *e))?;
+                }
+                Ok(())
+            }
+            Self::ScaleOctaveDump {
+                device_id,
+                bank,
+                preset,
+                name,
+                cents,
+                checksum_valid,
+            } => {
+                write!(
+                    f,
+                    "Scale/octave dump device {device_id} bank {bank} preset {preset} \"{name}\" ({checksum})",
+                    checksum = fmt_checksum(*checksum_valid)
+                )?;
+                fmt_cents(f, cents)
+            }
+            Self::ScaleOctave {
+                real_time,
+                device_id,
+                channels,
+                cents,
+            } => {
+                write!(
+                    f,
+                    "Scale/octave tuning ({timing}) device {device_id} channels {channels}",
+                    timing = if *real_time {
+                        "real-time"
+                    } else {
+                        "non-real-time"
+                    },
+                    channels = channels
+                        .iter()
+                        .map(|c| (c + 1).to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )?;
+                fmt_cents(f, cents)
+            }
+            Self::Handshake {
+                device_id,
+                handshake,
+                packet,
+            } => write!(
+                f,
+                "Handshake {handshake} device {device_id} packet {packet}"
+            ),
+            Self::SysEx {
+                manufacturer_id,
+                device_id,
+                len,
+            } => {
+                write!(
+                    f,
+                    "SysEx manufacturer {manufacturer_id}",
+                    manufacturer_id = manufacturer_id
+                        .iter()
+                        .map(|b| format!("{b:02X}"))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )?;
+                match manufacturer_id.as_slice() {
+                    [0x7d] => write!(f, " (non-commercial)")?,
+                    [0x7e] => write!(f, " (universal non-real-time)")?,
+                    [0x7f] => write!(f, " (universal real-time)")?,
+                    _ => {}
+                }
+                if let Some(device_id) = device_id {
+                    write!(f, " device {device_id}")?;
+                }
+                write!(f, " ({len} bytes)")
+            }
+            Self::Channel { channel, message } => {
+                write!(f, "Channel {channel}: ", channel = channel + 1)?;
+                match message {
+                    MidiMessage::NoteOff { key, vel } => write!(f, "note off {key} velocity {vel}"),
+                    MidiMessage::NoteOn { key, vel } => write!(f, "note on {key} velocity {vel}"),
+                    MidiMessage::Aftertouch { key, vel } => {
+                        write!(f, "aftertouch {key} pressure {vel}")
+                    }
+                    MidiMessage::Controller { controller, value } => {
+                        write!(f, "controller {controller} value {value}")
+                    }
+                    MidiMessage::ProgramChange { program } => write!(f, "program change {program}"),
+                    MidiMessage::ChannelAftertouch { vel } => {
+                        write!(f, "channel aftertouch {vel}")
+                    }
+                    MidiMessage::PitchBend { bend } => write!(f, "pitch bend {}", bend.as_int()),
+                }
+            }
+            Self::System(message) => write!(f, "System {message}"),
+        }
+    }
+}
+
+/// Tuning of a single key: target frequency and offset from 12-EDO, or
+/// `None` for both if the key is left unchanged
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TunedKey {
+    pub key: u8,
+    pub frequency: Option<Frequency>,
+    pub cents: Option<f64>,
+}
+
+impl TunedKey {
+    pub fn new(key: u8, mts: Option<MtsEntry>) -> Self {
+        Self {
+            key,
+            frequency: mts.map(|mts| mts.to_frequency()),
+            cents: mts.map(|mts| (mts.to_semitones().0 - key as f64) * 100f64),
+        }
+    }
+}
+
+impl Display for TunedKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match (self.frequency, self.cents) {
+            (Some(frequency), Some(cents)) => write!(
+                f,
+                "key {key:>3}: {frequency:.6} Hz ({cents:+.2} cents)",
+                key = self.key,
+                frequency = frequency.0
+            ),
+            _ => write!(f, "key {key:>3}: no change", key = self.key),
+        }
+    }
+}
+
+fn read_name<I: Iterator<Item = u8>>(
+    iter: &mut I,
+    calc: &mut ChecksumCalculator,
+) -> Result<String> {
+    let bytes = (0..NAME_LEN)
+        .map(|_| Ok(calc.update(read::<MidiValue, _>(iter)?).to_u8()))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(String::from_utf8_lossy(&bytes)
+        .trim_end_matches(['\0', ' '])
+        .to_string())
+}
+
+fn read_scale_octave_cents<I: Iterator<Item = u8>>(
+    iter: &mut I,
+    two_byte: bool,
+    calc: &mut ChecksumCalculator,
+) -> Result<Vec<f64>> {
+    (0..SCALE_OCTAVE_LEN)
+        .map(|_| {
+            let msb = calc.update(read::<MidiValue, _>(iter)?).to_u8();
+            if two_byte {
+                let lsb = calc.update(read::<MidiValue, _>(iter)?).to_u8();
+                let value = ((msb as u16) << 7) | lsb as u16;
+                Ok((value as f64 - 8192f64) * 100f64 / 8192f64)
+            } else {
+                Ok(msb as f64 - 64f64)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bulk_dump_reply::BulkDumpReply;
+    use crate::decoded_message::{DecodedMessage, Handshake, MessageKind};
+    use crate::mts_entry::MtsEntry;
+    use crate::mts_messages::make_note_change_messages;
+    use crate::note_change_entry::NoteChangeEntry;
+    use crate::resources::include_resource_bytes;
+    use crate::semitones::Semitones;
+    use crate::types::{ChunkSize, DeviceId, KeyNumber, Preset};
+    use anyhow::Result;
+    use rstest::rstest;
+    use std::io::Read;
+
+    #[rstest]
+    #[case(MessageKind::NoteChange, "note-change")]
+    #[case(MessageKind::ScaleOctave, "scale-octave")]
+    #[case(MessageKind::SysEx, "sysex")]
+    fn message_kind(#[case] kind: MessageKind, #[case] s: &str) -> Result<()> {
+        assert_eq!(s, kind.to_string());
+        assert_eq!(kind, s.parse()?);
+        Ok(())
+    }
+
+    #[test]
+    fn note_change() -> Result<()> {
+        let entries = vec![NoteChangeEntry {
+            key_number: KeyNumber::try_from(60)?,
+            mts: Semitones(60.5).to_mts_entry()?,
+        }];
+        let messages = make_note_change_messages(
+            DeviceId::try_from(1)?,
+            Preset::ZERO,
+            &entries,
+            ChunkSize::try_from(16)?,
+        )?;
+        assert_eq!(1, messages.len());
+
+        let DecodedMessage::NoteChange {
+            real_time,
+            device_id,
+            bank,
+            entries,
+            ..
+        } = DecodedMessage::decode(&messages[0])?
+        else {
+            panic!("Expected note change")
+        };
+        assert!(real_time);
+        assert_eq!(1, device_id.to_u8());
+        assert!(bank.is_none());
+        assert_eq!(1, entries.len());
+        assert_eq!(60, entries[0].key_number.to_u8());
+        Ok(())
+    }
+
+    #[test]
+    fn note_change_no_change() -> Result<()> {
+        let entries = vec![NoteChangeEntry {
+            key_number: KeyNumber::try_from(60)?,
+            mts: MtsEntry::NO_CHANGE,
+        }];
+        let messages =
+            make_note_change_messages(DeviceId::ZERO, Preset::ZERO, &entries, ChunkSize::ONE)?;
+        let message = DecodedMessage::decode(&messages[0])?;
+        assert!(message.to_string().ends_with("key  60: no change"));
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_dump() -> Result<()> {
+        let bytes = include_resource_bytes!("carlos_super.syx").bytes();
+        let reply = BulkDumpReply::from_bytes(bytes)?;
+        let mut bytes = reply.to_bytes_with_start_and_end()?;
+
+        let message = DecodedMessage::decode(&bytes)?;
+        assert_eq!(MessageKind::BulkDump, message.kind());
+        let DecodedMessage::BulkDump {
+            entries,
+            checksum_valid,
+            ..
+        } = message
+        else {
+            panic!("Expected bulk dump")
+        };
+        assert_eq!(128, entries.len());
+        assert!(checksum_valid);
+
+        let checksum_index = bytes.len() - 2;
+        bytes[checksum_index] ^= 1;
+        let DecodedMessage::BulkDump { checksum_valid, .. } = DecodedMessage::decode(&bytes)?
+        else {
+            panic!("Expected bulk dump")
+        };
+        assert!(!checksum_valid);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(&[0xf0, 0x7f, 0x7f, 0x08, 0x08, 0x03, 0x7f, 0x7f, 0x40, 0x41, 0x3f, 0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x00, 0xf7], 16, [0f64, 1f64, -1f64, 0f64])]
+    #[case(&[0xf0, 0x7e, 0x00, 0x08, 0x09, 0x00, 0x00, 0x01, 0x40, 0x00, 0x60, 0x00, 0x20, 0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0xf7], 1, [0f64, 50f64, -50f64, 0f64])]
+    fn scale_octave(
+        #[case] input: &[u8],
+        #[case] expected_channel_count: usize,
+        #[case] expected_cents: [f64; 4],
+    ) -> Result<()> {
+        let DecodedMessage::ScaleOctave {
+            channels, cents, ..
+        } = DecodedMessage::decode(input)?
+        else {
+            panic!("Expected scale/octave tuning")
+        };
+        assert_eq!(expected_channel_count, channels.len());
+        assert_eq!(expected_cents, cents[..4]);
+        Ok(())
+    }
+
+    #[test]
+    fn handshake() -> Result<()> {
+        let DecodedMessage::Handshake {
+            handshake, packet, ..
+        } = DecodedMessage::decode(&[0xf0, 0x7e, 0x00, 0x7f, 0x05, 0xf7])?
+        else {
+            panic!("Expected handshake")
+        };
+        assert_eq!(Handshake::Ack, handshake);
+        assert_eq!(5, packet.to_u8());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(MessageKind::Channel, "Channel 1: note on 60 velocity 100", &[0x90, 60, 100])]
+    #[case(MessageKind::SysEx, "SysEx manufacturer 43 (5 bytes)", &[0xf0, 0x43, 0x10, 0x01, 0xf7])]
+    #[case(MessageKind::SysEx, "SysEx manufacturer 00 20 29 (6 bytes)", &[0xf0, 0x00, 0x20, 0x29, 0x01, 0xf7])]
+    #[case(MessageKind::SysEx, "SysEx manufacturer 7E (universal non-real-time) device 127 (6 bytes)", &[0xf0, 0x7e, 0x7f, 0x06, 0x01, 0xf7])]
+    #[case(MessageKind::System, "System TimingClock", &[0xf8])]
+    fn other(
+        #[case] expected_kind: MessageKind,
+        #[case] expected_str: &str,
+        #[case] input: &[u8],
+    ) -> Result<()> {
+        let message = DecodedMessage::decode(input)?;
+        assert_eq!(expected_kind, message.kind());
+        assert_eq!(expected_str, message.to_string());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(&[])]
+    #[case(&[0xf0, 0x7f, 0x00, 0x08, 0x02, 0x00])]
+    #[case(&[0xf0, 0x7f, 0x00, 0x08, 0x02, 0x00, 0x01, 0xf7])]
+    #[case(&[0xf0, 0x7e, 0x00, 0x08, 0x00, 0x00, 0x00, 0xf7])]
+    fn invalid(#[case] input: &[u8]) {
+        assert!(DecodedMessage::decode(input).is_err());
+    }
+}
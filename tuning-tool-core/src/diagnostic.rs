@@ -0,0 +1,282 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::{bail, Result};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// How serious a parse problem is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Problem found while parsing a file, with 1-based line and column
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning<S: Into<String>>(line: usize, column: usize, message: S) -> Self {
+        Self {
+            severity: Severity::Warning,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    pub fn error<S: Into<String>>(line: usize, column: usize, message: S) -> Self {
+        Self {
+            severity: Severity::Error,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{line}:{column}: {severity}: {message}",
+            line = self.line,
+            column = self.column,
+            severity = self.severity,
+            message = self.message
+        )
+    }
+}
+
+/// Outcome of a tolerant parse: the value, unless there were errors, and
+/// every warning and error found along the way, in file order
+#[derive(Debug)]
+pub struct Parsed<T> {
+    pub value: Option<T>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<T> Parsed<T> {
+    pub(crate) fn new(value: Option<T>, mut diagnostics: Vec<Diagnostic>) -> Self {
+        // Checks that span several lines report after the per-line checks
+        diagnostics.sort_by_key(|d| (d.line, d.column));
+        let value = if diagnostics.iter().any(Diagnostic::is_error) {
+            None
+        } else {
+            value
+        };
+        Self { value, diagnostics }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| !d.is_error())
+    }
+
+    /// Value, ignoring any warnings, or the first error: use `warnings` first
+    /// to report them
+    pub fn into_result(self) -> Result<T> {
+        for diagnostic in &self.diagnostics {
+            if diagnostic.is_error() {
+                bail!(
+                    "Line {line}, column {column}: {message}",
+                    line = diagnostic.line,
+                    column = diagnostic.column,
+                    message = diagnostic.message
+                )
+            }
+        }
+
+        match self.value {
+            Some(value) => Ok(value),
+            None => bail!("Parsing failed"),
+        }
+    }
+}
+
+/// Line of a text file with its 1-based line number
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SourceLine<'a> {
+    pub(crate) number: usize,
+    pub(crate) text: &'a str,
+}
+
+impl<'a> SourceLine<'a> {
+    // Splits on LF, CRLF or lone CR line endings, dropping any byte-order mark
+    pub(crate) fn split(s: &'a str) -> Vec<Self> {
+        let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+        let s = s.strip_suffix('\n').unwrap_or(s);
+        let s = s.strip_suffix('\r').unwrap_or(s);
+        if s.is_empty() {
+            return Vec::new();
+        }
+
+        s.split('\n')
+            .flat_map(|line| line.strip_suffix('\r').unwrap_or(line).split('\r'))
+            .enumerate()
+            .map(|(i, text)| Self {
+                number: i + 1,
+                text,
+            })
+            .collect()
+    }
+
+    // Line number just past the end of the file, for reporting missing values
+    pub(crate) fn end_line(lines: &[Self]) -> usize {
+        lines.last().map_or(1, |line| line.number + 1)
+    }
+
+    pub(crate) fn trimmed(&self) -> &'a str {
+        self.text.trim()
+    }
+
+    // 1-based column of a slice of this line's text
+    pub(crate) fn column(&self, part: &str) -> usize {
+        let offset = part.as_ptr() as usize - self.text.as_ptr() as usize;
+        self.text[..offset].chars().count() + 1
+    }
+
+    pub(crate) fn warning<S: Into<String>>(&self, part: &str, message: S) -> Diagnostic {
+        Diagnostic::warning(self.number, self.column(part), message)
+    }
+
+    pub(crate) fn error<S: Into<String>>(&self, part: &str, message: S) -> Diagnostic {
+        Diagnostic::error(self.number, self.column(part), message)
+    }
+
+    // First whitespace-delimited value on the line and whatever follows it
+    pub(crate) fn split_value(&self) -> (&'a str, &'a str) {
+        let s = self.trimmed();
+        match s.find(char::is_whitespace) {
+            Some(i) => (&s[..i], s[i..].trim_start()),
+            None => (s, ""),
+        }
+    }
+
+    // Parses the first value on the line, tolerating junk stuck to the end
+    // of the value (e.g. "3/2,") and, unless allowed, warning about text
+    // after it
+    pub(crate) fn parse_value<T: FromStr>(
+        &self,
+        what: &str,
+        allow_trailing_text: bool,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<T> {
+        let (token, rest) = self.split_value();
+        if let Ok(value) = token.parse() {
+            if !allow_trailing_text && !rest.is_empty() {
+                diagnostics.push(self.warning(rest, format!("Ignoring text after {what}")));
+            }
+            return Some(value);
+        }
+
+        let prefix = numeric_prefix(token);
+        if !prefix.is_empty() && prefix.len() < token.len() {
+            if let Ok(value) = prefix.parse() {
+                diagnostics.push(self.warning(
+                    &token[prefix.len()..],
+                    format!("Ignoring text after {what}"),
+                ));
+                return Some(value);
+            }
+        }
+
+        diagnostics.push(self.error(token, format!("Expected {what}, found \"{token}\"")));
+        None
+    }
+}
+
+// Longest prefix of a token that looks like a number, e.g. "3/2" of "3/2,"
+fn numeric_prefix(token: &str) -> &str {
+    let end = token
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '/' | '-' | '+')))
+        .unwrap_or(token.len());
+    &token[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Diagnostic, SourceLine};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(vec![], "")]
+    #[case(vec!["a"], "a")]
+    #[case(vec!["a", "b"], "a\nb\n")]
+    #[case(vec!["a", "b"], "a\r\nb\r\n")]
+    #[case(vec!["a", "b", "c"], "a\rb\r\nc")]
+    #[case(vec!["a", "", "b"], "\u{feff}a\n\nb")]
+    fn split(#[case] expected: Vec<&str>, #[case] input: &str) {
+        let lines = SourceLine::split(input);
+        assert_eq!(
+            expected,
+            lines.iter().map(|line| line.text).collect::<Vec<_>>()
+        );
+        assert!(lines
+            .iter()
+            .enumerate()
+            .all(|(i, line)| line.number == i + 1));
+    }
+
+    #[rstest]
+    #[case(Some(12), vec![], "12")]
+    #[case(Some(12), vec![], "  12  ")]
+    #[case(Some(12), vec![Diagnostic::warning(1, 4, "Ignoring text after count")], "12 notes")]
+    #[case(Some(12), vec![Diagnostic::warning(1, 3, "Ignoring text after count")], "12,")]
+    #[case(None, vec![Diagnostic::error(1, 2, "Expected count, found \"twelve\"")], " twelve")]
+    fn parse_value(
+        #[case] expected_value: Option<usize>,
+        #[case] expected_diagnostics: Vec<Diagnostic>,
+        #[case] input: &str,
+    ) {
+        let line = SourceLine {
+            number: 1,
+            text: input,
+        };
+        let mut diagnostics = Vec::new();
+        let value = line.parse_value::<usize>("count", false, &mut diagnostics);
+        assert_eq!(expected_value, value);
+        assert_eq!(expected_diagnostics, diagnostics);
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::frequency::Frequency;
+use crate::interval::Interval;
+use crate::keyboard_mapping::KeyboardMapping;
+use crate::midi_note::MidiNote;
+use crate::mts_entry::MtsEntry;
+use crate::reference::Reference;
+use crate::scale::Scale;
+use crate::semitones::Semitones;
+use crate::tuning_format::Tuning;
+use crate::types::KeyNumber;
+use anyhow::{bail, Result};
+use std::iter::zip;
+
+const KEY_COUNT: usize = 128;
+
+// Each MTS entry is quantized to 100/16384 cents so a difference between
+// two entries may be out by twice that
+const TOLERANCE_CENTS: f64 = 0.02;
+
+const MAX_PERIOD: usize = 64;
+
+// Keys around middle C are searched for the zero key
+const MIDDLE_KEY: usize = 60;
+
+// Equaves written as ratios, in order of preference
+const KNOWN_EQUAVES: [(i64, i64); 3] = [(2, 1), (3, 1), (3, 2)];
+
+/// Scale and keyboard mapping reconstructed from a full 128-key tuning
+/// table such as the contents of a bulk dump
+#[derive(Debug)]
+pub struct ImportedTuning {
+    tuning: Tuning,
+    is_periodic: bool,
+}
+
+impl ImportedTuning {
+    /// Guesses the period, zero key and step pattern of the tuning, falling
+    /// back to a 128-note scale with a linear mapping when no pattern
+    /// repeats: keys left unchanged are assumed to be at their 12-EDO
+    /// frequencies
+    pub fn from_entries(description: &str, entries: &[Option<MtsEntry>]) -> Result<Self> {
+        if entries.len() != KEY_COUNT {
+            bail!("Expected {KEY_COUNT} MTS entries, got {}", entries.len())
+        }
+
+        let frequencies = zip(entries, MidiNote::ALL)
+            .map(|(e, note)| e.map_or(note.frequency(), |e| e.to_frequency()))
+            .collect::<Vec<_>>();
+
+        // Keys at either end of the MTS range have probably been clamped and
+        // unchanged keys say nothing about the tuning
+        let in_range = entries
+            .iter()
+            .map(|e| {
+                e.is_some_and(|e| {
+                    let semitones = e.to_semitones().0;
+                    semitones > 0f64 && semitones < Semitones::MAX.0 - 0.0001f64
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Self::new(description, &frequencies, &in_range)
+    }
+
+    /// As `from_entries` but for exact frequencies such as a `.tun` file's
+    /// 128-note table
+    pub fn from_frequencies(description: &str, frequencies: &[Frequency]) -> Result<Self> {
+        if frequencies.len() != KEY_COUNT {
+            bail!(
+                "Expected {KEY_COUNT} frequencies, got {}",
+                frequencies.len()
+            )
+        }
+
+        if frequencies.iter().any(|f| !f.0.is_finite() || f.0 <= 0f64) {
+            bail!("Frequencies must be positive and finite")
+        }
+
+        Self::new(description, frequencies, &[true; KEY_COUNT])
+    }
+
+    fn new(description: &str, frequencies: &[Frequency], in_range: &[bool]) -> Result<Self> {
+        let cents = frequencies
+            .iter()
+            .map(|f| 1200f64 * (f.0 / Frequency::MIN.0).log2())
+            .collect::<Vec<_>>();
+
+        let periodic = find_period(&cents, in_range).and_then(|(period, equave)| {
+            find_zero_key(&cents, in_range, period).map(|zero_key| (period, equave, zero_key))
+        });
+
+        let (intervals, zero_key, is_periodic) = match periodic {
+            Some((period, equave, zero_key)) => {
+                let mut intervals = (1..period)
+                    .map(|i| Interval::from_cents(cents[zero_key + i] - cents[zero_key]))
+                    .collect::<Vec<_>>();
+                intervals.push(equave);
+                (intervals, zero_key, true)
+            }
+            None => {
+                let mut intervals = (1..KEY_COUNT)
+                    .map(|i| Interval::from_cents(cents[i] - cents[0]))
+                    .collect::<Vec<_>>();
+                let last_step = cents[KEY_COUNT - 1] - cents[KEY_COUNT - 2];
+                intervals.push(Interval::from_cents(
+                    cents[KEY_COUNT - 1] - cents[0] + last_step,
+                ));
+                (intervals, 0, false)
+            }
+        };
+
+        let zero_key_number = KeyNumber::try_from(zero_key as u8)?;
+        let reference = Reference::new(zero_key_number, zero_key_number, frequencies[zero_key]);
+
+        Ok(Self {
+            tuning: Tuning::new(
+                description,
+                Scale::new(intervals)?,
+                KeyboardMapping::new_full_linear(&reference)?,
+            ),
+            is_periodic,
+        })
+    }
+
+    pub const fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+
+    pub fn into_tuning(self) -> Tuning {
+        self.tuning
+    }
+
+    pub const fn is_periodic(&self) -> bool {
+        self.is_periodic
+    }
+}
+
+// Returns the number of keys per period and the equave, preferring
+// well-known equaves over shorter periods
+fn find_period(cents: &[f64], in_range: &[bool]) -> Option<(usize, Interval)> {
+    let periods = (1..=MAX_PERIOD)
+        .filter_map(|period| {
+            let diffs = (0..KEY_COUNT - period)
+                .filter(|k| in_range[*k] && in_range[k + period])
+                .map(|k| cents[k + period] - cents[k])
+                .collect::<Vec<_>>();
+            if diffs.len() < period {
+                return None;
+            }
+
+            let equave = diffs.iter().sum::<f64>() / diffs.len() as f64;
+            if equave <= 0f64 || diffs.iter().any(|d| (d - equave).abs() > TOLERANCE_CENTS) {
+                return None;
+            }
+
+            Some((period, equave))
+        })
+        .collect::<Vec<_>>();
+
+    for (numer, denom) in KNOWN_EQUAVES {
+        let known_cents = 1200f64 * (numer as f64 / denom as f64).log2();
+        if let Some((period, _)) = periods
+            .iter()
+            .find(|(_, equave)| (equave - known_cents).abs() <= TOLERANCE_CENTS)
+        {
+            return Some((*period, Interval::from_ratio(numer, denom)));
+        }
+    }
+
+    periods
+        .first()
+        .map(|(period, equave)| (*period, Interval::from_cents(*equave)))
+}
+
+// Picks the key in the period ending at middle C that is closest to its
+// 12-EDO pitch, falling back to any key with a whole period in range above
+// it
+fn find_zero_key(cents: &[f64], in_range: &[bool], period: usize) -> Option<usize> {
+    let has_full_period =
+        |k: usize| k + period < KEY_COUNT && (k..=k + period).all(|k| in_range[k]);
+    let deviation = |k: usize| (cents[k] - k as f64 * 100f64).abs();
+
+    (MIDDLE_KEY.saturating_sub(period - 1)..=MIDDLE_KEY)
+        .rev()
+        .filter(|k| has_full_period(*k))
+        .fold(None, |best: Option<usize>, k| match best {
+            Some(best) if deviation(best) <= deviation(k) + TOLERANCE_CENTS => Some(best),
+            _ => Some(k),
+        })
+        .or_else(|| (0..KEY_COUNT).find(|k| has_full_period(*k)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dump_import::ImportedTuning;
+    use crate::frequency::Frequency;
+    use crate::kbm_file::KbmFile;
+    use crate::key_frequency_mapping::compute_direct;
+    use crate::keyboard_mapping::KeyboardMapping;
+    use crate::mts_entry::MtsEntry;
+    use crate::reference::Reference;
+    use crate::resources::include_resource_str;
+    use crate::scale::Scale;
+    use crate::scl_file::SclFile;
+    use crate::semitones::Semitones;
+    use crate::tuning_format::{KbmWriter, SclWriter, TuningWriter};
+    use crate::types::KeyNumber;
+    use anyhow::Result;
+    use rstest::rstest;
+
+    const EDO12: &str = "12-EDO\n12\n100.0\n200.0\n300.0\n400.0\n500.0\n600.0\n700.0\n800.0\n900.0\n1000.0\n1100.0\n2/1\n";
+
+    fn to_mts_entries(scale: &Scale, keyboard_mapping: &KeyboardMapping) -> Result<Vec<MtsEntry>> {
+        compute_direct(scale, keyboard_mapping)?
+            .iter()
+            .map(|m| Frequency(m.frequency.0).to_mts_entry())
+            .collect()
+    }
+
+    fn to_options(entries: &[MtsEntry]) -> Vec<Option<MtsEntry>> {
+        entries.iter().copied().map(Some).collect()
+    }
+
+    fn assert_round_trip(expected: &[MtsEntry], imported: &ImportedTuning) -> Result<()> {
+        // Written files must parse and reproduce the original tuning
+        let tuning = imported.tuning();
+        let scl_file = SclWriter.write_tuning(tuning)?.parse::<SclFile>()?;
+        let kbm_file = KbmWriter.write_tuning(tuning)?.parse::<KbmFile>()?;
+        let actual = to_mts_entries(scl_file.scale(), kbm_file.keyboard_mapping())?;
+        assert_eq!(expected.len(), actual.len());
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert!((expected.to_semitones().0 - actual.to_semitones().0).abs() < 0.0005f64);
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(12, "2/1", 60, EDO12, 69, 440f64)]
+    #[case(22, "2/1", 60, include_resource_str!("22edo2.scl"), 60, 261.625565f64)]
+    #[case(12, "2/1", 60, include_resource_str!("carlos_super.scl"), 60, 261.625565f64)]
+    #[case(13, "3/1", 60, include_resource_str!("scala-archive/bohlen-p_et.scl"), 60, 261.625565f64)]
+    fn periodic(
+        #[case] expected_period: usize,
+        #[case] expected_equave: &str,
+        #[case] expected_zero_key: u8,
+        #[case] scl: &str,
+        #[case] reference_key: u8,
+        #[case] reference_frequency: f64,
+    ) -> Result<()> {
+        let scl_file = scl.parse::<SclFile>()?;
+        let reference_key = KeyNumber::try_from(reference_key)?;
+        let keyboard_mapping = KeyboardMapping::new_full_linear(&Reference::new(
+            reference_key,
+            reference_key,
+            Frequency(reference_frequency),
+        ))?;
+        let entries = to_mts_entries(scl_file.scale(), &keyboard_mapping)?;
+
+        let imported = ImportedTuning::from_entries("imported", &to_options(&entries))?;
+        assert!(imported.is_periodic());
+        let intervals = imported.tuning().scale().intervals();
+        assert_eq!(expected_period, intervals.len());
+        assert_eq!(expected_equave, intervals[intervals.len() - 1].to_string());
+        assert_eq!(
+            expected_zero_key,
+            imported
+                .tuning()
+                .keyboard_mapping()
+                .reference()
+                .zero_key()
+                .to_u8()
+        );
+
+        assert_round_trip(&entries, &imported)
+    }
+
+    #[test]
+    fn not_periodic() -> Result<()> {
+        let entries = (0..128)
+            .map(|k| Semitones(k as f64 + ((k * k) % 101) as f64 / 200f64).to_mts_entry())
+            .collect::<Result<Vec<_>>>()?;
+
+        let imported = ImportedTuning::from_entries("irregular", &to_options(&entries))?;
+        assert!(!imported.is_periodic());
+        assert_eq!(128, imported.tuning().scale().intervals().len());
+        assert_eq!(
+            0,
+            imported
+                .tuning()
+                .keyboard_mapping()
+                .reference()
+                .zero_key()
+                .to_u8()
+        );
+
+        assert_round_trip(&entries, &imported)
+    }
+
+    #[test]
+    fn unchanged_keys() -> Result<()> {
+        let scl_file = include_resource_str!("carlos_super.scl").parse::<SclFile>()?;
+        let keyboard_mapping = KeyboardMapping::new_full_linear(&Reference::default())?;
+        let entries = to_mts_entries(scl_file.scale(), &keyboard_mapping)?;
+        let mut options = to_options(&entries);
+        options[..12].fill(None);
+
+        let imported = ImportedTuning::from_entries("unchanged", &options)?;
+        assert!(imported.is_periodic());
+        assert_eq!(12, imported.tuning().scale().intervals().len());
+        assert_round_trip(&entries, &imported)
+    }
+
+    #[rstest]
+    #[case(&[])]
+    #[case(&[Frequency(0f64); 128])]
+    fn invalid_frequencies(#[case] input: &[Frequency]) {
+        assert!(ImportedTuning::from_frequencies("invalid", input).is_err());
+    }
+
+    #[test]
+    fn wrong_length() {
+        assert!(ImportedTuning::from_entries("short", &[]).is_err());
+    }
+}
@@ -23,7 +23,7 @@
 use tuning_tool_lib::symbolic::Expression;
 use tuning_tool_lib::symbolic::Value::{R, Z};
 
-pub(crate) trait Evaluate {
+pub trait Evaluate {
     fn as_f64(&self) -> f64;
 }
 
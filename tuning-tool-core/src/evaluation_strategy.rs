@@ -30,7 +30,7 @@ use std::fmt::Display;
 use std::ops::{Div, Mul};
 use tuning_tool_lib::symbolic::Expression;
 
-pub(crate) trait EvaluationStrategy {
+pub trait EvaluationStrategy {
     type Frequency: Clone
         + Display
         + Div<Self::Ratio, Output = Self::Frequency>
@@ -43,7 +43,7 @@ pub(crate) trait EvaluationStrategy {
     fn interval_ratio(interval: &Interval) -> Self::Ratio;
 }
 
-pub(crate) struct Symbolic;
+pub struct Symbolic;
 
 impl EvaluationStrategy for Symbolic {
     type Frequency = Expression;
@@ -62,7 +62,7 @@ impl EvaluationStrategy for Symbolic {
     }
 }
 
-pub(crate) struct Direct;
+pub struct Direct;
 
 impl EvaluationStrategy for Direct {
     type Frequency = Frequency;
@@ -30,21 +30,20 @@ use crate::types::f64_newtype;
 use anyhow::Result;
 use std::ops::{Div, Mul};
 
-f64_newtype!(Frequency, pub(crate));
+f64_newtype!(Frequency, pub);
 
 impl Frequency {
-    pub(crate) const CONCERT_A4: Self = Self(440f64);
-    pub(crate) const MAX: Self = Self(13289.656616f64);
+    pub const CONCERT_A4: Self = Self(440f64);
+    pub const MAX: Self = Self(13289.656616f64);
 
-    #[cfg(test)]
-    pub(crate) const MIN: Frequency = crate::midi_note::MidiNote::ALL[0].frequency();
+    pub const MIN: Frequency = crate::midi_note::MidiNote::ALL[0].frequency();
 
     // c.f. ftomts
-    pub(crate) fn to_semitones(&self) -> Semitones {
+    pub fn to_semitones(&self) -> Semitones {
         self.to_semitones_with_ignore_limit(false)
     }
 
-    pub(crate) fn to_semitones_with_ignore_limit(&self, ignore_limit: bool) -> Semitones {
+    pub fn to_semitones_with_ignore_limit(&self, ignore_limit: bool) -> Semitones {
         if self.0 <= 0f64 {
             return Semitones(0f64);
         }
@@ -58,7 +57,7 @@ impl Frequency {
         Semitones(value)
     }
 
-    pub(crate) fn to_mts_entry(&self) -> Result<MtsEntry> {
+    pub fn to_mts_entry(&self) -> Result<MtsEntry> {
         self.to_semitones().to_mts_entry()
     }
 
@@ -0,0 +1,104 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::dump_import::ImportedTuning;
+use crate::frequency_table::FrequencyTable;
+use crate::midi_note::MidiNote;
+use crate::tuning_format::{Tuning, TuningReader, TuningWriter};
+use anyhow::Result;
+
+/// Reads a frequency table as a tuning, with one frequency or cents value
+/// per MIDI key
+pub struct FrequencyListReader;
+
+impl TuningReader for FrequencyListReader {
+    fn read_tuning(&self, bytes: &[u8]) -> Result<Tuning> {
+        let frequency_table = String::from_utf8_lossy(bytes).parse::<FrequencyTable>()?;
+        let frequencies = frequency_table
+            .frequencies()
+            .iter()
+            .zip(MidiNote::ALL)
+            .map(|(frequency, note)| frequency.unwrap_or(note.frequency()))
+            .collect::<Vec<_>>();
+
+        Ok(
+            ImportedTuning::from_frequencies(frequency_table.description(), &frequencies)?
+                .into_tuning(),
+        )
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &[
+            "Scale structure is inferred from the 128 frequencies",
+            "Keys marked x are read at their 12-EDO frequencies",
+        ]
+    }
+}
+
+/// Writes the frequency of each of the 128 MIDI keys in Hz, one per line,
+/// with unmapped keys marked x
+pub struct FrequencyListWriter;
+
+impl TuningWriter for FrequencyListWriter {
+    fn write_tuning(&self, tuning: &Tuning) -> Result<String> {
+        Ok(FrequencyTable::new(tuning.description(), tuning.frequencies()?)?.to_string())
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &["Scale intervals and equave are flattened into a 128-note table"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frequency_list::{FrequencyListReader, FrequencyListWriter};
+    use crate::resources::include_resource_str;
+    use crate::tuning_format::{SclReader, TuningReader, TuningWriter};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let tuning = SclReader::new(None)
+            .read_tuning(include_resource_str!("carlos_super.scl").as_bytes())?;
+        let s = FrequencyListWriter.write_tuning(&tuning)?;
+        let actual = FrequencyListReader.read_tuning(s.as_bytes())?;
+
+        assert_eq!("Carlos Super Just", actual.description());
+        assert_eq!(12, actual.scale().intervals().len());
+        for (expected, actual) in tuning.frequencies()?.iter().zip(actual.frequencies()?) {
+            let expected = expected.expect("Must be mapped").0;
+            let actual = actual.expect("Must be mapped").0;
+            assert!((expected - actual).abs() < 0.0001f64);
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("440\n")]
+    #[case("hello\n")]
+    #[case("6900c\n")]
+    fn read_fails(#[case] input: &str) {
+        assert!(FrequencyListReader.read_tuning(input.as_bytes()).is_err());
+    }
+}
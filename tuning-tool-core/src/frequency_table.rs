@@ -0,0 +1,202 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::frequency::Frequency;
+#[cfg(feature = "fs")]
+use crate::fs::read_to_string_lossy;
+use crate::semitones::Semitones;
+use anyhow::{anyhow, bail, Error, Result};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+const KEY_COUNT: usize = 128;
+
+/// Frequencies of all 128 MIDI keys given directly rather than derived from
+/// a scale and keyboard mapping, such as a measured instrument
+///
+/// The text form has one value per key, in key order: a frequency in Hz,
+/// cents above MIDI key 0 (8.18 Hz) with a `c` suffix, or `x` for a key
+/// that is not retuned. Blank lines and lines starting with `!` or `#` are
+/// ignored, except that the first comment is the description.
+#[derive(Clone, Debug)]
+pub struct FrequencyTable {
+    description: String,
+    frequencies: Vec<Option<Frequency>>,
+}
+
+impl FrequencyTable {
+    #[cfg(feature = "fs")]
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        read_to_string_lossy(path)?.parse()
+    }
+
+    pub fn new(description: &str, frequencies: Vec<Option<Frequency>>) -> Result<Self> {
+        if frequencies.len() != KEY_COUNT {
+            bail!(
+                "Expected {KEY_COUNT} frequencies, got {}",
+                frequencies.len()
+            )
+        }
+
+        if frequencies
+            .iter()
+            .flatten()
+            .any(|f| !f.0.is_finite() || f.0 <= 0f64)
+        {
+            bail!("Frequencies must be positive and finite")
+        }
+
+        Ok(Self {
+            description: String::from(description),
+            frequencies,
+        })
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Frequency of each MIDI key, or `None` for keys that are not retuned
+    pub fn frequencies(&self) -> &[Option<Frequency>] {
+        &self.frequencies
+    }
+}
+
+impl Display for FrequencyTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "# {}", self.description)?;
+        for frequency in &self.frequencies {
+            match frequency {
+                Some(frequency) => writeln!(f, "{:.6}", frequency.0)?,
+                None => writeln!(f, "x")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FrequencyTable {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        fn parse_value(s: &str) -> Option<Option<Frequency>> {
+            if s.eq_ignore_ascii_case("x") {
+                return Some(None);
+            }
+
+            if let Some(cents) = s.strip_suffix(['c', 'C']) {
+                let cents = cents.trim().parse::<f64>().ok()?;
+                return Some(Some(Semitones(cents / 100f64).to_frequency()));
+            }
+
+            Some(Some(Frequency(s.parse().ok()?)))
+        }
+
+        let mut description = None;
+        let mut frequencies = Vec::with_capacity(KEY_COUNT);
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(comment) = line.strip_prefix(['!', '#']) {
+                description.get_or_insert_with(|| comment.trim());
+                continue;
+            }
+
+            frequencies
+                .push(parse_value(line).ok_or_else(|| {
+                    anyhow!("Invalid frequency or cents value at line {}", i + 1)
+                })?);
+        }
+
+        Self::new(description.unwrap_or(""), frequencies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frequency_table::FrequencyTable;
+    use anyhow::Result;
+    use rstest::rstest;
+
+    fn make_table(values: &[&str]) -> String {
+        values
+            .iter()
+            .copied()
+            .chain(std::iter::repeat("x"))
+            .take(128)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[rstest]
+    #[case(Some(440f64), "440")]
+    #[case(Some(440f64), "6900c")]
+    #[case(Some(440f64), "6900.0 C")]
+    #[case(Some(8.175799f64), "0c")]
+    #[case(None, "x")]
+    fn parse_value(#[case] expected: Option<f64>, #[case] input: &str) -> Result<()> {
+        let table = make_table(&[input]).parse::<FrequencyTable>()?;
+        let actual = table.frequencies()[0].map(|f| f.0);
+        match (expected, actual) {
+            (Some(expected), Some(actual)) => assert!((expected - actual).abs() < 0.0001f64),
+            (expected, actual) => assert_eq!(expected, actual),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let s = format!(
+            "! Measured piano\n\n# Another comment\n{}",
+            make_table(&["100", "x", "1234.5c"])
+        );
+        let table = s.parse::<FrequencyTable>()?;
+        assert_eq!("Measured piano", table.description());
+
+        let actual = table.to_string().parse::<FrequencyTable>()?;
+        assert_eq!("Measured piano", actual.description());
+        for (expected, actual) in table.frequencies().iter().zip(actual.frequencies()) {
+            match (expected, actual) {
+                (Some(expected), Some(actual)) => {
+                    assert!((expected.0 - actual.0).abs() < 0.000001f64)
+                }
+                (expected, actual) => assert_eq!(expected.is_none(), actual.is_none()),
+            }
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("440\n")]
+    #[case(&make_table(&["hello"]))]
+    #[case(&make_table(&["-1"]))]
+    #[case(&format!("{}\n440", make_table(&[])))]
+    fn parse_fails(#[case] input: &str) {
+        assert!(input.parse::<FrequencyTable>().is_err());
+    }
+}
@@ -25,7 +25,7 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
-pub(crate) fn read_to_string_lossy<P: AsRef<Path>>(path: P) -> Result<String> {
+pub fn read_to_string_lossy<P: AsRef<Path>>(path: P) -> Result<String> {
     let mut file = File::open(path)?;
     let mut buffer = vec![];
     file.read_to_end(&mut buffer)?;
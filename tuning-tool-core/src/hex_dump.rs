@@ -20,11 +20,11 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::fmt::Write;
 use std::ops::Rem;
 
-pub(crate) fn to_hex_dump(bytes: &[u8], columns: Option<usize>) -> Result<String> {
+pub fn to_hex_dump(bytes: &[u8], columns: Option<usize>) -> Result<String> {
     let columns = columns.unwrap_or(32);
     let bytes_len = bytes.len();
     let mut s = String::new();
@@ -41,10 +41,7 @@ pub(crate) fn to_hex_dump(bytes: &[u8], columns: Option<usize>) -> Result<String
     Ok(s)
 }
 
-#[cfg(test)]
-pub(crate) fn from_hex_dump(s: &str) -> Result<Vec<u8>> {
-    use anyhow::anyhow;
-
+pub fn from_hex_dump(s: &str) -> Result<Vec<u8>> {
     s.split_whitespace()
         .map(|t| u8::from_str_radix(t, 16).map_err(|e| anyhow!(e)))
         .collect::<Result<Vec<_>>>()
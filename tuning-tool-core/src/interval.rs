@@ -36,14 +36,27 @@ enum Inner {
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Interval(Inner);
+pub struct Interval(Inner);
 
 impl Interval {
-    pub(crate) fn unison() -> Self {
+    pub fn unison() -> Self {
         Self(Inner::Ratio(BigRational::one()))
     }
 
-    pub(crate) fn as_ratio_expr(&self) -> Expression {
+    /// Interval in cents, kept to five decimal places so that it is always
+    /// written with a decimal point as Scala requires
+    pub fn from_cents(cents: f64) -> Self {
+        Self(Inner::Cents(Decimal::new(
+            (cents * 100_000f64).round() as i64,
+            5,
+        )))
+    }
+
+    pub fn from_ratio(numer: i64, denom: i64) -> Self {
+        Self(Inner::Ratio(BigRational::new(numer.into(), denom.into())))
+    }
+
+    pub fn as_ratio_expr(&self) -> Expression {
         match &self.0 {
             Inner::Cents(value) => Expression::new_z(2).pow(
                 Expression::try_from(*value).expect("Must be convertible")
@@ -53,7 +66,7 @@ impl Interval {
         }
     }
 
-    pub(crate) fn as_ratio(&self) -> Ratio {
+    pub fn as_ratio(&self) -> Ratio {
         Ratio(match &self.0 {
             Inner::Cents(value) => 2f64.powf(value.to_f64().expect("Must be f64") / 1200f64),
             Inner::Ratio(value) => value.to_f64().expect("Must be f64"),
@@ -120,6 +133,13 @@ mod tests {
         assert_eq!(2f64, interval.as_ratio_expr().as_f64());
         assert_eq!("2/1", interval.to_string());
 
+        assert_eq!("100.00000", Interval::from_cents(100f64).to_string());
+        assert_eq!(
+            "54.54545",
+            Interval::from_cents(1200f64 / 22f64).to_string()
+        );
+        assert_eq!("3/1", Interval::from_ratio(3, 1).to_string());
+
         Ok(())
     }
 }
@@ -0,0 +1,369 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::diagnostic::{Diagnostic, Parsed, SourceLine};
+use crate::frequency::Frequency;
+#[cfg(feature = "fs")]
+use crate::fs::read_to_string_lossy;
+use crate::key_mapping::KeyMapping;
+use crate::key_mappings::KeyMappings;
+use crate::keyboard_mapping::KeyboardMapping;
+use crate::reference::Reference;
+use crate::types::KeyNumber;
+use anyhow::Error;
+#[cfg(feature = "fs")]
+use anyhow::{Context, Result};
+use log::trace;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+/// Scala keyboard mapping (`.kbm`) file
+#[derive(Debug)]
+pub struct KbmFile {
+    equave_degree: usize,
+    keyboard_mapping: KeyboardMapping,
+}
+
+impl KbmFile {
+    /// Reads and parses, ignoring any warnings
+    #[cfg(feature = "fs")]
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Self::read_parsed(path)?
+            .into_result()
+            .with_context(|| format!("Failed to parse {path}", path = path.display()))
+    }
+
+    /// Reads and parses tolerantly, reporting warnings as well as errors
+    /// with their line and column
+    #[cfg(feature = "fs")]
+    pub fn read_parsed<P: AsRef<Path>>(path: P) -> Result<Parsed<Self>> {
+        trace!("Reading .kbm file {path}", path = path.as_ref().display());
+        Ok(Self::parse(&read_to_string_lossy(path)?))
+    }
+
+    /// Parses tolerantly, reporting warnings as well as errors with their
+    /// line and column
+    pub fn parse(s: &str) -> Parsed<Self> {
+        let mut diagnostics = Vec::new();
+        let value = parse_lines(s, &mut diagnostics);
+        Parsed::new(value, diagnostics)
+    }
+
+    pub const fn new(keyboard_mapping: KeyboardMapping, equave_degree: usize) -> Self {
+        Self {
+            equave_degree,
+            keyboard_mapping,
+        }
+    }
+
+    pub const fn keyboard_mapping(&self) -> &KeyboardMapping {
+        &self.keyboard_mapping
+    }
+
+    pub const fn equave_degree(&self) -> usize {
+        self.equave_degree
+    }
+}
+
+impl Display for KbmFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let keyboard_mapping = &self.keyboard_mapping;
+        let reference = keyboard_mapping.reference();
+        let key_mappings: &[KeyMapping] = match keyboard_mapping.key_mappings() {
+            KeyMappings::Linear => &[],
+            KeyMappings::Custom(key_mappings) => key_mappings,
+        };
+
+        writeln!(f, "! Size of map:")?;
+        writeln!(f, "{}", key_mappings.len())?;
+        writeln!(f, "! First MIDI note number to retune:")?;
+        writeln!(f, "{}", keyboard_mapping.start_key())?;
+        writeln!(f, "! Last MIDI note number to retune:")?;
+        writeln!(f, "{}", keyboard_mapping.end_key())?;
+        writeln!(
+            f,
+            "! Middle note where the first entry in the mapping is mapped to:"
+        )?;
+        writeln!(f, "{}", reference.zero_key())?;
+        writeln!(f, "! Reference note for which frequency is given:")?;
+        writeln!(f, "{}", reference.reference_key())?;
+        writeln!(
+            f,
+            "! Frequency to tune the above note to (floating point e.g. 440.0):"
+        )?;
+        writeln!(f, "{:.6}", reference.reference_frequency().0)?;
+        writeln!(f, "! Scale degree to consider as formal octave:")?;
+        writeln!(f, "{}", self.equave_degree)?;
+        writeln!(f, "! Mapping.")?;
+        for key_mapping in key_mappings {
+            match key_mapping {
+                KeyMapping::Degree(degree) => writeln!(f, "{degree}")?,
+                KeyMapping::Unmapped => writeln!(f, "x")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KbmFile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        Self::parse(s).into_result()
+    }
+}
+
+// Parses the content of a .kbm file, collecting every problem found:
+// returns None after the first problem that prevents building the mapping
+fn parse_lines(s: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<KbmFile> {
+    trace!("Content is [[{s}]]");
+
+    let lines = SourceLine::split(s);
+    let end_line = SourceLine::end_line(&lines);
+    let mut lines = lines.into_iter().filter(|line| {
+        let s = line.trimmed();
+        !s.is_empty() && !s.starts_with("!")
+    });
+
+    macro_rules! read {
+        ($ty: ty, $what: expr) => {{
+            let Some(line) = lines.next() else {
+                diagnostics.push(Diagnostic::error(
+                    end_line,
+                    1,
+                    format!("Unexpected end of file: expected {what}", what = $what),
+                ));
+                return None;
+            };
+            let value = line.parse_value::<$ty>($what, false, diagnostics)?;
+            (line, value)
+        }};
+    }
+
+    let (size_line, size) = read!(usize, "map size");
+    if size > 127 {
+        let (token, _) = size_line.split_value();
+        diagnostics.push(size_line.error(token, format!("Map size {size} is larger than 127")));
+        return None;
+    }
+
+    // Start of MIDI key range
+    let (start_key_line, start_key) = read!(KeyNumber, "first MIDI key number (0-127)");
+    trace!("Parsed start key {start_key}");
+
+    // End of MIDI key range
+    let (_, end_key) = read!(KeyNumber, "last MIDI key number (0-127)");
+    trace!("Parsed end key {end_key}");
+
+    // Middle key where 1/1 note is mapped
+    let (_, zero_key) = read!(KeyNumber, "middle MIDI key number (0-127)");
+    trace!("Parsed zero key {zero_key}");
+
+    // Key where reference frequency goes
+    let (_, reference_key) = read!(KeyNumber, "reference MIDI key number (0-127)");
+    trace!("Parsed reference key {reference_key}");
+
+    // Reference frequency (e.g. 440 Hz)
+    let (frequency_line, reference_frequency) = read!(f64, "reference frequency");
+    if reference_frequency <= 0f64 || !reference_frequency.is_finite() {
+        let (token, _) = frequency_line.split_value();
+        diagnostics.push(frequency_line.error(token, "Reference frequency must be positive"));
+        return None;
+    }
+    let reference_frequency = Frequency(reference_frequency);
+    trace!("Parsed reference frequency {reference_frequency}");
+
+    // Scale interval between adjacent repeating patterns
+    let (_, equave_degree) = read!(usize, "formal octave scale degree");
+    trace!("Parsed equave degree {equave_degree}");
+
+    let mut is_linear = true;
+    let mut failed = false;
+    let mut key_mappings = Vec::with_capacity(size);
+    for line in lines.by_ref().take(size) {
+        let (token, rest) = line.split_value();
+        let key_mapping = if token == "x" || token == "X" {
+            if !rest.is_empty() {
+                diagnostics.push(line.warning(rest, "Ignoring text after mapping entry"));
+            }
+            is_linear = false;
+            KeyMapping::Unmapped
+        } else {
+            let Some(degree) = line.parse_value("scale degree or \"x\"", false, diagnostics) else {
+                failed = true;
+                continue;
+            };
+            if is_linear && degree != key_mappings.len() {
+                is_linear = false;
+            }
+            KeyMapping::Degree(degree)
+        };
+        trace!("Parsed key mapping {key_mapping}");
+        key_mappings.push(key_mapping);
+    }
+
+    if failed {
+        return None;
+    }
+
+    // Missing entries are unmapped
+    if key_mappings.len() < size {
+        diagnostics.push(size_line.warning(
+            size_line.split_value().0,
+            format!(
+                "Map size is {size} but found {count} mapping entries: treating the rest as unmapped",
+                count = key_mappings.len()
+            ),
+        ));
+        is_linear = false;
+        key_mappings.resize(size, KeyMapping::Unmapped);
+    }
+
+    let extra_lines = lines.collect::<Vec<_>>();
+    if let Some(first) = extra_lines.first() {
+        diagnostics.push(first.warning(
+            first.trimmed(),
+            format!(
+                "Ignoring {count} line(s) after the {size} mapping entries declared on line {line}",
+                count = extra_lines.len(),
+                line = size_line.number
+            ),
+        ));
+    }
+
+    let key_mappings = if is_linear {
+        KeyMappings::Linear
+    } else {
+        KeyMappings::Custom(key_mappings)
+    };
+
+    let reference = Reference::new(zero_key, reference_key, reference_frequency);
+    let keyboard_mapping = match KeyboardMapping::new(start_key, end_key, &reference, key_mappings)
+    {
+        Ok(keyboard_mapping) => keyboard_mapping,
+        Err(e) => {
+            diagnostics.push(start_key_line.error(start_key_line.split_value().0, e.to_string()));
+            return None;
+        }
+    };
+
+    Some(KbmFile {
+        equave_degree,
+        keyboard_mapping,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::Diagnostic;
+    use crate::kbm_file::KbmFile;
+    use crate::key_mapping::KeyMapping;
+    use crate::key_mappings::KeyMappings;
+    use crate::resources::include_resource_str;
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let kbm_file = include_resource_str!("22edo2.kbm").parse::<KbmFile>()?;
+        let other = kbm_file.to_string().parse::<KbmFile>()?;
+        assert_eq!(22, other.equave_degree());
+
+        let (expected, actual) = (kbm_file.keyboard_mapping(), other.keyboard_mapping());
+        assert_eq!(expected.start_key(), actual.start_key());
+        assert_eq!(expected.end_key(), actual.end_key());
+        assert_eq!(expected.reference(), actual.reference());
+        let (KeyMappings::Custom(expected), KeyMappings::Custom(actual)) =
+            (expected.key_mappings(), actual.key_mappings())
+        else {
+            panic!("Expected custom key mappings")
+        };
+        assert_eq!(
+            expected.iter().map(|m| m.to_string()).collect::<Vec<_>>(),
+            actual.iter().map(|m| m.to_string()).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tolerant() -> Result<()> {
+        let parsed = KbmFile::parse(
+            "! test.kbm\r\n4\r\n0\r\n127\r\n60\r\n69 ! A\r\n440.0\r\n3\r\n0\r\nx\r\n2,\r\n",
+        );
+        assert_eq!(
+            vec![
+                Diagnostic::warning(
+                    2,
+                    1,
+                    "Map size is 4 but found 3 mapping entries: treating the rest as unmapped"
+                ),
+                Diagnostic::warning(
+                    6,
+                    4,
+                    "Ignoring text after reference MIDI key number (0-127)"
+                ),
+                Diagnostic::warning(11, 2, "Ignoring text after scale degree or \"x\""),
+            ],
+            parsed.diagnostics
+        );
+
+        let kbm_file = parsed.into_result()?;
+        let KeyMappings::Custom(key_mappings) = kbm_file.keyboard_mapping().key_mappings() else {
+            panic!("Expected custom key mappings")
+        };
+        assert_eq!(4, key_mappings.len());
+        assert!(matches!(key_mappings[3], KeyMapping::Unmapped));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("Line 1, column 1: Unexpected end of file: expected map size", "")]
+    #[case("Line 1, column 1: Map size 200 is larger than 127", "200\n")]
+    #[case(
+        "Line 4, column 1: Unexpected end of file: expected middle MIDI key number (0-127)",
+        "0\n0\n127\n"
+    )]
+    #[case(
+        "Line 3, column 1: Expected last MIDI key number (0-127), found \"128\"",
+        "0\n0\n128\n"
+    )]
+    #[case(
+        "Line 7, column 1: Reference frequency must be positive",
+        "0\n0\n127\n60\n69\n\n-440.0\n"
+    )]
+    #[case(
+        "Line 9, column 1: Expected scale degree or \"x\", found \"y\"",
+        "1\n0\n127\n60\n69\n440.0\n1\n! Mapping\ny\n"
+    )]
+    fn errors(#[case] expected: &str, #[case] input: &str) {
+        let parsed = KbmFile::parse(input);
+        assert!(parsed.has_errors());
+        assert_eq!(
+            expected,
+            parsed.into_result().expect_err("Must fail").to_string()
+        );
+    }
+}
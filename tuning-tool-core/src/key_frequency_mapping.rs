@@ -35,30 +35,35 @@ use num::pow::Pow;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::iter::once;
 
-pub(crate) fn compute_direct(
+/// Computes floating-point frequencies for the MIDI keys mapped by the
+/// keyboard mapping, in key order, omitting unmapped keys
+pub fn compute_direct(
     scale: &Scale,
     keyboard_mapping: &KeyboardMapping,
 ) -> Result<Vec<KeyFrequencyMapping<Direct>>> {
     KeyFrequencyMapping::<Direct>::compute(scale, keyboard_mapping)
 }
 
-pub(crate) fn compute_symbolic(
+/// Computes exact symbolic frequencies for the MIDI keys mapped by the
+/// keyboard mapping, in key order, omitting unmapped keys
+pub fn compute_symbolic(
     scale: &Scale,
     keyboard_mapping: &KeyboardMapping,
 ) -> Result<Vec<KeyFrequencyMapping<Symbolic>>> {
     KeyFrequencyMapping::<Symbolic>::compute(scale, keyboard_mapping)
 }
 
+/// Frequency and scale degree computed for a single MIDI key
 #[derive(Debug)]
-pub(crate) struct KeyFrequencyMapping<E: EvaluationStrategy> {
-    pub(crate) key: KeyNumber,
-    pub(crate) frequency: E::Frequency,
-    pub(crate) degree: usize,
-    pub(crate) interval: Interval,
+pub struct KeyFrequencyMapping<E: EvaluationStrategy> {
+    pub key: KeyNumber,
+    pub frequency: E::Frequency,
+    pub degree: usize,
+    pub interval: Interval,
 }
 
 impl<E: EvaluationStrategy> KeyFrequencyMapping<E> {
-    pub(crate) fn compute(scale: &Scale, keyboard_mapping: &KeyboardMapping) -> Result<Vec<Self>> {
+    pub fn compute(scale: &Scale, keyboard_mapping: &KeyboardMapping) -> Result<Vec<Self>> {
         let start = keyboard_mapping.start_key().to_u8() as usize;
         let end = keyboard_mapping.end_key().to_u8() as usize;
         Ok(Self::compute_all(
@@ -181,7 +186,7 @@ impl<E: EvaluationStrategy> KeyFrequencyMapping<E> {
             .collect::<Result<Vec<_>>>()
     }
 
-    pub(crate) fn frequency(&self) -> &E::Frequency {
+    pub fn frequency(&self) -> &E::Frequency {
         &self.frequency
     }
 }
@@ -23,7 +23,7 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
 #[derive(Clone, Debug)]
-pub(crate) enum KeyMapping {
+pub enum KeyMapping {
     Degree(usize),
     Unmapped,
 }
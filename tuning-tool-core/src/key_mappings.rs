@@ -23,7 +23,7 @@
 use crate::key_mapping::KeyMapping;
 
 #[derive(Clone, Debug)]
-pub(crate) enum KeyMappings {
+pub enum KeyMappings {
     Linear,
     Custom(Vec<KeyMapping>),
 }
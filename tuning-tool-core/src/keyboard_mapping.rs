@@ -25,8 +25,9 @@ use crate::reference::Reference;
 use crate::types::KeyNumber;
 use anyhow::{bail, Result};
 
+/// Assignment of MIDI keys to scale degrees relative to a reference key and frequency
 #[derive(Clone, Debug)]
-pub(crate) struct KeyboardMapping {
+pub struct KeyboardMapping {
     start_key: KeyNumber,
     end_key: KeyNumber,
     reference: Reference,
@@ -34,7 +35,7 @@ pub(crate) struct KeyboardMapping {
 }
 
 impl KeyboardMapping {
-    pub(crate) fn new(
+    pub fn new(
         start_key: KeyNumber,
         end_key: KeyNumber,
         reference: &Reference,
@@ -52,27 +53,27 @@ impl KeyboardMapping {
         })
     }
 
-    pub(crate) fn new_full(reference: &Reference, key_mappings: KeyMappings) -> Result<Self> {
+    pub fn new_full(reference: &Reference, key_mappings: KeyMappings) -> Result<Self> {
         Self::new(KeyNumber::ZERO, KeyNumber::MAX, reference, key_mappings)
     }
 
-    pub(crate) fn new_full_linear(reference: &Reference) -> Result<Self> {
+    pub fn new_full_linear(reference: &Reference) -> Result<Self> {
         Self::new_full(reference, KeyMappings::Linear)
     }
 
-    pub(crate) const fn start_key(&self) -> &KeyNumber {
+    pub const fn start_key(&self) -> &KeyNumber {
         &self.start_key
     }
 
-    pub(crate) const fn end_key(&self) -> &KeyNumber {
+    pub const fn end_key(&self) -> &KeyNumber {
         &self.end_key
     }
 
-    pub(crate) const fn reference(&self) -> &Reference {
+    pub const fn reference(&self) -> &Reference {
         &self.reference
     }
 
-    pub(crate) const fn key_mappings(&self) -> &KeyMappings {
+    pub const fn key_mappings(&self) -> &KeyMappings {
         &self.key_mappings
     }
 }
@@ -0,0 +1,89 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+//! Scale and keyboard mapping parsing, tuning table computation and MIDI
+//! Tuning Specification (MTS) encoding and decoding.
+//!
+//! ```
+//! use tuning_tool_core::key_frequency_mapping::compute_direct;
+//! use tuning_tool_core::keyboard_mapping::KeyboardMapping;
+//! use tuning_tool_core::reference::Reference;
+//! use tuning_tool_core::scl_file::SclFile;
+//!
+//! let scl_file = "12-EDO\n12\n100.0\n200.0\n300.0\n400.0\n500.0\n600.0\n700.0\n800.0\n900.0\n1000.0\n1100.0\n2/1\n"
+//!     .parse::<SclFile>()?;
+//! let keyboard_mapping = KeyboardMapping::new_full_linear(&Reference::default())?;
+//! let mappings = compute_direct(scl_file.scale(), &keyboard_mapping)?;
+//! assert_eq!(128, mappings.len());
+//! assert_eq!(440f64, mappings[69].frequency.0);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+#![allow(clippy::wrong_self_convention)]
+
+mod approx_eq;
+pub mod ascl_file;
+pub mod bulk_dump_reply;
+mod checksum_calculator;
+mod consts;
+pub mod decoded_message;
+pub mod diagnostic;
+pub mod dump_import;
+pub mod evaluate;
+pub mod evaluation_strategy;
+pub mod frequency;
+pub mod frequency_list;
+pub mod frequency_table;
+#[cfg(feature = "fs")]
+mod fs;
+mod hex_dump;
+pub mod interval;
+pub mod kbm_file;
+pub mod key_frequency_mapping;
+pub mod key_mapping;
+pub mod key_mappings;
+pub mod keyboard_mapping;
+mod midi_message_builder;
+pub mod midi_note;
+pub mod mts_entry;
+pub mod mts_messages;
+pub mod mts_verification;
+mod note_change;
+pub mod note_change_entry;
+pub mod note_number;
+mod num;
+pub mod out_of_range;
+pub mod pitch_detection;
+pub mod preset_name;
+pub mod ratio;
+mod read;
+pub mod reference;
+mod resources;
+pub mod scale;
+pub mod scl_file;
+pub mod semitones;
+pub mod surge_patch;
+pub mod synth;
+pub mod syx;
+pub mod tun_file;
+pub mod tuning_format;
+pub mod types;
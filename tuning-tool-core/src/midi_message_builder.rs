@@ -22,36 +22,45 @@
 
 use crate::types::MidiValue;
 use anyhow::{bail, Result};
+use midly::live::{LiveEvent, SystemCommon};
+use midly::num::u7;
 use tuning_tool_lib::u7::U7;
 
-pub(crate) struct MidiMessageBuilder {
+pub struct MidiMessageBuilder {
     required_len: usize,
     values: Vec<MidiValue>,
 }
 
 impl MidiMessageBuilder {
-    pub(crate) fn with_required_len(required_len: usize) -> Self {
+    pub fn with_required_len(required_len: usize) -> Self {
         Self {
             required_len,
             values: Vec::with_capacity(required_len),
         }
     }
 
-    pub(crate) fn push<U: U7>(&mut self, value: U) -> U {
+    pub fn push<U: U7>(&mut self, value: U) -> U {
         self.values.push(MidiValue::from_u8_lossy(value.to_u8()));
         value
     }
 
-    #[cfg(test)]
-    pub(crate) fn extend_from_slice<U: U7>(&mut self, other: &[U]) {
+    pub fn extend_from_slice<U: U7>(&mut self, other: &[U]) {
         self.values
             .extend(other.iter().map(|x| MidiValue::from_u8_lossy(x.to_u8())))
     }
 
-    pub(crate) fn finalize(self) -> Result<Vec<MidiValue>> {
+    pub fn finalize(self) -> Result<Vec<MidiValue>> {
         if self.values.len() != self.required_len {
             bail!("MIDI value vector was not expected length")
         }
         Ok(self.values)
     }
 }
+
+pub fn to_sysex(values: &[MidiValue]) -> Result<Vec<u8>> {
+    let u7_slice = u7::slice_from_int(MidiValue::to_u8_slice(values));
+    let event = LiveEvent::Common(SystemCommon::SysEx(u7_slice));
+    let mut message = Vec::new();
+    event.write_std(&mut message)?;
+    Ok(message)
+}
@@ -30,7 +30,7 @@ use std::str::FromStr;
 include!(concat!(env!("OUT_DIR"), "/midi_note_generated.rs"));
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub(crate) struct MidiNote {
+pub struct MidiNote {
     note_number: NoteNumber,
     name: &'static str,
     is_natural: bool,
@@ -39,25 +39,25 @@ pub(crate) struct MidiNote {
 
 impl MidiNote {
     #[allow(unused)]
-    pub(crate) const ALL: [MidiNote; 128] = ALL;
+    pub const ALL: [MidiNote; 128] = ALL;
 
     #[allow(unused)]
-    pub(crate) const fn note_number(&self) -> NoteNumber {
+    pub const fn note_number(&self) -> NoteNumber {
         self.note_number
     }
 
     #[allow(unused)]
-    pub(crate) const fn name(&self) -> &str {
+    pub const fn name(&self) -> &str {
         self.name
     }
 
     #[allow(unused)]
-    pub(crate) const fn is_natural(&self) -> bool {
+    pub const fn is_natural(&self) -> bool {
         self.is_natural
     }
 
     #[allow(unused)]
-    pub(crate) const fn frequency(&self) -> Frequency {
+    pub const fn frequency(&self) -> Frequency {
         self.frequency
     }
 
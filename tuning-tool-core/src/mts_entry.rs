@@ -26,16 +26,33 @@ use crate::num::round_default_scale;
 use crate::semitones::Semitones;
 use crate::types::{Lsb, Msb};
 
+/// Three-byte MTS frequency data: semitone and 14-bit fraction
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub(crate) struct MtsEntry {
-    pub(crate) note_number: NoteNumber,
-    pub(crate) msb: Msb,
-    pub(crate) lsb: Lsb,
+pub struct MtsEntry {
+    pub note_number: NoteNumber,
+    pub msb: Msb,
+    pub lsb: Lsb,
 }
 
 impl MtsEntry {
+    /// Reserved value telling the receiver to leave the key's tuning unchanged
+    pub const NO_CHANGE: Self = Self {
+        note_number: NoteNumber::MAX,
+        msb: Msb::MAX,
+        lsb: Lsb::MAX,
+    };
+
+    pub fn is_no_change(&self) -> bool {
+        *self == Self::NO_CHANGE
+    }
+
+    /// Entry as received from a device: `None` for the "no change" value
+    pub fn to_option(self) -> Option<Self> {
+        (!self.is_no_change()).then_some(self)
+    }
+
     // c.f. mtsBytesToMts
-    pub(crate) fn to_semitones(&self) -> Semitones {
+    pub fn to_semitones(&self) -> Semitones {
         fn make_14_bit(msb: Msb, lsb: Lsb) -> u16 {
             ((msb.to_u8() as u16) << 7) + lsb.to_u8() as u16
         }
@@ -51,7 +68,7 @@ impl MtsEntry {
     }
 
     // c.f. mtsBytesToFrequency
-    pub(crate) fn to_frequency(&self) -> Frequency {
+    pub fn to_frequency(&self) -> Frequency {
         let mts = self.to_semitones();
         let frequency = mts.to_frequency();
         Frequency(round_default_scale(frequency.0))
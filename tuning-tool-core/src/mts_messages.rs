@@ -0,0 +1,333 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::bulk_dump_reply::{BulkDumpReply, MtsEntries};
+use crate::consts::{BULK_DUMP_REQUEST, MIDI_TUNING, UNIVERSAL_NON_REAL_TIME};
+use crate::frequency::Frequency;
+use crate::frequency_table::FrequencyTable;
+use crate::key_frequency_mapping::compute_direct;
+use crate::keyboard_mapping::KeyboardMapping;
+use crate::midi_message_builder::{to_sysex, MidiMessageBuilder};
+use crate::note_change::NoteChange;
+use crate::note_change_entry::NoteChangeEntry;
+use crate::out_of_range::OutOfRangePolicy;
+use crate::preset_name::PresetName;
+use crate::scale::Scale;
+use crate::types::{ChunkSize, DeviceId, KeyNumber, MidiValue, Preset};
+use anyhow::{bail, Result};
+
+/// Computes the frequency and MTS entry of every key mapped by the keyboard
+/// mapping, encoding out-of-range frequencies according to the policy
+pub fn make_note_change_entries(
+    scale: &Scale,
+    keyboard_mapping: &KeyboardMapping,
+    policy: OutOfRangePolicy,
+) -> Result<Vec<(Frequency, NoteChangeEntry)>> {
+    compute_direct(scale, keyboard_mapping)?
+        .iter()
+        .map(|mapping| make_note_change_entry(mapping.key, Frequency(mapping.frequency.0), policy))
+        .collect::<Result<Vec<_>>>()
+}
+
+/// Computes the MTS entry of every key the frequency table retunes,
+/// encoding out-of-range frequencies according to the policy
+pub fn make_frequency_table_entries(
+    frequency_table: &FrequencyTable,
+    policy: OutOfRangePolicy,
+) -> Result<Vec<(Frequency, NoteChangeEntry)>> {
+    frequency_table
+        .frequencies()
+        .iter()
+        .enumerate()
+        .filter_map(|(i, f)| f.map(|f| (i, f)))
+        .map(|(i, f)| make_note_change_entry(KeyNumber::try_from(i as u8)?, f, policy))
+        .collect::<Result<Vec<_>>>()
+}
+
+fn make_note_change_entry(
+    key_number: KeyNumber,
+    f: Frequency,
+    policy: OutOfRangePolicy,
+) -> Result<(Frequency, NoteChangeEntry)> {
+    let (mts, _) = policy
+        .to_mts_entry(f)
+        .map_err(|e| e.context(format!("Key {key_number}")))?;
+    Ok((f, NoteChangeEntry { key_number, mts }))
+}
+
+/// Encodes entries as single note tuning change SysEx messages of at most
+/// `chunk_size` entries each
+pub fn make_note_change_messages(
+    device_id: DeviceId,
+    preset: Preset,
+    entries: &[NoteChangeEntry],
+    chunk_size: ChunkSize,
+) -> Result<Vec<Vec<u8>>> {
+    entries
+        .chunks(chunk_len(chunk_size)?)
+        .map(|chunk| to_sysex(&NoteChange::new(device_id, preset, chunk)?.to_vec()?))
+        .collect()
+}
+
+/// Encodes entries as single note tuning change with bank select SysEx
+/// messages of at most `chunk_size` entries each
+pub fn make_note_change_with_bank_messages(
+    device_id: DeviceId,
+    bank: MidiValue,
+    preset: Preset,
+    entries: &[NoteChangeEntry],
+    chunk_size: ChunkSize,
+) -> Result<Vec<Vec<u8>>> {
+    entries
+        .chunks(chunk_len(chunk_size)?)
+        .map(|chunk| to_sysex(&NoteChange::with_bank(device_id, bank, preset, chunk)?.to_vec()?))
+        .collect()
+}
+
+fn chunk_len(chunk_size: ChunkSize) -> Result<usize> {
+    if chunk_size == ChunkSize::ZERO {
+        bail!("Chunk size must be at least 1")
+    }
+    Ok(chunk_size.to_u8() as usize)
+}
+
+/// Encodes entries as a bulk tuning dump reply SysEx message: bulk dumps
+/// always carry all 128 keys, so keys without an entry, such as keys the
+/// keyboard mapping leaves unmapped, are sent as "no change"
+pub fn make_bulk_dump_message(
+    device_id: DeviceId,
+    preset: Preset,
+    name: PresetName,
+    entries: &[NoteChangeEntry],
+) -> Result<Vec<u8>> {
+    let mut mts_entries: MtsEntries = [None; 128];
+    for e in entries {
+        mts_entries[e.key_number.to_u8() as usize] = e.mts.to_option();
+    }
+    BulkDumpReply::new(device_id, preset, name, mts_entries)?.to_bytes_with_start_and_end()
+}
+
+/// Encodes a bulk tuning dump request SysEx message asking the device for
+/// the tuning of the preset
+pub fn make_bulk_dump_request_message(device_id: DeviceId, preset: Preset) -> Result<Vec<u8>> {
+    let mut values = MidiMessageBuilder::with_required_len(5);
+    values.push(UNIVERSAL_NON_REAL_TIME);
+    values.push(device_id);
+    values.push(MIDI_TUNING);
+    values.push(BULK_DUMP_REQUEST);
+    values.push(preset);
+    to_sysex(&values.finalize()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bulk_dump_reply::BulkDumpReply;
+    use crate::decoded_message::DecodedMessage;
+    use crate::frequency_table::FrequencyTable;
+    use crate::kbm_file::KbmFile;
+    use crate::keyboard_mapping::KeyboardMapping;
+    use crate::mts_entry::MtsEntry;
+    use crate::mts_messages::{
+        make_bulk_dump_message, make_bulk_dump_request_message, make_frequency_table_entries,
+        make_note_change_entries, make_note_change_messages, make_note_change_with_bank_messages,
+    };
+    use crate::out_of_range::OutOfRangePolicy;
+    use crate::reference::Reference;
+    use crate::resources::include_resource_str;
+    use crate::scl_file::SclFile;
+    use crate::types::{ChunkSize, DeviceId, MidiValue, Preset};
+    use anyhow::Result;
+    use std::io::Read;
+
+    #[test]
+    fn note_change_entries_use_mapped_keys() -> Result<()> {
+        // Mapping starts at key 60 and leaves every third key unmapped, so
+        // entries must take their key numbers from the mapping rather than
+        // from their position
+        let scl_file = include_resource_str!("22edo2.scl").parse::<SclFile>()?;
+        let kbm_file =
+            "12\n60\n71\n60\n60\n261.625565\n22\n0\n2\nx\n6\n7\nx\n11\n13\nx\n17\n18\nx\n"
+                .parse::<KbmFile>()?;
+        let entries = make_note_change_entries(
+            scl_file.scale(),
+            kbm_file.keyboard_mapping(),
+            OutOfRangePolicy::Clamp,
+        )?;
+        assert_eq!(
+            vec![60, 61, 63, 64, 66, 67, 69, 70],
+            entries
+                .iter()
+                .map(|(_, e)| e.key_number.to_u8())
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn note_change_messages() -> Result<()> {
+        let scl_file = include_resource_str!("carlos_super.scl").parse::<SclFile>()?;
+        let keyboard_mapping = KeyboardMapping::new_full_linear(&Reference::default())?;
+        let entries =
+            make_note_change_entries(scl_file.scale(), &keyboard_mapping, OutOfRangePolicy::Clamp)?
+                .into_iter()
+                .map(|x| x.1)
+                .collect::<Vec<_>>();
+        assert_eq!(128, entries.len());
+
+        let messages =
+            make_note_change_messages(DeviceId::ZERO, Preset::ZERO, &entries, ChunkSize::ONE)?;
+        assert_eq!(128, messages.len());
+        assert_eq!(
+            &[0xf0, 0x7f, 0x00, 0x08, 0x02, 0x00, 0x01, 0x00],
+            &messages[0][..8]
+        );
+        assert!(
+            make_note_change_messages(DeviceId::ZERO, Preset::ZERO, &entries, ChunkSize::ZERO)
+                .is_err()
+        );
+        assert!(make_note_change_with_bank_messages(
+            DeviceId::ZERO,
+            MidiValue::ZERO,
+            Preset::ZERO,
+            &entries,
+            ChunkSize::ZERO
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn frequency_table_entries() -> Result<()> {
+        let s = std::iter::once("440")
+            .chain(std::iter::repeat_n("x", 126))
+            .chain(std::iter::once("6900c"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let entries =
+            make_frequency_table_entries(&s.parse::<FrequencyTable>()?, OutOfRangePolicy::Clamp)?;
+        assert_eq!(2, entries.len());
+        assert_eq!(0, entries[0].1.key_number.to_u8());
+        assert_eq!(127, entries[1].1.key_number.to_u8());
+        assert_eq!(entries[0].1.mts, entries[1].1.mts);
+        Ok(())
+    }
+
+    #[test]
+    fn frequency_table_entries_out_of_range() -> Result<()> {
+        let s = std::iter::once("4")
+            .chain(std::iter::repeat_n("x", 126))
+            .chain(std::iter::once("20000"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let frequency_table = s.parse::<FrequencyTable>()?;
+
+        let entries = make_frequency_table_entries(&frequency_table, OutOfRangePolicy::Unchanged)?;
+        assert_eq!(2, entries.len());
+        assert!(entries.iter().all(|e| e.1.mts == MtsEntry::NO_CHANGE));
+
+        let entries = make_frequency_table_entries(&frequency_table, OutOfRangePolicy::Fold)?;
+        assert_eq!(11, entries[0].1.mts.note_number.to_u8());
+        assert_eq!(123, entries[1].1.mts.note_number.to_u8());
+
+        let e = make_frequency_table_entries(&frequency_table, OutOfRangePolicy::Fail)
+            .expect_err("Must fail");
+        assert_eq!("Key 0", e.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_dump_message_round_trip() -> Result<()> {
+        let scl_file = include_resource_str!("carlos_super.scl").parse::<SclFile>()?;
+        let keyboard_mapping = KeyboardMapping::new_full_linear(&Reference::default())?;
+        let entries =
+            make_note_change_entries(scl_file.scale(), &keyboard_mapping, OutOfRangePolicy::Clamp)?
+                .into_iter()
+                .map(|x| x.1)
+                .collect::<Vec<_>>();
+
+        let bytes = make_bulk_dump_message(
+            DeviceId::ZERO,
+            Preset::ZERO,
+            "carlos_super".parse()?,
+            &entries,
+        )?;
+        let bulk_dump_reply = BulkDumpReply::from_bytes(bytes.bytes())?;
+        assert_eq!(
+            "carlos_super",
+            bulk_dump_reply.name().to_string().trim_end_matches('\0')
+        );
+        for e in &entries {
+            assert_eq!(
+                Some(e.mts),
+                bulk_dump_reply.entries()[e.key_number.to_u8() as usize]
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_dump_message_unmapped_keys() -> Result<()> {
+        let scl_file = include_resource_str!("carlos_super.scl").parse::<SclFile>()?;
+        let kbm_file = "12\n0\n127\n60\n69\n440.0\n12\n0\nx\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n"
+            .parse::<KbmFile>()?;
+        let entries = make_note_change_entries(
+            scl_file.scale(),
+            kbm_file.keyboard_mapping(),
+            OutOfRangePolicy::Clamp,
+        )?
+        .into_iter()
+        .map(|x| x.1)
+        .collect::<Vec<_>>();
+        assert!(entries.iter().all(|e| e.key_number.to_u8() % 12 != 1));
+
+        let bytes = make_bulk_dump_message(
+            DeviceId::ZERO,
+            Preset::ZERO,
+            "carlos_super".parse()?,
+            &entries,
+        )?;
+        let bulk_dump_reply = BulkDumpReply::from_bytes(bytes.bytes())?;
+        for (key, e) in bulk_dump_reply.entries().iter().enumerate() {
+            assert_eq!(key % 12 == 1, e.is_none());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_dump_request_message() -> Result<()> {
+        let message =
+            make_bulk_dump_request_message(DeviceId::constant::<1>(), Preset::constant::<8>())?;
+        assert_eq!(vec![0xf0, 0x7e, 0x01, 0x08, 0x00, 0x08, 0xf7], message);
+        let DecodedMessage::DumpRequest {
+            device_id,
+            bank,
+            preset,
+        } = DecodedMessage::decode(&message)?
+        else {
+            panic!("Must be dump request");
+        };
+        assert_eq!(DeviceId::constant::<1>(), device_id);
+        assert!(bank.is_none());
+        assert_eq!(Preset::constant::<8>(), preset);
+        Ok(())
+    }
+}
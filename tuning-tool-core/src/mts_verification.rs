@@ -0,0 +1,157 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::frequency::Frequency;
+use crate::mts_entry::MtsEntry;
+use crate::out_of_range::{OutOfRange, OutOfRangePolicy};
+use crate::types::KeyNumber;
+use anyhow::Result;
+
+/// Frequency of a key before and after conversion to MTS bytes: the MTS
+/// frequency and error are `None` if the key is left unchanged
+#[derive(Clone, Debug)]
+pub struct RoundTrip {
+    pub frequency: Frequency,
+    pub mts_entry: MtsEntry,
+    pub mts_frequency: Option<Frequency>,
+    pub error_cents: Option<f64>,
+    pub out_of_range: Option<OutOfRange>,
+}
+
+/// Round trip of a single MIDI key, or `None` if the key is unmapped
+#[derive(Clone, Debug)]
+pub struct KeyVerification {
+    pub key: KeyNumber,
+    pub round_trip: Option<RoundTrip>,
+}
+
+/// Converts the frequency of each MIDI key to MTS bytes and back, as a
+/// device would see it, measuring the error introduced in cents: the error
+/// of a folded key is measured against its folded frequency
+pub fn verify_round_trip(
+    frequencies: &[Option<Frequency>],
+    policy: OutOfRangePolicy,
+) -> Result<Vec<KeyVerification>> {
+    frequencies
+        .iter()
+        .enumerate()
+        .map(|(i, frequency)| {
+            let key = KeyNumber::try_from(i as u8)?;
+            let round_trip = frequency
+                .map(|frequency| {
+                    let (mts_entry, out_of_range) = policy
+                        .to_mts_entry(frequency)
+                        .map_err(|e| e.context(format!("Key {key}")))?;
+                    let target = match out_of_range {
+                        Some(OutOfRange::Unchanged) => None,
+                        Some(OutOfRange::Folded { octaves }) => {
+                            Some(Frequency(frequency.0 * 2f64.powi(octaves)))
+                        }
+                        _ => Some(frequency),
+                    };
+                    let mts_frequency = target.map(|_| mts_entry.to_frequency());
+                    let error_cents = target.zip(mts_frequency).map(|(target, mts_frequency)| {
+                        1200f64 * (mts_frequency.0 / target.0).log2()
+                    });
+                    anyhow::Ok(RoundTrip {
+                        frequency,
+                        mts_entry,
+                        mts_frequency,
+                        error_cents,
+                        out_of_range,
+                    })
+                })
+                .transpose()?;
+            Ok(KeyVerification { key, round_trip })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frequency::Frequency;
+    use crate::midi_note::MidiNote;
+    use crate::mts_verification::verify_round_trip;
+    use crate::out_of_range::{OutOfRange, OutOfRangePolicy};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(None, 0.004f64, 440f64)]
+    #[case(None, 0.004f64, 261.625565f64)]
+    #[case(None, 0.004f64, 8.175799f64)]
+    #[case(None, 0.004f64, 13289.656616f64)]
+    #[case(Some(OutOfRange::ClampedLow), 2000f64, 4f64)]
+    #[case(Some(OutOfRange::ClampedHigh), 2000f64, 30000f64)]
+    fn round_trip(
+        #[case] expected_out_of_range: Option<OutOfRange>,
+        #[case] max_error_cents: f64,
+        #[case] input: f64,
+    ) -> Result<()> {
+        let result = verify_round_trip(&[Some(Frequency(input))], OutOfRangePolicy::Clamp)?;
+        let round_trip = result[0].round_trip.as_ref().expect("Must be mapped");
+        assert_eq!(expected_out_of_range, round_trip.out_of_range);
+        let error_cents = round_trip.error_cents.expect("Must have error");
+        assert!(error_cents.abs() < max_error_cents);
+        if expected_out_of_range.is_some() {
+            assert!(error_cents.abs() > 100f64);
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(Some(OutOfRange::Folded { octaves: 2 }), OutOfRangePolicy::Fold)]
+    #[case(Some(OutOfRange::Unchanged), OutOfRangePolicy::Unchanged)]
+    fn round_trip_policy(
+        #[case] expected_out_of_range: Option<OutOfRange>,
+        #[case] policy: OutOfRangePolicy,
+    ) -> Result<()> {
+        let result = verify_round_trip(&[Some(Frequency(4f64))], policy)?;
+        let round_trip = result[0].round_trip.as_ref().expect("Must be mapped");
+        assert_eq!(expected_out_of_range, round_trip.out_of_range);
+        if let Some(error_cents) = round_trip.error_cents {
+            assert!(error_cents.abs() < 0.004f64);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn all_keys() -> Result<()> {
+        let mut frequencies = MidiNote::ALL
+            .iter()
+            .map(|n| Some(n.frequency()))
+            .collect::<Vec<_>>();
+        frequencies[1] = None;
+
+        let result = verify_round_trip(&frequencies, OutOfRangePolicy::Fail)?;
+        assert_eq!(128, result.len());
+        assert_eq!(1, result[1].key.to_u8());
+        assert!(result[1].round_trip.is_none());
+        assert!(result
+            .iter()
+            .filter_map(|k| k.round_trip.as_ref())
+            .all(|r| r.out_of_range.is_none()
+                && r.error_cents
+                    .is_some_and(|error_cents| error_cents.abs() < 0.004f64)));
+        Ok(())
+    }
+}
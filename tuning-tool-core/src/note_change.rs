@@ -20,22 +20,38 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::consts::{MIDI_TUNING, NOTE_CHANGE, UNIVERSAL_REAL_TIME};
+use crate::consts::{MIDI_TUNING, NOTE_CHANGE, NOTE_CHANGE_WITH_BANK, UNIVERSAL_REAL_TIME};
 use crate::midi_message_builder::MidiMessageBuilder;
 use crate::note_change_entry::NoteChangeEntry;
 use crate::types::{DeviceId, MidiValue, Preset};
 use anyhow::{bail, Result};
 
+/// MTS single note tuning change (real-time) message
 #[derive(Debug)]
-pub(crate) struct NoteChange {
+pub struct NoteChange {
     device_id: DeviceId,
+    bank: Option<MidiValue>,
     preset: Preset,
     entries: Vec<NoteChangeEntry>,
 }
 
 impl NoteChange {
-    pub(crate) fn new(
+    pub fn new(device_id: DeviceId, preset: Preset, entries: &[NoteChangeEntry]) -> Result<Self> {
+        Self::new_inner(device_id, None, preset, entries)
+    }
+
+    pub fn with_bank(
         device_id: DeviceId,
+        bank: MidiValue,
+        preset: Preset,
+        entries: &[NoteChangeEntry],
+    ) -> Result<Self> {
+        Self::new_inner(device_id, Some(bank), preset, entries)
+    }
+
+    fn new_inner(
+        device_id: DeviceId,
+        bank: Option<MidiValue>,
         preset: Preset,
         entries: &[NoteChangeEntry],
     ) -> Result<Self> {
@@ -44,38 +60,53 @@ impl NoteChange {
         }
         Ok(Self {
             device_id,
+            bank,
             preset,
             entries: entries.to_vec(),
         })
     }
 
     #[allow(unused)]
-    pub(crate) const fn device_id(&self) -> DeviceId {
+    pub const fn device_id(&self) -> DeviceId {
         self.device_id
     }
 
     #[allow(unused)]
-    pub(crate) const fn preset(&self) -> Preset {
+    pub const fn bank(&self) -> Option<MidiValue> {
+        self.bank
+    }
+
+    #[allow(unused)]
+    pub const fn preset(&self) -> Preset {
         self.preset
     }
 
     #[allow(unused)]
-    pub(crate) fn entries(&self) -> &Vec<NoteChangeEntry> {
+    pub fn entries(&self) -> &Vec<NoteChangeEntry> {
         &self.entries
     }
 
     #[allow(unused)]
-    pub(crate) fn to_vec(&self) -> Result<Vec<MidiValue>> {
+    pub fn to_vec(&self) -> Result<Vec<MidiValue>> {
         let entry_count = self.entries.len();
         assert!(entry_count < 128);
-        let message_len = 6 + entry_count * 4;
+        let bank_len = if self.bank.is_some() { 1 } else { 0 };
+        let message_len = 6 + bank_len + entry_count * 4;
         let entry_count = MidiValue::from_u8_lossy(entry_count as u8);
 
         let mut values = MidiMessageBuilder::with_required_len(message_len);
         values.push(UNIVERSAL_REAL_TIME);
         values.push(self.device_id);
         values.push(MIDI_TUNING);
-        values.push(NOTE_CHANGE);
+        match self.bank {
+            Some(bank) => {
+                values.push(NOTE_CHANGE_WITH_BANK);
+                values.push(bank);
+            }
+            None => {
+                values.push(NOTE_CHANGE);
+            }
+        }
         values.push(self.preset);
         values.push(entry_count);
 
@@ -184,4 +215,23 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn with_bank() -> Result<()> {
+        let entries = [NoteChangeEntry {
+            key_number: KeyNumber::constant::<69>(),
+            mts: Frequency::CONCERT_A4.to_mts_entry()?,
+        }];
+        let message = NoteChange::with_bank(
+            DeviceId::ZERO,
+            MidiValue::constant::<3>(),
+            Preset::constant::<8>(),
+            &entries,
+        )?;
+        assert_eq!(
+            from_hex_dump("7F 00 08 07 03 08 01 45 45 00 00")?,
+            MidiValue::to_u8_slice(&message.to_vec()?)
+        );
+        Ok(())
+    }
 }
@@ -24,7 +24,7 @@ use crate::mts_entry::MtsEntry;
 use crate::types::KeyNumber;
 
 #[derive(Clone, Debug)]
-pub(crate) struct NoteChangeEntry {
-    pub(crate) key_number: KeyNumber,
-    pub(crate) mts: MtsEntry,
+pub struct NoteChangeEntry {
+    pub key_number: KeyNumber,
+    pub mts: MtsEntry,
 }
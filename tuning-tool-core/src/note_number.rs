@@ -22,8 +22,8 @@
 
 use crate::types::u7_newtype;
 
-u7_newtype!(NoteNumber, pub(crate));
+u7_newtype!(NoteNumber, pub);
 
 impl NoteNumber {
-    pub(crate) const A4: Self = Self::constant::<69>();
+    pub const A4: Self = Self::constant::<69>();
 }
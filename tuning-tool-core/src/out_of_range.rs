@@ -0,0 +1,216 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::frequency::Frequency;
+use crate::mts_entry::MtsEntry;
+use crate::semitones::Semitones;
+use anyhow::{bail, Error, Result};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+/// How to encode a frequency that lies outside the range MTS can represent,
+/// i.e. below MIDI note 0 or above 127.99 semitones
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutOfRangePolicy {
+    /// Clamp to the nearest end of the MTS range
+    #[default]
+    Clamp,
+
+    /// Move by whole octaves until the frequency is in range
+    Fold,
+
+    /// Send the MTS "no change" value so that the key keeps its tuning
+    Unchanged,
+
+    /// Fail
+    Fail,
+}
+
+/// Treatment applied to a frequency outside the MTS range
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutOfRange {
+    ClampedLow,
+    ClampedHigh,
+    Folded { octaves: i32 },
+    Unchanged,
+}
+
+impl OutOfRangePolicy {
+    pub const ALL: [OutOfRangePolicy; 4] = [Self::Clamp, Self::Fold, Self::Unchanged, Self::Fail];
+
+    /// Encodes the frequency as an MTS entry, returning the treatment
+    /// applied if the frequency is out of range
+    pub fn to_mts_entry(&self, frequency: Frequency) -> Result<(MtsEntry, Option<OutOfRange>)> {
+        let semitones = frequency.to_semitones_with_ignore_limit(true);
+        let out_of_range = if frequency.0 <= 0f64 || semitones.0 < 0f64 {
+            OutOfRange::ClampedLow
+        } else if semitones.0 > Semitones::MAX.0 {
+            OutOfRange::ClampedHigh
+        } else {
+            return Ok((frequency.to_mts_entry()?, None));
+        };
+
+        match self {
+            Self::Clamp => Ok((frequency.to_mts_entry()?, Some(out_of_range))),
+            Self::Fold => {
+                if !frequency.0.is_finite() || frequency.0 <= 0f64 {
+                    bail!(
+                        "Frequency {f} Hz cannot be folded into MTS range",
+                        f = frequency.0
+                    )
+                }
+                let octaves = fold_octaves(semitones);
+                let folded = Frequency(frequency.0 * 2f64.powi(octaves));
+                Ok((folded.to_mts_entry()?, Some(OutOfRange::Folded { octaves })))
+            }
+            Self::Unchanged => Ok((MtsEntry::NO_CHANGE, Some(OutOfRange::Unchanged))),
+            Self::Fail => bail!(
+                "Frequency {f} Hz is outside MTS range of {min:.6} Hz to {max:.6} Hz",
+                f = frequency.0,
+                min = Frequency::MIN.0,
+                max = Frequency::MAX.0
+            ),
+        }
+    }
+}
+
+impl Display for OutOfRangePolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Clamp => write!(f, "clamp"),
+            Self::Fold => write!(f, "fold"),
+            Self::Unchanged => write!(f, "unchanged"),
+            Self::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+impl FromStr for OutOfRangePolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        for policy in Self::ALL {
+            if s == policy.to_string() {
+                return Ok(policy);
+            }
+        }
+        bail!("Invalid out-of-range policy {s}")
+    }
+}
+
+impl Display for OutOfRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::ClampedLow => write!(f, "clamped to 0"),
+            Self::ClampedHigh => write!(f, "clamped to 127.99"),
+            Self::Folded { octaves } if *octaves > 0 => write!(f, "folded up {octaves} octave(s)"),
+            Self::Folded { octaves } => write!(f, "folded down {n} octave(s)", n = -octaves),
+            Self::Unchanged => write!(f, "left unchanged"),
+        }
+    }
+}
+
+// Number of octaves to move by to bring semitones into the MTS range
+fn fold_octaves(semitones: Semitones) -> i32 {
+    if semitones.0 < 0f64 {
+        (-semitones.0 / 12f64).ceil() as i32
+    } else {
+        -((semitones.0 - Semitones::MAX.0) / 12f64).ceil() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frequency::Frequency;
+    use crate::mts_entry::MtsEntry;
+    use crate::out_of_range::{OutOfRange, OutOfRangePolicy};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(None, 440f64)]
+    #[case(None, 8.175799f64)]
+    #[case(None, 13289.656616f64)]
+    #[case(Some(OutOfRange::ClampedLow), 4f64)]
+    #[case(Some(OutOfRange::ClampedHigh), 20000f64)]
+    fn clamp(#[case] expected: Option<OutOfRange>, #[case] input: f64) -> Result<()> {
+        let input = Frequency(input);
+        let (mts_entry, out_of_range) = OutOfRangePolicy::Clamp.to_mts_entry(input)?;
+        assert_eq!(expected, out_of_range);
+        assert_eq!(input.to_mts_entry()?, mts_entry);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(None, 440f64, 440f64)]
+    #[case(Some(1), 16f64, 8f64)]
+    #[case(Some(2), 12f64, 3f64)]
+    #[case(Some(-1), 10000f64, 20000f64)]
+    #[case(Some(-2), 10000f64, 40000f64)]
+    fn fold(
+        #[case] expected_octaves: Option<i32>,
+        #[case] expected_frequency: f64,
+        #[case] input: f64,
+    ) -> Result<()> {
+        let (mts_entry, out_of_range) = OutOfRangePolicy::Fold.to_mts_entry(Frequency(input))?;
+        assert_eq!(
+            expected_octaves.map(|octaves| OutOfRange::Folded { octaves }),
+            out_of_range
+        );
+        assert_eq!(Frequency(expected_frequency).to_mts_entry()?, mts_entry);
+        Ok(())
+    }
+
+    #[test]
+    fn unchanged() -> Result<()> {
+        let (mts_entry, out_of_range) =
+            OutOfRangePolicy::Unchanged.to_mts_entry(Frequency(20000f64))?;
+        assert_eq!(MtsEntry::NO_CHANGE, mts_entry);
+        assert_eq!(Some(OutOfRange::Unchanged), out_of_range);
+        Ok(())
+    }
+
+    #[test]
+    fn fail() -> Result<()> {
+        assert!(OutOfRangePolicy::Fail
+            .to_mts_entry(Frequency(4f64))
+            .is_err());
+        assert_eq!(
+            None,
+            OutOfRangePolicy::Fail.to_mts_entry(Frequency(440f64))?.1
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(OutOfRangePolicy::Clamp, "clamp")]
+    #[case(OutOfRangePolicy::Fold, "fold")]
+    #[case(OutOfRangePolicy::Unchanged, "unchanged")]
+    #[case(OutOfRangePolicy::Fail, "fail")]
+    fn round_trip(#[case] expected: OutOfRangePolicy, #[case] input: &str) -> Result<()> {
+        let policy = input.parse::<OutOfRangePolicy>()?;
+        assert_eq!(expected, policy);
+        assert_eq!(input, policy.to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::frequency::Frequency;
+use std::ops::Range;
+
+// Lowest fundamental to look for, below the range of most instruments
+const MIN_FREQUENCY: f64 = 25f64;
+
+// YIN's absolute threshold on the normalized difference function
+const YIN_THRESHOLD: f64 = 0.15;
+
+// Frames whose normalized difference never drops below this are unpitched
+const MAX_APERIODICITY: f64 = 0.5;
+
+// Number of frames analysed across the steady part of each note
+const FRAME_COUNT: usize = 7;
+
+// Fraction of each note skipped at either end to avoid the attack and decay
+const MARGIN: f64 = 0.1;
+
+// Frames quieter than this RMS level are treated as silence
+const SILENCE_RMS: f32 = 0.001;
+
+// Hop between energy frames used to find onsets, in seconds
+const ONSET_HOP_SECONDS: f64 = 0.01;
+
+// Rise in level over the quietest of the previous few frames that marks a
+// new note: comparing with the quietest of several frames catches attacks
+// that straddle a frame boundary, and the lower ratio catches notes that
+// start while the previous note is still sounding
+const ONSET_RATIO: f32 = 1.5f32;
+const ONSET_LOOKBACK: usize = 3;
+
+// Shortest note that can follow an onset, in seconds
+const MIN_NOTE_SECONDS: f64 = 0.1;
+
+/// Estimates the fundamental frequency of a single sustained note with the
+/// YIN algorithm, taking the median over several frames across the note
+///
+/// Returns `None` for silence or sounds with no clear pitch.
+pub fn detect_pitch(samples: &[f32], sample_rate: u32) -> Option<Frequency> {
+    let max_lag = (sample_rate as f64 / MIN_FREQUENCY).ceil() as usize;
+    let frame_len = 2 * max_lag;
+
+    let margin = (samples.len() as f64 * MARGIN) as usize;
+    let steady = &samples[margin..samples.len() - margin];
+    let steady = if steady.len() < frame_len {
+        samples
+    } else {
+        steady
+    };
+    if steady.len() < frame_len {
+        return None;
+    }
+
+    let last_start = steady.len() - frame_len;
+    let mut pitches = (0..FRAME_COUNT)
+        .map(|i| i * last_start / (FRAME_COUNT - 1))
+        .filter_map(|start| yin(&steady[start..start + frame_len], max_lag, sample_rate))
+        .collect::<Vec<_>>();
+    if pitches.is_empty() {
+        return None;
+    }
+
+    pitches.sort_by(f64::total_cmp);
+    Some(Frequency(pitches[pitches.len() / 2]))
+}
+
+/// Splits a recording of several notes into one range of samples per note,
+/// starting a new note wherever the level jumps after a quieter stretch
+pub fn split_notes(samples: &[f32], sample_rate: u32) -> Vec<Range<usize>> {
+    let hop = ((sample_rate as f64 * ONSET_HOP_SECONDS) as usize).max(1);
+    let min_note_hops = (MIN_NOTE_SECONDS / ONSET_HOP_SECONDS).ceil() as usize;
+
+    let levels = samples.chunks(hop).map(rms).collect::<Vec<_>>();
+
+    let mut onsets = Vec::new();
+    for (i, level) in levels.iter().copied().enumerate() {
+        let previous = levels[i.saturating_sub(ONSET_LOOKBACK)..i]
+            .iter()
+            .copied()
+            .reduce(f32::min)
+            .unwrap_or(0f32);
+        let is_onset = level > SILENCE_RMS && level > previous * ONSET_RATIO;
+        let is_far_enough = onsets
+            .last()
+            .is_none_or(|last: &usize| i - last >= min_note_hops);
+        if is_onset && is_far_enough {
+            onsets.push(i);
+        }
+    }
+
+    onsets
+        .iter()
+        .enumerate()
+        .map(|(i, onset)| {
+            let end = onsets.get(i + 1).map_or(samples.len(), |next| next * hop);
+            onset * hop..end
+        })
+        .collect()
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+// de Cheveigné and Kawahara, "YIN, a fundamental frequency estimator for
+// speech and music" (2002)
+fn yin(frame: &[f32], max_lag: usize, sample_rate: u32) -> Option<f64> {
+    if rms(frame) < SILENCE_RMS {
+        return None;
+    }
+
+    let window = frame.len() - max_lag;
+    let difference = (0..max_lag)
+        .map(|lag| {
+            (0..window)
+                .map(|j| {
+                    let d = (frame[j] - frame[j + lag]) as f64;
+                    d * d
+                })
+                .sum::<f64>()
+        })
+        .collect::<Vec<_>>();
+
+    // Cumulative mean normalized difference
+    let mut normalized = vec![1f64; max_lag];
+    let mut running_sum = 0f64;
+    for lag in 1..max_lag {
+        running_sum += difference[lag];
+        normalized[lag] = if running_sum > 0f64 {
+            difference[lag] * lag as f64 / running_sum
+        } else {
+            1f64
+        };
+    }
+
+    // First dip below the threshold, followed down to its minimum
+    let lag = match (2..max_lag).find(|lag| normalized[*lag] < YIN_THRESHOLD) {
+        Some(mut lag) => {
+            while lag + 1 < max_lag && normalized[lag + 1] < normalized[lag] {
+                lag += 1;
+            }
+            lag
+        }
+        None => {
+            let lag = (2..max_lag).min_by(|a, b| normalized[*a].total_cmp(&normalized[*b]))?;
+            if normalized[lag] > MAX_APERIODICITY {
+                return None;
+            }
+            lag
+        }
+    };
+
+    // Parabolic interpolation between neighbouring lags
+    let refined = if lag + 1 < max_lag {
+        let (a, b, c) = (difference[lag - 1], difference[lag], difference[lag + 1]);
+        let denominator = a - 2f64 * b + c;
+        if denominator.abs() > f64::EPSILON {
+            lag as f64 + 0.5f64 * (a - c) / denominator
+        } else {
+            lag as f64
+        }
+    } else {
+        lag as f64
+    };
+
+    Some(sample_rate as f64 / refined)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pitch_detection::{detect_pitch, split_notes};
+    use rstest::rstest;
+    use std::f64::consts::TAU;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    // Note with a few decaying harmonics, like a plucked or struck string
+    fn make_note(frequency: f64, seconds: f64, amplitude: f64) -> Vec<f32> {
+        let len = (SAMPLE_RATE as f64 * seconds) as usize;
+        (0..len)
+            .map(|i| {
+                let t = i as f64 / SAMPLE_RATE as f64;
+                let value = (1..=4)
+                    .map(|h| (TAU * frequency * h as f64 * t).sin() / h as f64)
+                    .sum::<f64>();
+                (amplitude * value * 0.4f64) as f32
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(55f64)]
+    #[case(261.625565f64)]
+    #[case(440f64)]
+    #[case(1318.51f64)]
+    fn detect(#[case] expected: f64) {
+        let samples = make_note(expected, 0.5f64, 1f64);
+        let actual = detect_pitch(&samples, SAMPLE_RATE)
+            .expect("Must detect pitch")
+            .0;
+        // Within half a cent
+        assert!((1200f64 * (actual / expected).log2()).abs() < 0.5f64);
+    }
+
+    #[test]
+    fn silence() {
+        assert!(detect_pitch(&[0f32; SAMPLE_RATE as usize], SAMPLE_RATE).is_none());
+        assert!(detect_pitch(&[0f32; 100], SAMPLE_RATE).is_none());
+    }
+
+    #[test]
+    fn split() {
+        let gap = vec![0f32; SAMPLE_RATE as usize / 10];
+        let samples = [
+            gap.clone(),
+            make_note(220f64, 0.5f64, 1f64),
+            gap.clone(),
+            make_note(330f64, 0.3f64, 0.1f64),
+            make_note(440f64, 0.4f64, 1f64),
+        ]
+        .concat();
+
+        let notes = split_notes(&samples, SAMPLE_RATE);
+        assert_eq!(3, notes.len());
+        for (expected, note) in [220f64, 330f64, 440f64].iter().zip(notes) {
+            let actual = detect_pitch(&samples[note], SAMPLE_RATE)
+                .expect("Must detect pitch")
+                .0;
+            assert!((1200f64 * (actual / expected).log2()).abs() < 1f64);
+        }
+    }
+
+    #[test]
+    fn split_sustained_note_once() {
+        let gap = vec![0f32; SAMPLE_RATE as usize / 10];
+        let samples = [gap, make_note(220f64, 2f64, 1f64)].concat();
+        assert_eq!(1, split_notes(&samples, SAMPLE_RATE).len());
+    }
+
+    #[test]
+    fn split_overlapping_notes() {
+        // Second note starts while the first is still sounding and rises
+        // over 30 ms, so no frame doubles the level of the one before
+        let attack = SAMPLE_RATE as usize * 3 / 100;
+        let start = SAMPLE_RATE as usize / 2;
+        let mut samples = make_note(220f64, 1f64, 0.5f64);
+        for (i, s) in make_note(330f64, 0.5f64, 1f64).into_iter().enumerate() {
+            samples[start + i] += s * (i as f32 / attack as f32).min(1f32);
+        }
+
+        let notes = split_notes(&samples, SAMPLE_RATE);
+        assert_eq!(2, notes.len());
+        let actual = detect_pitch(&samples[notes[0].clone()], SAMPLE_RATE)
+            .expect("Must detect pitch")
+            .0;
+        assert!((1200f64 * (actual / 220f64).log2()).abs() < 1f64);
+    }
+}
@@ -31,16 +31,16 @@ const PRESET_NAME_LEN: usize = 16;
 type PresetNameArray = [Char7; PRESET_NAME_LEN];
 
 #[derive(Debug)]
-pub(crate) struct PresetName(PresetNameArray);
+pub struct PresetName(PresetNameArray);
 
 impl PresetName {
-    pub(crate) const LEN: usize = PRESET_NAME_LEN;
+    pub const LEN: usize = PRESET_NAME_LEN;
 
-    pub(crate) const fn new(slice: PresetNameArray) -> Self {
+    pub const fn new(slice: PresetNameArray) -> Self {
         Self(slice)
     }
 
-    pub(crate) const fn as_array(&self) -> &PresetNameArray {
+    pub const fn as_array(&self) -> &PresetNameArray {
         &self.0
     }
 }
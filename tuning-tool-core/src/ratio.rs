@@ -24,7 +24,7 @@ use crate::evaluate::Evaluate;
 use crate::types::f64_newtype;
 use num::pow::Pow;
 
-f64_newtype!(Ratio, pub(crate));
+f64_newtype!(Ratio, pub);
 
 impl Evaluate for Ratio {
     fn as_f64(&self) -> f64 {
@@ -25,14 +25,14 @@ use crate::types::KeyNumber;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Reference {
+pub struct Reference {
     zero_key: KeyNumber,
     reference_key: KeyNumber,
     reference_frequency: Frequency,
 }
 
 impl Reference {
-    pub(crate) const fn new(
+    pub const fn new(
         zero_key: KeyNumber,
         reference_key: KeyNumber,
         reference_frequency: Frequency,
@@ -44,15 +44,15 @@ impl Reference {
         }
     }
 
-    pub(crate) fn zero_key(&self) -> KeyNumber {
+    pub fn zero_key(&self) -> KeyNumber {
         self.zero_key
     }
 
-    pub(crate) fn reference_key(&self) -> KeyNumber {
+    pub fn reference_key(&self) -> KeyNumber {
         self.reference_key
     }
 
-    pub(crate) fn reference_frequency(&self) -> Frequency {
+    pub fn reference_frequency(&self) -> Frequency {
         self.reference_frequency
     }
 }
@@ -25,28 +25,29 @@ use crate::ratio::Ratio;
 use anyhow::{bail, Result};
 use tuning_tool_lib::symbolic::Expression;
 
-#[derive(Debug)]
-pub(crate) struct Scale {
+/// Named sequence of intervals whose last interval is the equivalence interval
+#[derive(Clone, Debug)]
+pub struct Scale {
     intervals: Vec<Interval>,
 }
 
 impl Scale {
-    pub(crate) fn new(intervals: Vec<Interval>) -> Result<Self> {
+    pub fn new(intervals: Vec<Interval>) -> Result<Self> {
         if intervals.is_empty() {
             bail!("Need at least one interval");
         }
         Ok(Self { intervals })
     }
 
-    pub(crate) fn intervals(&self) -> &Vec<Interval> {
+    pub fn intervals(&self) -> &Vec<Interval> {
         &self.intervals
     }
 
-    pub(crate) fn equave_ratio_expr(&self) -> Expression {
+    pub fn equave_ratio_expr(&self) -> Expression {
         self.last_interval().as_ratio_expr()
     }
 
-    pub(crate) fn equave_ratio(&self) -> Ratio {
+    pub fn equave_ratio(&self) -> Ratio {
         self.last_interval().as_ratio()
     }
 
@@ -0,0 +1,416 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::diagnostic::{Diagnostic, Parsed, SourceLine};
+#[cfg(feature = "fs")]
+use crate::fs::read_to_string_lossy;
+use crate::interval::Interval;
+use crate::scale::Scale;
+use anyhow::Error;
+#[cfg(feature = "fs")]
+use anyhow::{Context, Result};
+use log::trace;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+/// Scala scale (`.scl`) file
+#[derive(Debug)]
+pub struct SclFile {
+    file_name: Option<String>,
+    description: String,
+    scale: Scale,
+}
+
+impl SclFile {
+    /// Reads and parses, ignoring any warnings
+    #[cfg(feature = "fs")]
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Self::read_parsed(path)?
+            .into_result()
+            .with_context(|| format!("Failed to parse {path}", path = path.display()))
+    }
+
+    /// Reads and parses tolerantly, reporting warnings as well as errors
+    /// with their line and column
+    #[cfg(feature = "fs")]
+    pub fn read_parsed<P: AsRef<Path>>(path: P) -> Result<Parsed<Self>> {
+        trace!("Reading .scl file {path}", path = path.as_ref().display());
+        Ok(Self::parse(&read_to_string_lossy(path)?))
+    }
+
+    /// Parses tolerantly, reporting warnings as well as errors with their
+    /// line and column
+    pub fn parse(s: &str) -> Parsed<Self> {
+        let mut diagnostics = Vec::new();
+        let value = parse_lines(s, &mut diagnostics);
+        Parsed::new(value, diagnostics)
+    }
+
+    pub fn new(file_name: Option<String>, description: &str, scale: Scale) -> Self {
+        Self {
+            file_name,
+            description: String::from(description),
+            scale,
+        }
+    }
+
+    #[allow(unused)]
+    pub const fn file_name(&self) -> &Option<String> {
+        &self.file_name
+    }
+
+    #[allow(unused)]
+    pub fn description(&self) -> &str {
+        self.description.as_str()
+    }
+
+    pub fn scale(&self) -> &Scale {
+        &self.scale
+    }
+}
+
+impl Display for SclFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(file_name) = &self.file_name {
+            writeln!(f, "! {file_name}")?;
+        }
+        writeln!(f, "!")?;
+        writeln!(f, "{}", self.description)?;
+        writeln!(f, " {}", self.scale.intervals().len())?;
+        writeln!(f, "!")?;
+        for interval in self.scale.intervals() {
+            writeln!(f, " {interval}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SclFile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        Self::parse(s).into_result()
+    }
+}
+
+// Parses the content of a .scl file, collecting every problem found:
+// returns None after the first problem that prevents building the scale
+fn parse_lines(s: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<SclFile> {
+    trace!("Content is [[{s}]]");
+
+    let lines = SourceLine::split(s);
+    let end_line = SourceLine::end_line(&lines);
+    let mut lines = lines.into_iter().peekable();
+
+    let file_name = match lines.peek().map(SourceLine::trimmed) {
+        Some(line) if line.starts_with("!") && line.ends_with(".scl") => {
+            let file_name = line[1..].trim();
+            _ = lines.next().expect("Consume line");
+            trace!("Parsed file name {file_name}");
+            Some(String::from(file_name))
+        }
+        _ => {
+            trace!("Parsed no file name");
+            None
+        }
+    };
+
+    // Now skip all comment lines
+    let mut lines = lines.filter(|line| !line.trimmed().starts_with("!"));
+
+    let Some(description) = lines.next().map(|line| line.trimmed()) else {
+        diagnostics.push(Diagnostic::error(end_line, 1, "No description found"));
+        return None;
+    };
+
+    trace!(
+        "Parsed description {description}",
+        description = if description.is_empty() {
+            "(empty)"
+        } else {
+            description
+        }
+    );
+
+    // Now skip all blank lines too
+    let mut lines = lines.filter(|line| !line.trimmed().is_empty());
+
+    let Some(count_line) = lines.next() else {
+        diagnostics.push(Diagnostic::error(end_line, 1, "No note count found"));
+        return None;
+    };
+
+    let interval_count = count_line.parse_value::<usize>("note count", false, diagnostics)?;
+    trace!("Parsed interval count {interval_count}");
+
+    let mut intervals = Vec::with_capacity(interval_count);
+    let mut failed = false;
+    let mut extra_lines = Vec::new();
+    for (i, line) in lines.enumerate() {
+        if i >= interval_count {
+            extra_lines.push(line);
+            continue;
+        }
+
+        // Text after the pitch value is a comment according to the Scala spec
+        let Some(interval) = line.parse_value::<Interval>("pitch value", true, diagnostics) else {
+            failed = true;
+            continue;
+        };
+
+        let (token, _) = line.split_value();
+        let ratio = interval.as_ratio().0;
+        if ratio <= 0f64 || !ratio.is_finite() {
+            diagnostics.push(line.error(token, "Ratio must be positive"));
+            failed = true;
+            continue;
+        }
+
+        if ratio < 1f64 {
+            diagnostics.push(line.warning(token, format!("Pitch value {interval} is below 1/1")));
+        }
+
+        trace!("Parsed interval {interval}");
+        intervals.push(interval);
+    }
+
+    let (count_token, _) = count_line.split_value();
+    if let Some(first) = extra_lines.first() {
+        diagnostics.push(first.warning(
+            first.trimmed(),
+            format!(
+                "Ignoring {count} line(s) after the {interval_count} notes declared on line {line}",
+                count = extra_lines.len(),
+                line = count_line.number
+            ),
+        ));
+    } else if !failed && intervals.len() < interval_count {
+        diagnostics.push(count_line.warning(
+            count_token,
+            format!(
+                "Declared {interval_count} notes but found {count}",
+                count = intervals.len()
+            ),
+        ));
+    }
+
+    if failed {
+        return None;
+    }
+
+    if intervals.is_empty() {
+        diagnostics.push(count_line.error(count_token, "Scale has no notes"));
+        return None;
+    }
+
+    Some(SclFile {
+        file_name,
+        description: String::from(description),
+        scale: Scale::new(intervals).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use include_dir::include_dir;
+    use std::ffi::OsStr;
+
+    macro_rules! verify_scl_files {
+        ($dir: expr, $count: expr) => {{
+            let extension = Some(OsStr::new("scl"));
+            let files = $dir
+                .files()
+                .filter(|f| f.path().extension() == extension)
+                .collect::<Vec<_>>();
+            assert_eq!($count, files.len());
+
+            for file in files {
+                verify_scl_file!(file);
+            }
+        }};
+    }
+
+    macro_rules! verify_scl_file {
+        ($file: expr) => {{
+            use crate::scl_file::SclFile;
+            use std::panic;
+
+            let path = $file.path();
+
+            let s = String::from_utf8_lossy($file.contents());
+
+            let Ok(scl_file) = s.parse::<SclFile>() else {
+                panic!("Failed to parse .scl file {path}", path = path.display());
+            };
+
+            let file_name = scl_file.file_name();
+            assert!(file_name.is_some() || file_name.is_none());
+        }};
+    }
+
+    macro_rules! verify_scl {
+        ($path: expr) => {{
+            use crate::resources::include_resource_str;
+            use crate::scl_file::SclFile;
+            use std::{assert, panic};
+
+            let s = include_resource_str!($path);
+
+            let Ok(scl_file) = s.parse::<SclFile>() else {
+                panic!("Failed to parse .scl file {path}", path = $path);
+            };
+
+            let file_name = scl_file.file_name();
+            assert!(file_name.is_some() || file_name.is_none());
+        }};
+    }
+
+    #[test]
+    fn scala_archive() {
+        verify_scl_files!(
+            include_dir!("$CARGO_MANIFEST_DIR/../resources/test/scala-archive"),
+            5233
+        );
+    }
+
+    #[test]
+    fn sevish_tunings_pack() {
+        verify_scl_files!(
+            include_dir!("$CARGO_MANIFEST_DIR/../resources/test/sevish-tunings-pack/eikosany"),
+            21
+        );
+        verify_scl_files!(
+            include_dir!("$CARGO_MANIFEST_DIR/../resources/test/sevish-tunings-pack/no-octaves"),
+            22
+        );
+        verify_scl_files!(
+            include_dir!(
+                "$CARGO_MANIFEST_DIR/../resources/test/sevish-tunings-pack/regular-temperaments"
+            ),
+            21
+        );
+        verify_scl_files!(
+            include_dir!("$CARGO_MANIFEST_DIR/../resources/test/sevish-tunings-pack/world-scales"),
+            26
+        );
+    }
+
+    #[test]
+    fn blank_description() {
+        verify_scl!("blank-description.scl");
+    }
+
+    #[test]
+    fn round_trip() -> anyhow::Result<()> {
+        use crate::resources::include_resource_str;
+        use crate::scl_file::SclFile;
+
+        let scl_file = include_resource_str!("22edo2.scl").parse::<SclFile>()?;
+        let s = scl_file.to_string();
+        assert!(s.starts_with("! 22edo2.scl\n!\n"));
+
+        let other = s.parse::<SclFile>()?;
+        assert_eq!(scl_file.file_name(), other.file_name());
+        assert_eq!(scl_file.description(), other.description());
+        assert_eq!(scl_file.scale().intervals(), other.scale().intervals());
+        Ok(())
+    }
+
+    #[test]
+    fn tolerant() -> anyhow::Result<()> {
+        use crate::diagnostic::Diagnostic;
+        use crate::scl_file::SclFile;
+
+        let parsed = SclFile::parse(
+            "! test.scl\r\n!\r\nTest\r\n 4 notes\r\n100.0 cents\r\n-50.0\r\n3/2,\r\n",
+        );
+        assert_eq!(
+            vec![
+                Diagnostic::warning(4, 2, "Declared 4 notes but found 3"),
+                Diagnostic::warning(4, 4, "Ignoring text after note count"),
+                Diagnostic::warning(6, 1, "Pitch value -50.0 is below 1/1"),
+                Diagnostic::warning(7, 4, "Ignoring text after pitch value"),
+            ],
+            parsed.diagnostics
+        );
+        assert_eq!(4, parsed.warnings().count());
+
+        let scl_file = parsed.into_result()?;
+        assert_eq!(&Some(String::from("test.scl")), scl_file.file_name());
+        assert_eq!(
+            vec!["100.0", "-50.0", "3/2"],
+            scl_file
+                .scale()
+                .intervals()
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn extra_lines() -> anyhow::Result<()> {
+        use crate::diagnostic::Diagnostic;
+        use crate::scl_file::SclFile;
+
+        let parsed = SclFile::parse("Test\n1\n2/1\n3/1\n");
+        assert_eq!(
+            vec![Diagnostic::warning(
+                4,
+                1,
+                "Ignoring 1 line(s) after the 1 notes declared on line 2"
+            )],
+            parsed.diagnostics
+        );
+        assert_eq!(1, parsed.into_result()?.scale().intervals().len());
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("Line 1, column 1: No description found", "")]
+    #[case("Line 3, column 1: No note count found", "! comment\nTest\n")]
+    #[case(
+        "Line 2, column 3: Expected note count, found \"twelve\"",
+        "Test\n  twelve\n"
+    )]
+    #[case(
+        "Line 4, column 2: Expected pitch value, found \"abc\"",
+        "Test\n2\n100.0\n abc\n"
+    )]
+    #[case("Line 3, column 1: Ratio must be positive", "Test\n1\n-3/2\n")]
+    #[case("Line 2, column 1: Scale has no notes", "Test\n0\n")]
+    fn errors(#[case] expected: &str, #[case] input: &str) {
+        use crate::scl_file::SclFile;
+
+        let parsed = SclFile::parse(input);
+        assert!(parsed.has_errors());
+        assert_eq!(
+            expected,
+            parsed.into_result().expect_err("Must fail").to_string()
+        );
+    }
+}
@@ -26,13 +26,14 @@ use crate::note_number::NoteNumber;
 use crate::types::{f64_newtype, Lsb, Msb};
 use anyhow::Result;
 
-f64_newtype!(Semitones, pub(crate));
+f64_newtype!(Semitones, pub);
 
 impl Semitones {
-    pub(crate) const MAX: Self = Self(127.999878f64);
+    pub const MAX: Self = Self(127.999878f64);
 
-    // c.f. mtsToMtsBytes
-    pub(crate) fn to_mts_entry(&self) -> Result<MtsEntry> {
+    // c.f. mtsToMtsBytes: values above MAX clamp to 7F 7F 7E since 7F 7F 7F
+    // is reserved for "no change"
+    pub fn to_mts_entry(&self) -> Result<MtsEntry> {
         if self.0 <= 0f64 {
             return Ok(MtsEntry {
                 note_number: NoteNumber::ZERO,
@@ -61,7 +62,7 @@ impl Semitones {
         })
     }
 
-    pub(crate) fn to_frequency(&self) -> Frequency {
+    pub fn to_frequency(&self) -> Frequency {
         let temp: f64 = NoteNumber::A4.to_u8() as f64;
         Frequency(Frequency::CONCERT_A4.0 * 2f64.powf((self.0 - temp) / 12f64))
     }
@@ -0,0 +1,145 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::kbm_file::KbmFile;
+use crate::keyboard_mapping::KeyboardMapping;
+use crate::midi_note::MidiNote;
+use crate::reference::Reference;
+use crate::scl_file::SclFile;
+use crate::tuning_format::{KbmWriter, SclWriter, Tuning, TuningReader, TuningWriter};
+use crate::types::KeyNumber;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+const ELEMENT_NAME: &str = "patchTuning";
+
+// Surge's default mapping puts middle C at 261.63 Hz on key 60
+const DEFAULT_ZERO_KEY: u8 = 60;
+
+/// Reads the `<patchTuning>` element of a Surge XT patch, either a binary
+/// `.fxp` file or its XML, whose attributes hold base64-encoded `.scl` and
+/// `.kbm` files
+pub struct SurgePatchReader;
+
+impl TuningReader for SurgePatchReader {
+    fn read_tuning(&self, bytes: &[u8]) -> Result<Tuning> {
+        let s = String::from_utf8_lossy(bytes);
+        let start = s
+            .find(&format!("<{ELEMENT_NAME}"))
+            .ok_or_else(|| anyhow!("Patch has no tuning"))?;
+        let element = &s[start..];
+        let element = &element[..element.find('>').unwrap_or(element.len())];
+
+        let scl = attribute(element, "v")?.ok_or_else(|| anyhow!("Patch has no scale"))?;
+        let scl_file = scl.parse::<SclFile>()?;
+
+        let keyboard_mapping = match attribute(element, "m")? {
+            Some(kbm) => kbm.parse::<KbmFile>()?.keyboard_mapping().clone(),
+            None => {
+                let zero_key = KeyNumber::constant::<DEFAULT_ZERO_KEY>();
+                KeyboardMapping::new_full_linear(&Reference::new(
+                    zero_key,
+                    zero_key,
+                    MidiNote::ALL[DEFAULT_ZERO_KEY as usize].frequency(),
+                ))?
+            }
+        };
+
+        Ok(Tuning::new(
+            scl_file.description(),
+            scl_file.scale().clone(),
+            keyboard_mapping,
+        ))
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+/// Writes a `<patchTuning>` element for pasting into a Surge XT patch
+pub struct SurgePatchWriter;
+
+impl TuningWriter for SurgePatchWriter {
+    fn write_tuning(&self, tuning: &Tuning) -> Result<String> {
+        Ok(format!(
+            "<{ELEMENT_NAME} v=\"{scl}\" m=\"{kbm}\" />\n",
+            scl = STANDARD.encode(SclWriter.write_tuning(tuning)?),
+            kbm = STANDARD.encode(KbmWriter.write_tuning(tuning)?)
+        ))
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &["Only the <patchTuning> element is written, not a complete patch"]
+    }
+}
+
+// Decodes a base64 attribute of the element
+fn attribute(element: &str, name: &str) -> Result<Option<String>> {
+    let prefix = format!(" {name}=\"");
+    let Some(start) = element.find(&prefix) else {
+        return Ok(None);
+    };
+    let value = &element[start + prefix.len()..];
+    let value = &value[..value
+        .find('"')
+        .ok_or_else(|| anyhow!("Unterminated attribute {name}"))?];
+    if value.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8(STANDARD.decode(value)?)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::include_resource_str;
+    use crate::surge_patch::{SurgePatchReader, SurgePatchWriter};
+    use crate::tuning_format::{SclReader, TuningReader, TuningWriter};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let tuning =
+            SclReader::new(None).read_tuning(include_resource_str!("22edo2.scl").as_bytes())?;
+        let element = SurgePatchWriter.write_tuning(&tuning)?;
+        let patch = format!("<patch revision=\"21\">\n  {element}</patch>\n");
+        let actual = SurgePatchReader.read_tuning(patch.as_bytes())?;
+
+        assert_eq!(22, actual.scale().intervals().len());
+        for (expected, actual) in tuning.frequencies()?.iter().zip(actual.frequencies()?) {
+            let expected = expected.expect("Must be mapped").0;
+            let actual = actual.expect("Must be mapped").0;
+            assert!((expected - actual).abs() < 0.0001f64);
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("<patch></patch>")]
+    #[case("<patchTuning m=\"\" />")]
+    #[case("<patchTuning v=\"!!!\" />")]
+    fn read_fails(#[case] input: &str) {
+        assert!(SurgePatchReader.read_tuning(input.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::frequency::Frequency;
+use std::f64::consts::TAU;
+
+const ATTACK_SECONDS: f64 = 0.01;
+const RELEASE_SECONDS: f64 = 0.2;
+
+// Time for a held note to decay by a factor of e, as for a plucked string
+const DECAY_SECONDS: f64 = 1.5;
+
+// Peak level of the rendered mix
+const PEAK_LEVEL: f32 = 0.8;
+
+/// Note rendered by `render`, with times in seconds
+#[derive(Clone, Debug)]
+pub struct SynthNote {
+    pub start: f64,
+    pub duration: f64,
+    pub frequency: Frequency,
+    pub velocity: f64,
+}
+
+/// Renders notes with a simple additive synth, with harmonic `h` at
+/// amplitude `1/h`, normalizing the mix to a fixed peak level
+///
+/// A single harmonic gives pure sine tones. Harmonics above the Nyquist
+/// frequency are dropped.
+pub fn render(notes: &[SynthNote], sample_rate: u32, harmonics: usize) -> Vec<f32> {
+    let rate = sample_rate as f64;
+    let end = notes
+        .iter()
+        .map(|n| n.start + n.duration + RELEASE_SECONDS)
+        .fold(0f64, f64::max);
+    let mut samples = vec![0f32; (end * rate).ceil() as usize];
+
+    for note in notes {
+        let partials = (1..=harmonics.max(1))
+            .map(|h| h as f64)
+            .filter(|h| note.frequency.0 * h < rate / 2f64)
+            .collect::<Vec<_>>();
+        let first = (note.start * rate) as usize;
+        let len = ((note.duration + RELEASE_SECONDS) * rate) as usize;
+
+        for (i, sample) in samples.iter_mut().skip(first).take(len).enumerate() {
+            let t = i as f64 / rate;
+            let value = partials
+                .iter()
+                .map(|h| (TAU * note.frequency.0 * h * t).sin() / h)
+                .sum::<f64>();
+            *sample += (value * note.velocity * envelope(t, note.duration)) as f32;
+        }
+    }
+
+    let peak = samples.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+    if peak > 0f32 {
+        for sample in &mut samples {
+            *sample *= PEAK_LEVEL / peak;
+        }
+    }
+
+    samples
+}
+
+fn envelope(t: f64, duration: f64) -> f64 {
+    let level = |t: f64| (t / ATTACK_SECONDS).min(1f64) * (-t / DECAY_SECONDS).exp();
+    if t < duration {
+        level(t)
+    } else {
+        level(duration) * (1f64 - (t - duration) / RELEASE_SECONDS).max(0f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frequency::Frequency;
+    use crate::pitch_detection::{detect_pitch, split_notes};
+    use crate::synth::{render, SynthNote};
+    use rstest::rstest;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    #[rstest]
+    #[case(1)]
+    #[case(6)]
+    fn render_note(#[case] harmonics: usize) {
+        let notes = [SynthNote {
+            start: 0.1f64,
+            duration: 0.5f64,
+            frequency: Frequency(440f64),
+            velocity: 1f64,
+        }];
+        let samples = render(&notes, SAMPLE_RATE, harmonics);
+
+        // Silence before the note, then the note with its release
+        assert_eq!(SAMPLE_RATE as usize * 8 / 10, samples.len());
+        assert!(samples[..4410].iter().all(|s| *s == 0f32));
+        let peak = samples.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+        assert!((peak - 0.8f32).abs() < 0.0001f32);
+
+        let actual = detect_pitch(&samples[4410..26460], SAMPLE_RATE)
+            .expect("Must detect pitch")
+            .0;
+        assert!((actual - 440f64).abs() < 0.5f64);
+    }
+
+    #[test]
+    fn split_legato_run() {
+        let notes = [440f64, 495f64, 550f64]
+            .iter()
+            .enumerate()
+            .map(|(i, f)| SynthNote {
+                start: i as f64 * 0.5f64,
+                duration: 0.5f64,
+                frequency: Frequency(*f),
+                velocity: 1f64,
+            })
+            .collect::<Vec<_>>();
+        let samples = render(&notes, SAMPLE_RATE, 6);
+        assert_eq!(3, split_notes(&samples, SAMPLE_RATE).len());
+    }
+
+    #[test]
+    fn render_nothing() {
+        assert!(render(&[], SAMPLE_RATE, 1).is_empty());
+    }
+}
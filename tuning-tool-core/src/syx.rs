@@ -0,0 +1,145 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::{bail, Result};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+pub use crate::consts::{EOX, SYSEX, UNIVERSAL_NON_REAL_TIME};
+pub use crate::hex_dump::{from_hex_dump, to_hex_dump};
+pub use crate::midi_message_builder::to_sysex;
+
+/// Run of bytes outside any F0...F7 message in the contents of a .syx file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrayBytes {
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Display for StrayBytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{len} byte(s) outside SysEx message at offset {offset}",
+            len = self.len,
+            offset = self.offset
+        )
+    }
+}
+
+/// Splits the contents of a .syx file into individual F0...F7 messages,
+/// skipping any stray bytes between messages
+pub fn split_messages(bytes: &[u8]) -> Result<Vec<&[u8]>> {
+    Ok(split_messages_with_stray_bytes(bytes)?.0)
+}
+
+/// Splits the contents of a .syx file into individual F0...F7 messages,
+/// also returning the runs of stray bytes skipped between messages so that
+/// the caller can report them
+pub fn split_messages_with_stray_bytes(bytes: &[u8]) -> Result<(Vec<&[u8]>, Vec<StrayBytes>)> {
+    let mut messages = Vec::new();
+    let mut stray_bytes = Vec::new();
+    let mut start = None;
+    let mut stray_start = None;
+    for (i, b) in bytes.iter().enumerate() {
+        if *b == SYSEX {
+            if let Some(offset) = stray_start.take() {
+                stray_bytes.push(StrayBytes {
+                    offset,
+                    len: i - offset,
+                });
+            }
+        }
+
+        match (*b, start) {
+            (SYSEX, None) => start = Some(i),
+            (SYSEX, Some(_)) => bail!("Unterminated SysEx message at offset {i}"),
+            (EOX, Some(s)) => {
+                messages.push(&bytes[s..=i]);
+                start = None;
+            }
+            (_, Some(_)) if *b < 0x80 => {}
+            (_, Some(_)) => bail!("Unexpected status byte {b:02X} at offset {i}"),
+            (_, None) => {
+                stray_start.get_or_insert(i);
+            }
+        }
+    }
+
+    if let Some(s) = start {
+        bail!("Unterminated SysEx message at offset {s}")
+    }
+
+    if let Some(offset) = stray_start {
+        stray_bytes.push(StrayBytes {
+            offset,
+            len: bytes.len() - offset,
+        });
+    }
+
+    Ok((messages, stray_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syx::{split_messages, split_messages_with_stray_bytes, StrayBytes};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Vec::<&[u8]>::new(), &[])]
+    #[case(vec![&[0xf0, 0xf7][..]], &[0xf0, 0xf7])]
+    #[case(vec![&[0xf0, 0x7e, 0x00, 0xf7][..], &[0xf0, 0x43, 0xf7][..]], &[0xf0, 0x7e, 0x00, 0xf7, 0xf0, 0x43, 0xf7])]
+    #[case(Vec::<&[u8]>::new(), &[0x00])]
+    #[case(vec![&[0xf0, 0xf7][..], &[0xf0, 0x43, 0xf7][..]], &[0x0a, 0xf0, 0xf7, 0x0d, 0x0a, 0xf7, 0xf0, 0x43, 0xf7, 0x00])]
+    fn basics(#[case] expected: Vec<&[u8]>, #[case] input: &[u8]) -> Result<()> {
+        assert_eq!(expected, split_messages(input)?);
+        Ok(())
+    }
+
+    #[test]
+    fn stray_bytes() -> Result<()> {
+        let (messages, stray_bytes) =
+            split_messages_with_stray_bytes(&[0x0a, 0xf0, 0xf7, 0x0d, 0x0a, 0xf0, 0xf7, 0x00])?;
+        assert_eq!(2, messages.len());
+        assert_eq!(
+            vec![
+                StrayBytes { offset: 0, len: 1 },
+                StrayBytes { offset: 3, len: 2 },
+                StrayBytes { offset: 7, len: 1 }
+            ],
+            stray_bytes
+        );
+        assert_eq!(
+            "2 byte(s) outside SysEx message at offset 3",
+            stray_bytes[1].to_string()
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(&[0xf0, 0x7e])]
+    #[case(&[0xf0, 0x7e, 0xf0, 0xf7])]
+    #[case(&[0xf0, 0x90, 0xf7])]
+    fn invalid(#[case] input: &[u8]) {
+        assert!(split_messages(input).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::dump_import::ImportedTuning;
+use crate::frequency::Frequency;
+use crate::tuning_format::{Tuning, TuningReader, TuningWriter};
+use anyhow::{anyhow, bail, Result};
+use std::fmt::Write;
+
+// Frequency of MIDI key 0 that [Tuning] cents are relative to
+const DEFAULT_BASE_FREQUENCY: f64 = 8.1757989156;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Info,
+    Tuning,
+    ExactTuning,
+    Other,
+}
+
+/// Reads an AnaMark `.tun` file (version 1 or 2), preferring the
+/// `[Exact Tuning]` note table over the integer cents of `[Tuning]`
+pub struct TunReader;
+
+impl TuningReader for TunReader {
+    fn read_tuning(&self, bytes: &[u8]) -> Result<Tuning> {
+        let s = String::from_utf8_lossy(bytes);
+
+        let mut section = Section::Other;
+        let mut name = None;
+        let mut base_frequency = DEFAULT_BASE_FREQUENCY;
+        let mut tuning_cents = [None; 128];
+        let mut exact_cents = [None; 128];
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split(';').next().expect("Must have at least one item");
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = match header.trim().to_lowercase().as_str() {
+                    "info" => Section::Info,
+                    "tuning" => Section::Tuning,
+                    "exact tuning" => Section::ExactTuning,
+                    _ => Section::Other,
+                };
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+            let line_number = i + 1;
+
+            match (section, key.as_str()) {
+                (Section::Info, "name") => name = Some(String::from(value.trim_matches('"'))),
+                (Section::ExactTuning, "basefreq") => {
+                    base_frequency = value
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid BaseFreq at line {line_number}"))?
+                }
+                (Section::Tuning | Section::ExactTuning, key) if key.starts_with("note") => {
+                    let note = key["note".len()..]
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|n| *n < 128)
+                        .ok_or_else(|| anyhow!("Invalid note number at line {line_number}"))?;
+                    let cents = value
+                        .parse::<f64>()
+                        .map_err(|_| anyhow!("Invalid cents value at line {line_number}"))?;
+                    if section == Section::Tuning {
+                        tuning_cents[note] = Some(cents);
+                    } else {
+                        exact_cents[note] = Some(cents);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (cents, base_frequency) = if exact_cents.iter().any(Option::is_some) {
+            (exact_cents, base_frequency)
+        } else if tuning_cents.iter().any(Option::is_some) {
+            (tuning_cents, DEFAULT_BASE_FREQUENCY)
+        } else {
+            bail!("No [Tuning] or [Exact Tuning] note table found")
+        };
+
+        // Notes missing from the table keep their 12-EDO tuning
+        let frequencies = cents
+            .iter()
+            .enumerate()
+            .map(|(i, cents)| {
+                let cents = cents.unwrap_or(i as f64 * 100f64);
+                Frequency(base_frequency * 2f64.powf(cents / 1200f64))
+            })
+            .collect::<Vec<_>>();
+
+        Ok(
+            ImportedTuning::from_frequencies(name.as_deref().unwrap_or(""), &frequencies)?
+                .into_tuning(),
+        )
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &[
+            "Scale structure is inferred from the 128-note table",
+            "[Functional Tuning] sections are ignored in favour of the note table",
+        ]
+    }
+}
+
+/// Writes an AnaMark version 2 `.tun` file with both `[Tuning]` and
+/// `[Exact Tuning]` note tables
+pub struct TunWriter;
+
+impl TuningWriter for TunWriter {
+    fn write_tuning(&self, tuning: &Tuning) -> Result<String> {
+        let cents = tuning
+            .frequencies_or_12_edo()?
+            .iter()
+            .map(|f| 1200f64 * (f.0 / DEFAULT_BASE_FREQUENCY).log2())
+            .collect::<Vec<_>>();
+
+        let mut s = String::new();
+        writeln!(s, "; AnaMark tuning file")?;
+        writeln!(s, "; {}", tuning.description())?;
+        writeln!(s)?;
+        writeln!(s, "[Scale Begin]")?;
+        writeln!(s, "Format = \"AnaMark-TUN\"")?;
+        writeln!(s, "FormatVersion = 200")?;
+        writeln!(
+            s,
+            "FormatSpecs = \"http://www.mark-henning.de/eternity/tuningspecs.html\""
+        )?;
+        writeln!(s)?;
+        writeln!(s, "[Info]")?;
+        writeln!(s, "Name = \"{}\"", tuning.description().replace('"', "'"))?;
+        writeln!(s)?;
+        writeln!(s, "[Tuning]")?;
+        for (i, cents) in cents.iter().enumerate() {
+            writeln!(s, "note {i} = {}", cents.round() as i64)?;
+        }
+        writeln!(s)?;
+        writeln!(s, "[Exact Tuning]")?;
+        writeln!(s, "BaseFreq = {DEFAULT_BASE_FREQUENCY}")?;
+        for (i, cents) in cents.iter().enumerate() {
+            writeln!(s, "note {i} = {cents:.6}")?;
+        }
+        writeln!(s)?;
+        writeln!(s, "[Scale End]")?;
+        Ok(s)
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &[
+            "Scale intervals and equave are flattened into a 128-note table",
+            "Unmapped keys are written at their 12-EDO frequencies",
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::include_resource_str;
+    use crate::tun_file::{TunReader, TunWriter};
+    use crate::tuning_format::{SclReader, TuningReader, TuningWriter};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let tuning = SclReader::new(None)
+            .read_tuning(include_resource_str!("carlos_super.scl").as_bytes())?;
+        let s = TunWriter.write_tuning(&tuning)?;
+        let actual = TunReader.read_tuning(s.as_bytes())?;
+
+        assert_eq!(12, actual.scale().intervals().len());
+        assert_eq!("2/1", actual.scale().intervals()[11].to_string());
+        for (expected, actual) in tuning.frequencies()?.iter().zip(actual.frequencies()?) {
+            let expected = expected.expect("Must be mapped").0;
+            let actual = actual.expect("Must be mapped").0;
+            assert!((expected - actual).abs() < 0.0001f64);
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(440f64, "[Tuning]\nnote 69 = 6900\n")]
+    #[case(440f64, "[Exact Tuning]\nnote 69 = 6900.0\n[Tuning]\nnote 69 = 6899\n")]
+    #[case(
+        457.4476f64,
+        "; comment\n[Exact Tuning]\nbasefreq = 8.5 ; comment\nNOTE 69=6900\n"
+    )]
+    fn read(#[case] expected: f64, #[case] input: &str) -> Result<()> {
+        let tuning = TunReader.read_tuning(input.as_bytes())?;
+        let actual = tuning.frequencies()?[69].expect("Must be mapped").0;
+        assert!((expected - actual).abs() < 0.0001f64);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("[Tuning]\nnote 128 = 0\n")]
+    #[case("[Tuning]\nnote 0 = x\n")]
+    fn read_fails(#[case] input: &str) {
+        assert!(TunReader.read_tuning(input.as_bytes()).is_err());
+    }
+}
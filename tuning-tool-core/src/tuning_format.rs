@@ -0,0 +1,270 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::ascl_file::{AsclReader, AsclWriter};
+use crate::frequency::Frequency;
+use crate::frequency_list::{FrequencyListReader, FrequencyListWriter};
+use crate::kbm_file::KbmFile;
+use crate::key_frequency_mapping::compute_direct;
+use crate::keyboard_mapping::KeyboardMapping;
+use crate::midi_note::MidiNote;
+use crate::reference::Reference;
+use crate::scale::Scale;
+use crate::scl_file::SclFile;
+use crate::surge_patch::{SurgePatchReader, SurgePatchWriter};
+use crate::tun_file::{TunReader, TunWriter};
+use anyhow::{bail, Error, Result};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+/// Scale together with the keyboard mapping that places it on MIDI keys
+#[derive(Clone, Debug)]
+pub struct Tuning {
+    description: String,
+    scale: Scale,
+    keyboard_mapping: KeyboardMapping,
+}
+
+impl Tuning {
+    pub fn new(description: &str, scale: Scale, keyboard_mapping: KeyboardMapping) -> Self {
+        Self {
+            description: String::from(description),
+            scale,
+            keyboard_mapping,
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub const fn scale(&self) -> &Scale {
+        &self.scale
+    }
+
+    pub const fn keyboard_mapping(&self) -> &KeyboardMapping {
+        &self.keyboard_mapping
+    }
+
+    /// Frequency of each of the 128 MIDI keys, or `None` for unmapped keys
+    pub fn frequencies(&self) -> Result<Vec<Option<Frequency>>> {
+        let mut frequencies = vec![None; MidiNote::ALL.len()];
+        for mapping in compute_direct(&self.scale, &self.keyboard_mapping)? {
+            frequencies[mapping.key.to_u8() as usize] = Some(Frequency(mapping.frequency.0));
+        }
+        Ok(frequencies)
+    }
+
+    /// Frequency of each of the 128 MIDI keys with unmapped keys left at
+    /// their 12-EDO frequencies, for formats that need a value for every key
+    pub fn frequencies_or_12_edo(&self) -> Result<Vec<Frequency>> {
+        Ok(self
+            .frequencies()?
+            .into_iter()
+            .zip(MidiNote::ALL)
+            .map(|(frequency, note)| frequency.unwrap_or(note.frequency()))
+            .collect())
+    }
+}
+
+/// Reads a tuning from the contents of a file in some format
+pub trait TuningReader {
+    fn read_tuning(&self, bytes: &[u8]) -> Result<Tuning>;
+
+    /// Details of the file that do not survive reading into a `Tuning`
+    fn losses(&self) -> &'static [&'static str];
+}
+
+/// Writes a tuning as the contents of a file in some format
+pub trait TuningWriter {
+    fn write_tuning(&self, tuning: &Tuning) -> Result<String>;
+
+    /// Details of the `Tuning` that the format cannot represent
+    fn losses(&self) -> &'static [&'static str];
+}
+
+/// Tuning file format supported by `TuningReader` and `TuningWriter`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TuningFormat {
+    Scala,
+    AnaMark,
+    Ableton,
+    Surge,
+    Frequencies,
+}
+
+impl TuningFormat {
+    pub const ALL: [TuningFormat; 5] = [
+        Self::Scala,
+        Self::AnaMark,
+        Self::Ableton,
+        Self::Surge,
+        Self::Frequencies,
+    ];
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "scl" => Some(Self::Scala),
+            "tun" => Some(Self::AnaMark),
+            "ascl" => Some(Self::Ableton),
+            "fxp" | "xml" => Some(Self::Surge),
+            "txt" => Some(Self::Frequencies),
+            _ => None,
+        }
+    }
+
+    /// Reader for the format, with the keyboard mapping used for formats
+    /// that only describe a scale
+    pub fn reader(&self, keyboard_mapping: Option<KeyboardMapping>) -> Box<dyn TuningReader> {
+        match self {
+            Self::Scala => Box::new(SclReader::new(keyboard_mapping)),
+            Self::AnaMark => Box::new(TunReader),
+            Self::Ableton => Box::new(AsclReader),
+            Self::Surge => Box::new(SurgePatchReader),
+            Self::Frequencies => Box::new(FrequencyListReader),
+        }
+    }
+
+    pub fn writer(&self) -> Box<dyn TuningWriter> {
+        match self {
+            Self::Scala => Box::new(SclWriter),
+            Self::AnaMark => Box::new(TunWriter),
+            Self::Ableton => Box::new(AsclWriter),
+            Self::Surge => Box::new(SurgePatchWriter),
+            Self::Frequencies => Box::new(FrequencyListWriter),
+        }
+    }
+}
+
+impl Display for TuningFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Scala => write!(f, "scl"),
+            Self::AnaMark => write!(f, "tun"),
+            Self::Ableton => write!(f, "ascl"),
+            Self::Surge => write!(f, "surge"),
+            Self::Frequencies => write!(f, "frequencies"),
+        }
+    }
+}
+
+impl FromStr for TuningFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        for format in Self::ALL {
+            if s == format.to_string() {
+                return Ok(format);
+            }
+        }
+        bail!("Invalid tuning format {s}")
+    }
+}
+
+/// Reads a Scala `.scl` file, using the given keyboard mapping or a linear
+/// mapping with A4 at 440 Hz
+pub struct SclReader {
+    keyboard_mapping: Option<KeyboardMapping>,
+}
+
+impl SclReader {
+    pub const fn new(keyboard_mapping: Option<KeyboardMapping>) -> Self {
+        Self { keyboard_mapping }
+    }
+}
+
+impl TuningReader for SclReader {
+    fn read_tuning(&self, bytes: &[u8]) -> Result<Tuning> {
+        let scl_file = String::from_utf8_lossy(bytes).parse::<SclFile>()?;
+        let keyboard_mapping = match &self.keyboard_mapping {
+            Some(keyboard_mapping) => keyboard_mapping.clone(),
+            None => KeyboardMapping::new_full_linear(&Reference::default())?,
+        };
+        Ok(Tuning::new(
+            scl_file.description(),
+            scl_file.scale().clone(),
+            keyboard_mapping,
+        ))
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &["Comments other than the file name are dropped"]
+    }
+}
+
+/// Writes the scale of a tuning as a Scala `.scl` file
+pub struct SclWriter;
+
+impl TuningWriter for SclWriter {
+    fn write_tuning(&self, tuning: &Tuning) -> Result<String> {
+        Ok(SclFile::new(None, tuning.description(), tuning.scale().clone()).to_string())
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &["The keyboard mapping is only kept if written to a separate .kbm file"]
+    }
+}
+
+/// Writes the keyboard mapping of a tuning as a Scala `.kbm` file
+pub struct KbmWriter;
+
+impl TuningWriter for KbmWriter {
+    fn write_tuning(&self, tuning: &Tuning) -> Result<String> {
+        Ok(KbmFile::new(
+            tuning.keyboard_mapping().clone(),
+            tuning.scale().intervals().len(),
+        )
+        .to_string())
+    }
+
+    fn losses(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::include_resource_str;
+    use crate::tuning_format::{SclReader, TuningFormat, TuningReader};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Some(TuningFormat::Scala), "scl")]
+    #[case(Some(TuningFormat::AnaMark), "TUN")]
+    #[case(Some(TuningFormat::Surge), "fxp")]
+    #[case(None, "kbm")]
+    fn from_extension(#[case] expected: Option<TuningFormat>, #[case] input: &str) {
+        assert_eq!(expected, TuningFormat::from_extension(input));
+    }
+
+    #[test]
+    fn frequencies() -> Result<()> {
+        let tuning = SclReader::new(None)
+            .read_tuning(include_resource_str!("carlos_super.scl").as_bytes())?;
+        let frequencies = tuning.frequencies()?;
+        assert_eq!(128, frequencies.len());
+        assert_eq!(Some(440f64), frequencies[69].map(|f| f.0));
+        Ok(())
+    }
+}
@@ -23,7 +23,7 @@
 macro_rules! f64_newtype {
     ($ident: ident, $vis: vis) => {
         #[derive(Clone, Copy, Debug, PartialEq)]
-        $vis struct $ident(pub(crate) f64);
+        $vis struct $ident(pub f64);
 
         impl std::fmt::Display for $ident {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -42,12 +42,12 @@ macro_rules! u7_newtype {
 }
 pub(crate) use u7_newtype;
 
-u7_newtype!(Char7, pub(crate));
-u7_newtype!(Checksum, pub(crate));
-u7_newtype!(ChunkSize, pub(crate));
-u7_newtype!(DeviceId, pub(crate));
-u7_newtype!(KeyNumber, pub(crate));
-u7_newtype!(Lsb, pub(crate));
-u7_newtype!(MidiValue, pub(crate));
-u7_newtype!(Msb, pub(crate));
-u7_newtype!(Preset, pub(crate));
+u7_newtype!(Char7, pub);
+u7_newtype!(Checksum, pub);
+u7_newtype!(ChunkSize, pub);
+u7_newtype!(DeviceId, pub);
+u7_newtype!(KeyNumber, pub);
+u7_newtype!(Lsb, pub);
+u7_newtype!(MidiValue, pub);
+u7_newtype!(Msb, pub);
+u7_newtype!(Preset, pub);
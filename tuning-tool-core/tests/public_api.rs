@@ -0,0 +1,162 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+// Exercises the crate only through its public API, as bindings and the
+// command-line tool use it
+#![cfg(feature = "fs")]
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tuning_tool_core::decoded_message::DecodedMessage;
+use tuning_tool_core::kbm_file::KbmFile;
+use tuning_tool_core::key_frequency_mapping::compute_direct;
+use tuning_tool_core::mts_messages::{
+    make_bulk_dump_message, make_note_change_entries, make_note_change_messages,
+};
+use tuning_tool_core::note_change_entry::NoteChangeEntry;
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::scl_file::SclFile;
+use tuning_tool_core::syx::{from_hex_dump, split_messages, to_hex_dump, EOX, SYSEX};
+use tuning_tool_core::tuning_format::Tuning;
+use tuning_tool_core::types::{ChunkSize, DeviceId, Preset};
+
+fn resource_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("resources")
+        .join("test")
+        .join(name)
+}
+
+fn read_22edo2() -> Result<Tuning> {
+    let scl_file = SclFile::read(resource_path("22edo2.scl"))?;
+    let kbm_file = KbmFile::read(resource_path("22edo2.kbm"))?;
+    Ok(Tuning::new(
+        scl_file.description(),
+        scl_file.scale().clone(),
+        kbm_file.keyboard_mapping().clone(),
+    ))
+}
+
+#[test]
+fn compute_direct_omits_unmapped_keys() -> Result<()> {
+    let tuning = read_22edo2()?;
+    let mappings = compute_direct(tuning.scale(), tuning.keyboard_mapping())?;
+    let frequencies = tuning.frequencies()?;
+
+    assert_eq!(128, frequencies.len());
+    assert!(mappings.len() < frequencies.len());
+    assert_eq!(
+        mappings.len(),
+        frequencies.iter().filter(|f| f.is_some()).count()
+    );
+    assert!(mappings
+        .windows(2)
+        .all(|w| w[0].key.to_u8() < w[1].key.to_u8()));
+    for mapping in &mappings {
+        let frequency = frequencies[mapping.key.to_u8() as usize].expect("Must be mapped");
+        assert_eq!(mapping.frequency.0, frequency.0);
+    }
+    Ok(())
+}
+
+#[test]
+fn note_changes_decode() -> Result<()> {
+    let tuning = read_22edo2()?;
+    let entries = make_note_change_entries(
+        tuning.scale(),
+        tuning.keyboard_mapping(),
+        OutOfRangePolicy::Clamp,
+    )?
+    .into_iter()
+    .map(|(_, entry)| entry)
+    .collect::<Vec<_>>();
+
+    let messages = make_note_change_messages(
+        DeviceId::ZERO,
+        Preset::ZERO,
+        &entries,
+        ChunkSize::constant::<16>(),
+    )?;
+    let syx = messages.concat();
+    let split = split_messages(&syx)?;
+    assert_eq!(messages.len(), split.len());
+
+    let mut decoded_entries = Vec::new();
+    for message in split {
+        assert_eq!(Some(&SYSEX), message.first());
+        assert_eq!(Some(&EOX), message.last());
+        let DecodedMessage::NoteChange { entries, .. } = DecodedMessage::decode(message)? else {
+            panic!("Must be note change");
+        };
+        decoded_entries.extend(entries);
+    }
+    assert_eq!(entries.len(), decoded_entries.len());
+    for (expected, actual) in entries.iter().zip(decoded_entries) {
+        assert_eq!(expected.key_number, actual.key_number);
+        assert_eq!(expected.mts, actual.mts);
+    }
+    Ok(())
+}
+
+#[test]
+fn bulk_dump_decodes() -> Result<()> {
+    let tuning = read_22edo2()?;
+    let entries = make_note_change_entries(
+        tuning.scale(),
+        tuning.keyboard_mapping(),
+        OutOfRangePolicy::Clamp,
+    )?
+    .into_iter()
+    .map(|(_, entry)| entry)
+    .collect::<Vec<NoteChangeEntry>>();
+
+    let message =
+        make_bulk_dump_message(DeviceId::ZERO, Preset::ZERO, "22edo2".parse()?, &entries)?;
+    let DecodedMessage::BulkDump {
+        name,
+        entries: decoded_entries,
+        checksum_valid,
+        ..
+    } = DecodedMessage::decode(&message)?
+    else {
+        panic!("Must be bulk dump");
+    };
+
+    assert!(checksum_valid);
+    assert_eq!("22edo2", name.trim_end());
+    for entry in &entries {
+        assert_eq!(
+            entry.mts.to_option(),
+            decoded_entries[entry.key_number.to_u8() as usize]
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn hex_dump_round_trip() -> Result<()> {
+    let bytes = from_hex_dump("F0 7E 00 7F 00 F7")?;
+    assert_eq!(vec![0xf0, 0x7e, 0x00, 0x7f, 0x00, 0xf7], bytes);
+    assert_eq!(bytes, from_hex_dump(&to_hex_dump(&bytes, None)?)?);
+    Ok(())
+}
@@ -0,0 +1,134 @@
+#ifndef TUNING_TOOL_H
+#define TUNING_TOOL_H
+
+/* Generated by cbindgen from tuning-tool-ffi: do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Number of MIDI keys in a frequency table
+#define TUNING_TOOL_KEY_COUNT 128
+
+// Length of a preset name including its NUL terminator
+#define TUNING_TOOL_NAME_SIZE 17
+
+// Result of a call
+typedef enum TuningToolStatus {
+  TUNING_TOOL_STATUS_OK = 0,
+  TUNING_TOOL_STATUS_NULL_ARGUMENT = 1,
+  TUNING_TOOL_STATUS_ERROR = 2,
+} TuningToolStatus;
+
+// Treatment of frequencies outside the range MTS can represent: functions
+// take it as `uint32_t` and fail on other values
+typedef enum TuningToolOutOfRange {
+  // Clamp to the nearest end of the MTS range
+  TUNING_TOOL_OUT_OF_RANGE_CLAMP = 0,
+  // Move by whole octaves until the frequency is in range
+  TUNING_TOOL_OUT_OF_RANGE_FOLD = 1,
+  // Send the MTS "no change" value so that the key keeps its tuning
+  TUNING_TOOL_OUT_OF_RANGE_UNCHANGED = 2,
+  // Fail with `TUNING_TOOL_STATUS_ERROR`
+  TUNING_TOOL_OUT_OF_RANGE_FAIL = 3,
+} TuningToolOutOfRange;
+
+// Scale and keyboard mapping
+typedef struct TuningToolTuning TuningToolTuning;
+
+// Byte buffer owned by the library
+//
+// Initialize to `{ NULL, 0 }` before first use. Functions that fill a
+// buffer release its previous contents first, so a buffer can be reused
+// across calls without leaking; on failure, the buffer is left unchanged.
+// The caller must release the final contents with `tuning_tool_buffer_free`
+// and must not free `data` itself.
+typedef struct TuningToolBuffer {
+  uint8_t *data;
+  size_t len;
+} TuningToolBuffer;
+
+// Decoded MTS bulk tuning dump reply: frequencies are NaN for keys left
+// unchanged
+typedef struct TuningToolBulkDump {
+  uint8_t device_id;
+  uint8_t preset;
+  char name[TUNING_TOOL_NAME_SIZE];
+  double frequencies[TUNING_TOOL_KEY_COUNT];
+} TuningToolBulkDump;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns the message describing the most recent failure on the calling
+// thread or NULL
+//
+// The library owns the string: the caller must not free it. It remains
+// valid until the next failing call on the same thread, so copy it to keep
+// it.
+const char *tuning_tool_last_error(void);
+
+// Parses NUL-terminated `.scl` text and optional `.kbm` text: when `kbm` is
+// NULL, all 128 keys are mapped linearly with A4 at 440 Hz. The caller owns
+// the tuning written to `out` and must release it with
+// `tuning_tool_tuning_free`. The library does not keep `scl` or `kbm`.
+enum TuningToolStatus tuning_tool_tuning_new(const char *scl,
+                                             const char *kbm,
+                                             struct TuningToolTuning **out);
+
+// Releases a tuning created by `tuning_tool_tuning_new`: NULL is ignored.
+// The tuning must not be used afterwards.
+void tuning_tool_tuning_free(struct TuningToolTuning *tuning);
+
+// Writes the frequencies of all 128 keys to `out`: unmapped keys are NaN
+enum TuningToolStatus tuning_tool_tuning_frequencies(const struct TuningToolTuning *tuning,
+                                                     double (*out)[TUNING_TOOL_KEY_COUNT]);
+
+// Encodes the tuning as concatenated MTS single note tuning change
+// messages of at most `chunk_size` keys each, treating frequencies outside
+// the MTS range according to `out_of_range`
+enum TuningToolStatus tuning_tool_tuning_note_change_messages(const struct TuningToolTuning *tuning,
+                                                              uint8_t device_id,
+                                                              uint8_t preset,
+                                                              uint8_t chunk_size,
+                                                              uint32_t out_of_range,
+                                                              struct TuningToolBuffer *out);
+
+// Encodes the tuning as concatenated MTS single note tuning change with
+// bank select messages of at most `chunk_size` keys each, treating
+// frequencies outside the MTS range according to `out_of_range`
+enum TuningToolStatus tuning_tool_tuning_note_change_with_bank_messages(const struct TuningToolTuning *tuning,
+                                                                        uint8_t device_id,
+                                                                        uint8_t bank,
+                                                                        uint8_t preset,
+                                                                        uint8_t chunk_size,
+                                                                        uint32_t out_of_range,
+                                                                        struct TuningToolBuffer *out);
+
+// Encodes the tuning as an MTS bulk tuning dump reply message, treating
+// frequencies outside the MTS range according to `out_of_range`: `name` is
+// NUL-terminated ASCII of at most 16 characters
+enum TuningToolStatus tuning_tool_tuning_bulk_dump_message(const struct TuningToolTuning *tuning,
+                                                           uint8_t device_id,
+                                                           uint8_t preset,
+                                                           const char *name,
+                                                           uint32_t out_of_range,
+                                                           struct TuningToolBuffer *out);
+
+// Releases the contents of a buffer and resets it to `{ NULL, 0 }`: NULL
+// and empty buffers are ignored
+void tuning_tool_buffer_free(struct TuningToolBuffer *buffer);
+
+// Decodes an MTS bulk tuning dump reply message of `len` bytes
+enum TuningToolStatus tuning_tool_decode_bulk_dump(const uint8_t *data,
+                                                   size_t len,
+                                                   struct TuningToolBulkDump *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TUNING_TOOL_H */
@@ -0,0 +1,600 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+//! C ABI for embedding the tuning engine in audio plugins: see
+//! `include/tuning_tool.h`. Functions report failure through their status
+//! return value and `tuning_tool_last_error` describes the most recent
+//! failure on the calling thread. Pointer arguments must be NULL or valid
+//! for the access described by each function.
+
+#![allow(clippy::missing_safety_doc)]
+
+use anyhow::{bail, Error, Result};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::io::Read;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::null;
+use std::result::Result as StdResult;
+use std::slice::from_raw_parts;
+use tuning_tool_core::bulk_dump_reply::BulkDumpReply;
+use tuning_tool_core::kbm_file::KbmFile;
+use tuning_tool_core::key_frequency_mapping::compute_direct;
+use tuning_tool_core::keyboard_mapping::KeyboardMapping;
+use tuning_tool_core::mts_messages::{
+    make_bulk_dump_message, make_note_change_entries, make_note_change_messages,
+    make_note_change_with_bank_messages,
+};
+use tuning_tool_core::note_change_entry::NoteChangeEntry;
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::preset_name::PresetName;
+use tuning_tool_core::reference::Reference;
+use tuning_tool_core::scl_file::SclFile;
+use tuning_tool_core::types::{ChunkSize, DeviceId, MidiValue, Preset};
+
+/// Number of MIDI keys in a frequency table
+pub const TUNING_TOOL_KEY_COUNT: usize = 128;
+
+/// Length of a preset name including its NUL terminator
+pub const TUNING_TOOL_NAME_SIZE: usize = 17;
+
+const _: () = assert!(TUNING_TOOL_NAME_SIZE == PresetName::LEN + 1);
+
+/// Result of a call
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TuningToolStatus {
+    Ok = 0,
+    NullArgument = 1,
+    Error = 2,
+}
+
+/// Treatment of frequencies outside the range MTS can represent: functions
+/// take it as `uint32_t` and fail on other values
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TuningToolOutOfRange {
+    /// Clamp to the nearest end of the MTS range
+    Clamp = 0,
+    /// Move by whole octaves until the frequency is in range
+    Fold = 1,
+    /// Send the MTS "no change" value so that the key keeps its tuning
+    Unchanged = 2,
+    /// Fail with `TUNING_TOOL_STATUS_ERROR`
+    Fail = 3,
+}
+
+impl TryFrom<u32> for TuningToolOutOfRange {
+    type Error = Error;
+
+    fn try_from(value: u32) -> StdResult<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Clamp),
+            1 => Ok(Self::Fold),
+            2 => Ok(Self::Unchanged),
+            3 => Ok(Self::Fail),
+            _ => bail!("Invalid out-of-range policy {value}"),
+        }
+    }
+}
+
+impl From<TuningToolOutOfRange> for OutOfRangePolicy {
+    fn from(value: TuningToolOutOfRange) -> Self {
+        match value {
+            TuningToolOutOfRange::Clamp => Self::Clamp,
+            TuningToolOutOfRange::Fold => Self::Fold,
+            TuningToolOutOfRange::Unchanged => Self::Unchanged,
+            TuningToolOutOfRange::Fail => Self::Fail,
+        }
+    }
+}
+
+/// Scale and keyboard mapping
+pub struct TuningToolTuning {
+    scl_file: SclFile,
+    keyboard_mapping: KeyboardMapping,
+}
+
+/// Byte buffer owned by the library
+///
+/// Initialize to `{ NULL, 0 }` before first use. Functions that fill a
+/// buffer release its previous contents first, so a buffer can be reused
+/// across calls without leaking; on failure, the buffer is left unchanged.
+/// The caller must release the final contents with `tuning_tool_buffer_free`
+/// and must not free `data` itself.
+#[repr(C)]
+pub struct TuningToolBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// Decoded MTS bulk tuning dump reply: frequencies are NaN for keys left
+/// unchanged
+#[repr(C)]
+pub struct TuningToolBulkDump {
+    pub device_id: u8,
+    pub preset: u8,
+    pub name: [c_char; TUNING_TOOL_NAME_SIZE],
+    pub frequencies: [f64; TUNING_TOOL_KEY_COUNT],
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).expect("NUL bytes must be removed");
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+fn call<F: FnOnce() -> Result<()>>(f: F) -> TuningToolStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => TuningToolStatus::Ok,
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            TuningToolStatus::Error
+        }
+        Err(_) => {
+            set_last_error(String::from("Internal error"));
+            TuningToolStatus::Error
+        }
+    }
+}
+
+macro_rules! check_not_null {
+    ($($ptr: ident),+) => {
+        if $($ptr.is_null())||+ {
+            set_last_error(String::from("Required argument is null"));
+            return TuningToolStatus::NullArgument;
+        }
+    };
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Result<&'a str> {
+    Ok(CStr::from_ptr(s).to_str()?)
+}
+
+impl TuningToolTuning {
+    fn parse(scl: &str, kbm: Option<&str>) -> Result<Self> {
+        let scl_file = scl.parse::<SclFile>()?;
+        let keyboard_mapping = match kbm {
+            Some(kbm) => kbm.parse::<KbmFile>()?.keyboard_mapping().clone(),
+            None => KeyboardMapping::new_full_linear(&Reference::default())?,
+        };
+        Ok(Self {
+            scl_file,
+            keyboard_mapping,
+        })
+    }
+
+    fn entries(&self, out_of_range: u32) -> Result<Vec<NoteChangeEntry>> {
+        Ok(make_note_change_entries(
+            self.scl_file.scale(),
+            &self.keyboard_mapping,
+            TuningToolOutOfRange::try_from(out_of_range)?.into(),
+        )?
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .collect())
+    }
+}
+
+impl TuningToolBuffer {
+    unsafe fn set(&mut self, bytes: Vec<u8>) {
+        tuning_tool_buffer_free(self);
+        let bytes = bytes.into_boxed_slice();
+        self.len = bytes.len();
+        self.data = Box::into_raw(bytes).cast();
+    }
+}
+
+/// Returns the message describing the most recent failure on the calling
+/// thread or NULL
+///
+/// The library owns the string: the caller must not free it. It remains
+/// valid until the next failing call on the same thread, so copy it to keep
+/// it.
+#[no_mangle]
+pub extern "C" fn tuning_tool_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(null(), |s| s.as_ptr()))
+}
+
+/// Parses NUL-terminated `.scl` text and optional `.kbm` text: when `kbm` is
+/// NULL, all 128 keys are mapped linearly with A4 at 440 Hz. The caller owns
+/// the tuning written to `out` and must release it with
+/// `tuning_tool_tuning_free`. The library does not keep `scl` or `kbm`.
+#[no_mangle]
+pub unsafe extern "C" fn tuning_tool_tuning_new(
+    scl: *const c_char,
+    kbm: *const c_char,
+    out: *mut *mut TuningToolTuning,
+) -> TuningToolStatus {
+    check_not_null!(scl, out);
+    call(|| {
+        let kbm = if kbm.is_null() {
+            None
+        } else {
+            Some(to_str(kbm)?)
+        };
+        let tuning = TuningToolTuning::parse(to_str(scl)?, kbm)?;
+        *out = Box::into_raw(Box::new(tuning));
+        Ok(())
+    })
+}
+
+/// Releases a tuning created by `tuning_tool_tuning_new`: NULL is ignored.
+/// The tuning must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn tuning_tool_tuning_free(tuning: *mut TuningToolTuning) {
+    if !tuning.is_null() {
+        drop(Box::from_raw(tuning));
+    }
+}
+
+/// Writes the frequencies of all 128 keys to `out`: unmapped keys are NaN
+#[no_mangle]
+pub unsafe extern "C" fn tuning_tool_tuning_frequencies(
+    tuning: *const TuningToolTuning,
+    out: *mut [f64; TUNING_TOOL_KEY_COUNT],
+) -> TuningToolStatus {
+    check_not_null!(tuning, out);
+    call(|| {
+        let tuning = &*tuning;
+        let mut frequencies = [f64::NAN; TUNING_TOOL_KEY_COUNT];
+        for mapping in compute_direct(tuning.scl_file.scale(), &tuning.keyboard_mapping)? {
+            frequencies[mapping.key.to_u8() as usize] = mapping.frequency.0;
+        }
+        *out = frequencies;
+        Ok(())
+    })
+}
+
+/// Encodes the tuning as concatenated MTS single note tuning change
+/// messages of at most `chunk_size` keys each, treating frequencies outside
+/// the MTS range according to `out_of_range`
+#[no_mangle]
+pub unsafe extern "C" fn tuning_tool_tuning_note_change_messages(
+    tuning: *const TuningToolTuning,
+    device_id: u8,
+    preset: u8,
+    chunk_size: u8,
+    out_of_range: u32,
+    out: *mut TuningToolBuffer,
+) -> TuningToolStatus {
+    check_not_null!(tuning, out);
+    call(|| {
+        let messages = make_note_change_messages(
+            device_id.try_into()?,
+            preset.try_into()?,
+            &(*tuning).entries(out_of_range)?,
+            ChunkSize::try_from(chunk_size)?,
+        )?;
+        (*out).set(messages.concat());
+        Ok(())
+    })
+}
+
+/// Encodes the tuning as concatenated MTS single note tuning change with
+/// bank select messages of at most `chunk_size` keys each, treating
+/// frequencies outside the MTS range according to `out_of_range`
+#[no_mangle]
+pub unsafe extern "C" fn tuning_tool_tuning_note_change_with_bank_messages(
+    tuning: *const TuningToolTuning,
+    device_id: u8,
+    bank: u8,
+    preset: u8,
+    chunk_size: u8,
+    out_of_range: u32,
+    out: *mut TuningToolBuffer,
+) -> TuningToolStatus {
+    check_not_null!(tuning, out);
+    call(|| {
+        let messages = make_note_change_with_bank_messages(
+            DeviceId::try_from(device_id)?,
+            MidiValue::try_from(bank)?,
+            Preset::try_from(preset)?,
+            &(*tuning).entries(out_of_range)?,
+            ChunkSize::try_from(chunk_size)?,
+        )?;
+        (*out).set(messages.concat());
+        Ok(())
+    })
+}
+
+/// Encodes the tuning as an MTS bulk tuning dump reply message, treating
+/// frequencies outside the MTS range according to `out_of_range`: `name` is
+/// NUL-terminated ASCII of at most 16 characters
+#[no_mangle]
+pub unsafe extern "C" fn tuning_tool_tuning_bulk_dump_message(
+    tuning: *const TuningToolTuning,
+    device_id: u8,
+    preset: u8,
+    name: *const c_char,
+    out_of_range: u32,
+    out: *mut TuningToolBuffer,
+) -> TuningToolStatus {
+    check_not_null!(tuning, name, out);
+    call(|| {
+        let message = make_bulk_dump_message(
+            device_id.try_into()?,
+            preset.try_into()?,
+            to_str(name)?.parse()?,
+            &(*tuning).entries(out_of_range)?,
+        )?;
+        (*out).set(message);
+        Ok(())
+    })
+}
+
+/// Releases the contents of a buffer and resets it to `{ NULL, 0 }`: NULL
+/// and empty buffers are ignored
+#[no_mangle]
+pub unsafe extern "C" fn tuning_tool_buffer_free(buffer: *mut TuningToolBuffer) {
+    if buffer.is_null() || (*buffer).data.is_null() {
+        return;
+    }
+    let buffer = &mut *buffer;
+    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+        buffer.data,
+        buffer.len,
+    )));
+    buffer.data = std::ptr::null_mut();
+    buffer.len = 0;
+}
+
+/// Decodes an MTS bulk tuning dump reply message of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn tuning_tool_decode_bulk_dump(
+    data: *const u8,
+    len: usize,
+    out: *mut TuningToolBulkDump,
+) -> TuningToolStatus {
+    check_not_null!(data, out);
+    call(|| {
+        let bulk_dump_reply = BulkDumpReply::from_bytes(Read::bytes(from_raw_parts(data, len)))?;
+        let out = &mut *out;
+        out.device_id = bulk_dump_reply.device_id().to_u8();
+        out.preset = bulk_dump_reply.preset().to_u8();
+        out.name = [0; TUNING_TOOL_NAME_SIZE];
+        for (c, value) in out.name.iter_mut().zip(bulk_dump_reply.name().as_array()) {
+            *c = value.to_u8() as c_char;
+        }
+        for (f, e) in out
+            .frequencies
+            .iter_mut()
+            .zip(bulk_dump_reply.entries().iter())
+        {
+            *f = e.map_or(f64::NAN, |e| e.to_frequency().0);
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::ffi::CString;
+    use std::mem::MaybeUninit;
+    use std::path::Path;
+    use std::ptr::{null, null_mut};
+
+    const SCL: &str = "! 12edo.scl\n12-EDO\n12\n!\n100.\n200.\n300.\n400.\n500.\n600.\n700.\n800.\n900.\n1000.\n1100.\n2/1\n";
+
+    fn new_tuning(scl: &str) -> (TuningToolStatus, *mut TuningToolTuning) {
+        let scl = CString::new(scl).expect("must be valid C string");
+        let mut tuning = null_mut();
+        let status = unsafe { tuning_tool_tuning_new(scl.as_ptr(), null(), &mut tuning) };
+        (status, tuning)
+    }
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(tuning_tool_last_error()) }
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn frequencies() {
+        let (status, tuning) = new_tuning(SCL);
+        assert_eq!(TuningToolStatus::Ok, status);
+        let mut frequencies = [0f64; TUNING_TOOL_KEY_COUNT];
+        let status = unsafe { tuning_tool_tuning_frequencies(tuning, &mut frequencies) };
+        assert_eq!(TuningToolStatus::Ok, status);
+        assert_eq!(440f64, frequencies[69]);
+        unsafe { tuning_tool_tuning_free(tuning) };
+    }
+
+    #[test]
+    fn invalid_scale() {
+        let (status, tuning) = new_tuning("12-EDO\n");
+        assert_eq!(TuningToolStatus::Error, status);
+        assert!(tuning.is_null());
+        assert!(!last_error().is_empty());
+    }
+
+    #[test]
+    fn null_argument() {
+        let mut tuning = null_mut();
+        let status = unsafe { tuning_tool_tuning_new(null(), null(), &mut tuning) };
+        assert_eq!(TuningToolStatus::NullArgument, status);
+        assert_eq!("Required argument is null", last_error());
+    }
+
+    #[test]
+    fn note_change_messages() {
+        let (_, tuning) = new_tuning(SCL);
+        let mut buffer = TuningToolBuffer {
+            data: null_mut(),
+            len: 0,
+        };
+        let status = unsafe {
+            tuning_tool_tuning_note_change_messages(
+                tuning,
+                0,
+                8,
+                127,
+                TuningToolOutOfRange::Clamp as u32,
+                &mut buffer,
+            )
+        };
+        assert_eq!(TuningToolStatus::Ok, status);
+        let bytes = unsafe { from_raw_parts(buffer.data, buffer.len) };
+        assert_eq!(&[0xf0, 0x7f, 0x00, 0x08, 0x02, 0x08, 0x7f], &bytes[..7]);
+
+        // Reusing the buffer releases its previous contents
+        let len = buffer.len;
+        let status = unsafe {
+            tuning_tool_tuning_note_change_messages(
+                tuning,
+                0,
+                8,
+                1,
+                TuningToolOutOfRange::Clamp as u32,
+                &mut buffer,
+            )
+        };
+        assert_eq!(TuningToolStatus::Ok, status);
+        assert!(buffer.len > len);
+        let status = unsafe {
+            tuning_tool_tuning_note_change_messages(
+                tuning,
+                0,
+                8,
+                0,
+                TuningToolOutOfRange::Clamp as u32,
+                &mut buffer,
+            )
+        };
+        assert_eq!(TuningToolStatus::Error, status);
+        unsafe {
+            tuning_tool_buffer_free(&mut buffer);
+            tuning_tool_tuning_free(tuning);
+        }
+        assert!(buffer.data.is_null());
+    }
+
+    #[test]
+    fn out_of_range() {
+        // Keys above the reference rise by two octaves per key
+        let (_, tuning) = new_tuning("! wide.scl\nWide\n1\n!\n4/1\n");
+        let mut buffer = TuningToolBuffer {
+            data: null_mut(),
+            len: 0,
+        };
+        let status = unsafe {
+            tuning_tool_tuning_note_change_messages(
+                tuning,
+                0,
+                8,
+                127,
+                TuningToolOutOfRange::Fail as u32,
+                &mut buffer,
+            )
+        };
+        assert_eq!(TuningToolStatus::Error, status);
+        assert!(!last_error().is_empty());
+        let status = unsafe {
+            tuning_tool_tuning_note_change_messages(
+                tuning,
+                0,
+                8,
+                127,
+                TuningToolOutOfRange::Unchanged as u32,
+                &mut buffer,
+            )
+        };
+        assert_eq!(TuningToolStatus::Ok, status);
+        let status =
+            unsafe { tuning_tool_tuning_note_change_messages(tuning, 0, 8, 127, 4, &mut buffer) };
+        assert_eq!(TuningToolStatus::Error, status);
+        assert_eq!("Invalid out-of-range policy 4", last_error());
+        unsafe {
+            tuning_tool_buffer_free(&mut buffer);
+            tuning_tool_tuning_free(tuning);
+        }
+    }
+
+    #[test]
+    fn bulk_dump_round_trip() {
+        let (_, tuning) = new_tuning(SCL);
+        let name = CString::new("12-EDO").expect("must be valid C string");
+        let mut buffer = TuningToolBuffer {
+            data: null_mut(),
+            len: 0,
+        };
+        let status = unsafe {
+            tuning_tool_tuning_bulk_dump_message(
+                tuning,
+                0,
+                5,
+                name.as_ptr(),
+                TuningToolOutOfRange::Clamp as u32,
+                &mut buffer,
+            )
+        };
+        assert_eq!(TuningToolStatus::Ok, status);
+
+        let mut bulk_dump = MaybeUninit::<TuningToolBulkDump>::uninit();
+        let status = unsafe {
+            tuning_tool_decode_bulk_dump(buffer.data, buffer.len, bulk_dump.as_mut_ptr())
+        };
+        assert_eq!(TuningToolStatus::Ok, status);
+        let bulk_dump = unsafe { bulk_dump.assume_init() };
+        assert_eq!(5, bulk_dump.preset);
+        assert_eq!(
+            "12-EDO",
+            unsafe { CStr::from_ptr(bulk_dump.name.as_ptr()) }.to_string_lossy()
+        );
+        assert!((440f64 - bulk_dump.frequencies[69]).abs() < 0.01f64);
+
+        let mut truncated = MaybeUninit::<TuningToolBulkDump>::uninit();
+        let status =
+            unsafe { tuning_tool_decode_bulk_dump(buffer.data, 10, truncated.as_mut_ptr()) };
+        assert_eq!(TuningToolStatus::Error, status);
+        unsafe {
+            tuning_tool_buffer_free(&mut buffer);
+            tuning_tool_tuning_free(tuning);
+        }
+    }
+
+    #[test]
+    fn header_is_up_to_date() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut generated = Vec::new();
+        cbindgen::Builder::new()
+            .with_config(
+                cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+                    .expect("cbindgen.toml must be valid"),
+            )
+            .with_src(crate_dir.join("src").join("lib.rs"))
+            .generate()
+            .expect("header must be generated")
+            .write(&mut generated);
+        let header = std::fs::read_to_string(crate_dir.join("include").join("tuning_tool.h"))
+            .expect("header must exist");
+        assert_eq!(
+            header,
+            String::from_utf8(generated).expect("header must be UTF-8"),
+            "include/tuning_tool.h is out of date: regenerate with cbindgen"
+        );
+    }
+}
@@ -0,0 +1,399 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+//! Python bindings for the tuning engine: build with
+//! [maturin](https://www.maturin.rs/) and `import tuning_tool`.
+//!
+//! Functions return plain lists rather than numpy arrays so that numpy is not
+//! a dependency. Lists of frequencies always hold 128 floats, with NaN for
+//! unmapped or unchanged keys, so `numpy.array` converts them directly.
+
+use anyhow::{bail, Result};
+use pyo3::prelude::*;
+use std::io::Read;
+use std::path::PathBuf;
+use tuning_tool_core::bulk_dump_reply::BulkDumpReply;
+use tuning_tool_core::frequency::Frequency;
+use tuning_tool_core::kbm_file::KbmFile;
+use tuning_tool_core::key_frequency_mapping;
+use tuning_tool_core::keyboard_mapping::KeyboardMapping;
+use tuning_tool_core::mts_entry::MtsEntry;
+use tuning_tool_core::mts_messages::{
+    make_bulk_dump_message, make_note_change_entries, make_note_change_messages,
+    make_note_change_with_bank_messages,
+};
+use tuning_tool_core::note_change_entry::NoteChangeEntry;
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::reference::Reference;
+use tuning_tool_core::scl_file::SclFile;
+use tuning_tool_core::types::{ChunkSize, DeviceId, MidiValue, Preset};
+
+const KEY_COUNT: usize = 128;
+const DEFAULT_DEVICE_ID: u8 = 0;
+const DEFAULT_PRESET: u8 = 8;
+const DEFAULT_CHUNK_SIZE: u8 = 1;
+const DEFAULT_OUT_OF_RANGE: &str = "clamp";
+
+/// Scala scale (`.scl`) file
+#[pyclass(name = "SclFile", frozen)]
+struct PySclFile(SclFile);
+
+#[pymethods]
+impl PySclFile {
+    #[staticmethod]
+    fn parse(s: &str) -> Result<Self> {
+        Ok(Self(s.parse()?))
+    }
+
+    #[staticmethod]
+    fn read(path: PathBuf) -> Result<Self> {
+        Ok(Self(SclFile::read(path)?))
+    }
+
+    #[getter]
+    fn description(&self) -> &str {
+        self.0.description()
+    }
+
+    #[getter]
+    fn intervals(&self) -> Vec<String> {
+        self.0
+            .scale()
+            .intervals()
+            .iter()
+            .map(|i| i.to_string())
+            .collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SclFile({:?})", self.0.description())
+    }
+}
+
+/// Scala keyboard mapping (`.kbm`) file
+#[pyclass(name = "KbmFile", frozen)]
+struct PyKbmFile(KbmFile);
+
+#[pymethods]
+impl PyKbmFile {
+    #[staticmethod]
+    fn parse(s: &str) -> Result<Self> {
+        Ok(Self(s.parse()?))
+    }
+
+    #[staticmethod]
+    fn read(path: PathBuf) -> Result<Self> {
+        Ok(Self(KbmFile::read(path)?))
+    }
+
+    #[getter]
+    fn start_key(&self) -> u8 {
+        self.0.keyboard_mapping().start_key().to_u8()
+    }
+
+    #[getter]
+    fn end_key(&self) -> u8 {
+        self.0.keyboard_mapping().end_key().to_u8()
+    }
+
+    #[getter]
+    fn zero_key(&self) -> u8 {
+        self.0.keyboard_mapping().reference().zero_key().to_u8()
+    }
+
+    #[getter]
+    fn reference_key(&self) -> u8 {
+        self.0
+            .keyboard_mapping()
+            .reference()
+            .reference_key()
+            .to_u8()
+    }
+
+    #[getter]
+    fn reference_frequency(&self) -> f64 {
+        self.0
+            .keyboard_mapping()
+            .reference()
+            .reference_frequency()
+            .0
+    }
+}
+
+/// Decoded MTS bulk tuning dump reply: frequencies are NaN for keys left
+/// unchanged, as in `compute_direct`
+#[pyclass(name = "BulkDump", frozen, get_all)]
+struct PyBulkDump {
+    device_id: u8,
+    preset: u8,
+    name: String,
+    frequencies: Vec<f64>,
+}
+
+fn keyboard_mapping(kbm_file: Option<PyRef<'_, PyKbmFile>>) -> Result<KeyboardMapping> {
+    match kbm_file {
+        Some(kbm_file) => Ok(kbm_file.0.keyboard_mapping().clone()),
+        None => KeyboardMapping::new_full_linear(&Reference::default()),
+    }
+}
+
+fn note_change_entries(
+    scl_file: &PySclFile,
+    kbm_file: Option<PyRef<'_, PyKbmFile>>,
+    out_of_range: &str,
+) -> Result<Vec<NoteChangeEntry>> {
+    Ok(make_note_change_entries(
+        scl_file.0.scale(),
+        &keyboard_mapping(kbm_file)?,
+        out_of_range.parse::<OutOfRangePolicy>()?,
+    )?
+    .into_iter()
+    .map(|(_, entry)| entry)
+    .collect())
+}
+
+/// Computes the frequencies of all 128 keys: unmapped keys are NaN so that
+/// the result can be passed straight to `numpy.array`
+#[pyfunction]
+#[pyo3(signature = (scl_file, kbm_file=None))]
+fn compute_direct(
+    scl_file: &PySclFile,
+    kbm_file: Option<PyRef<'_, PyKbmFile>>,
+) -> Result<Vec<f64>> {
+    let mut frequencies = vec![f64::NAN; KEY_COUNT];
+    for mapping in
+        key_frequency_mapping::compute_direct(scl_file.0.scale(), &keyboard_mapping(kbm_file)?)?
+    {
+        frequencies[mapping.key.to_u8() as usize] = mapping.frequency.0;
+    }
+    Ok(frequencies)
+}
+
+/// Computes exact frequency expressions of all 128 keys: unmapped keys are
+/// `None`
+#[pyfunction]
+#[pyo3(signature = (scl_file, kbm_file=None))]
+fn compute_symbolic(
+    scl_file: &PySclFile,
+    kbm_file: Option<PyRef<'_, PyKbmFile>>,
+) -> Result<Vec<Option<String>>> {
+    let mut frequencies = vec![None; KEY_COUNT];
+    for mapping in
+        key_frequency_mapping::compute_symbolic(scl_file.0.scale(), &keyboard_mapping(kbm_file)?)?
+    {
+        frequencies[mapping.key.to_u8() as usize] = Some(mapping.frequency.to_string());
+    }
+    Ok(frequencies)
+}
+
+/// Encodes a frequency as three MTS frequency data bytes: `out_of_range` is
+/// as for `note_change_messages`
+#[pyfunction]
+#[pyo3(signature = (frequency, out_of_range=DEFAULT_OUT_OF_RANGE))]
+fn frequency_to_mts(frequency: f64, out_of_range: &str) -> Result<Vec<u8>> {
+    let (mts_entry, _) = out_of_range
+        .parse::<OutOfRangePolicy>()?
+        .to_mts_entry(Frequency(frequency))?;
+    Ok(vec![
+        mts_entry.note_number.to_u8(),
+        mts_entry.msb.to_u8(),
+        mts_entry.lsb.to_u8(),
+    ])
+}
+
+/// Decodes three MTS frequency data bytes as a frequency
+#[pyfunction]
+fn mts_to_frequency(data: &[u8]) -> Result<f64> {
+    let [note_number, msb, lsb] = data else {
+        bail!("MTS frequency data must be exactly 3 bytes")
+    };
+    let mts_entry = MtsEntry {
+        note_number: (*note_number).try_into()?,
+        msb: (*msb).try_into()?,
+        lsb: (*lsb).try_into()?,
+    };
+    Ok(mts_entry.to_frequency().0)
+}
+
+/// Encodes the tuning as MTS single note tuning change SysEx messages, using
+/// the bank select variant when a bank is given: `out_of_range` is one of
+/// "clamp", "fold", "unchanged" or "fail"
+#[pyfunction]
+#[pyo3(signature = (
+    scl_file,
+    kbm_file=None,
+    device_id=DEFAULT_DEVICE_ID,
+    preset=DEFAULT_PRESET,
+    bank=None,
+    chunk_size=DEFAULT_CHUNK_SIZE,
+    out_of_range=DEFAULT_OUT_OF_RANGE
+))]
+fn note_change_messages(
+    scl_file: &PySclFile,
+    kbm_file: Option<PyRef<'_, PyKbmFile>>,
+    device_id: u8,
+    preset: u8,
+    bank: Option<u8>,
+    chunk_size: u8,
+    out_of_range: &str,
+) -> Result<Vec<Vec<u8>>> {
+    let device_id = DeviceId::try_from(device_id)?;
+    let preset = Preset::try_from(preset)?;
+    let chunk_size = ChunkSize::try_from(chunk_size)?;
+
+    let entries = note_change_entries(scl_file, kbm_file, out_of_range)?;
+    match bank {
+        Some(bank) => make_note_change_with_bank_messages(
+            device_id,
+            MidiValue::try_from(bank)?,
+            preset,
+            &entries,
+            chunk_size,
+        ),
+        None => make_note_change_messages(device_id, preset, &entries, chunk_size),
+    }
+}
+
+/// Encodes the tuning as an MTS bulk tuning dump reply SysEx message:
+/// `out_of_range` is as for `note_change_messages`
+#[pyfunction]
+#[pyo3(signature = (
+    scl_file,
+    kbm_file=None,
+    device_id=DEFAULT_DEVICE_ID,
+    preset=DEFAULT_PRESET,
+    name="",
+    out_of_range=DEFAULT_OUT_OF_RANGE
+))]
+fn bulk_dump_message(
+    scl_file: &PySclFile,
+    kbm_file: Option<PyRef<'_, PyKbmFile>>,
+    device_id: u8,
+    preset: u8,
+    name: &str,
+    out_of_range: &str,
+) -> Result<Vec<u8>> {
+    make_bulk_dump_message(
+        DeviceId::try_from(device_id)?,
+        Preset::try_from(preset)?,
+        name.parse()?,
+        &note_change_entries(scl_file, kbm_file, out_of_range)?,
+    )
+}
+
+/// Decodes an MTS bulk tuning dump reply SysEx message
+#[pyfunction]
+fn decode_bulk_dump(data: &[u8]) -> Result<PyBulkDump> {
+    let bulk_dump_reply = BulkDumpReply::from_bytes(Read::bytes(data))?;
+    Ok(PyBulkDump {
+        device_id: bulk_dump_reply.device_id().to_u8(),
+        preset: bulk_dump_reply.preset().to_u8(),
+        name: bulk_dump_reply
+            .name()
+            .to_string()
+            .trim_end_matches('\0')
+            .to_string(),
+        frequencies: bulk_dump_reply
+            .entries()
+            .iter()
+            .map(|e| e.map_or(f64::NAN, |e| e.to_frequency().0))
+            .collect(),
+    })
+}
+
+#[pymodule]
+fn tuning_tool(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySclFile>()?;
+    m.add_class::<PyKbmFile>()?;
+    m.add_class::<PyBulkDump>()?;
+    m.add_function(wrap_pyfunction!(compute_direct, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_symbolic, m)?)?;
+    m.add_function(wrap_pyfunction!(frequency_to_mts, m)?)?;
+    m.add_function(wrap_pyfunction!(mts_to_frequency, m)?)?;
+    m.add_function(wrap_pyfunction!(note_change_messages, m)?)?;
+    m.add_function(wrap_pyfunction!(bulk_dump_message, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_bulk_dump, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+    use pyo3::wrap_pymodule;
+    use std::ffi::CStr;
+    use std::path::Path;
+
+    // Calls the module as Python code would, checking that frequency lists
+    // hold floats with NaN for unmapped keys, as numpy.array expects
+    const SMOKE_TEST: &CStr = cr#"
+import math
+
+scl_file = tuning_tool.SclFile.read(scl_path)
+kbm_file = tuning_tool.KbmFile.read(kbm_path)
+
+frequencies = tuning_tool.compute_direct(scl_file, kbm_file)
+assert len(frequencies) == 128
+assert all(isinstance(f, float) for f in frequencies)
+assert any(math.isnan(f) for f in frequencies)
+assert not all(math.isnan(f) for f in frequencies)
+
+message = bytes(tuning_tool.bulk_dump_message(scl_file, kbm_file, name="22edo2"))
+bulk_dump = tuning_tool.decode_bulk_dump(message)
+assert bulk_dump.name == "22edo2"
+assert [math.isnan(f) for f in bulk_dump.frequencies] == [math.isnan(f) for f in frequencies]
+
+messages = tuning_tool.note_change_messages(scl_file, kbm_file, chunk_size=127)
+assert len(messages) == 1
+try:
+    tuning_tool.note_change_messages(scl_file, kbm_file, out_of_range="wrap")
+except RuntimeError:
+    pass
+else:
+    raise AssertionError("Invalid policy must be rejected")
+
+assert bytes(tuning_tool.frequency_to_mts(20000.0)) == bytes([127, 127, 126])
+assert bytes(tuning_tool.frequency_to_mts(20000.0, out_of_range="unchanged")) == bytes([127, 127, 127])
+try:
+    tuning_tool.frequency_to_mts(20000.0, out_of_range="fail")
+except RuntimeError:
+    pass
+else:
+    raise AssertionError("Out-of-range frequency must be rejected")
+"#;
+
+    #[test]
+    fn smoke() -> PyResult<()> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join("resources")
+            .join("test");
+        Python::initialize();
+        Python::attach(|py| {
+            let globals = PyDict::new(py);
+            globals.set_item("tuning_tool", wrap_pymodule!(crate::tuning_tool)(py))?;
+            globals.set_item("scl_path", dir.join("22edo2.scl"))?;
+            globals.set_item("kbm_path", dir.join("22edo2.kbm"))?;
+            py.run(SMOKE_TEST, Some(&globals), None)
+        })
+    }
+}
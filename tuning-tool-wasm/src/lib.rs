@@ -0,0 +1,226 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+//! WebAssembly bindings for browser-based tools: build with
+//! `wasm-pack build --target web tuning-tool-wasm`. SysEx byte arrays are
+//! identical to the `.syx` output of the command-line tool and can be passed
+//! directly to Web MIDI `MIDIOutput.send`.
+
+use anyhow::Result;
+use tuning_tool_core::kbm_file::KbmFile;
+use tuning_tool_core::key_frequency_mapping::compute_direct;
+use tuning_tool_core::keyboard_mapping::KeyboardMapping;
+use tuning_tool_core::mts_messages::{
+    make_bulk_dump_message, make_note_change_entries, make_note_change_messages,
+    make_note_change_with_bank_messages,
+};
+use tuning_tool_core::note_change_entry::NoteChangeEntry;
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::reference::Reference;
+use tuning_tool_core::scl_file::SclFile;
+use tuning_tool_core::types::{ChunkSize, DeviceId, MidiValue, Preset};
+use wasm_bindgen::prelude::*;
+
+const KEY_COUNT: usize = 128;
+
+fn to_js_error(e: anyhow::Error) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// Scale and keyboard mapping parsed from `.scl` and `.kbm` text
+#[wasm_bindgen]
+pub struct Tuning {
+    scl_file: SclFile,
+    keyboard_mapping: KeyboardMapping,
+}
+
+#[wasm_bindgen]
+impl Tuning {
+    /// Parses `.scl` text and optional `.kbm` text: without a keyboard
+    /// mapping, all 128 keys are mapped linearly with A4 at 440 Hz
+    #[wasm_bindgen(constructor)]
+    pub fn new(scl: &str, kbm: Option<String>) -> Result<Tuning, JsError> {
+        Self::parse(scl, kbm.as_deref()).map_err(to_js_error)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn description(&self) -> String {
+        self.scl_file.description().to_string()
+    }
+
+    /// Frequencies of all 128 keys: unmapped keys are NaN
+    pub fn frequencies(&self) -> Result<Vec<f64>, JsError> {
+        self.compute_frequencies().map_err(to_js_error)
+    }
+
+    /// Concatenated MTS single note tuning change messages, using the bank
+    /// select variant when a bank is given: `outOfRange` is one of "clamp"
+    /// (the default), "fold", "unchanged" or "fail"
+    #[wasm_bindgen(js_name = noteChangeMessages)]
+    pub fn note_change_messages(
+        &self,
+        device_id: u8,
+        preset: u8,
+        bank: Option<u8>,
+        chunk_size: u8,
+        out_of_range: Option<String>,
+    ) -> Result<Vec<u8>, JsError> {
+        self.make_note_change_messages(device_id, preset, bank, chunk_size, out_of_range.as_deref())
+            .map_err(to_js_error)
+    }
+
+    /// MTS bulk tuning dump reply message: `outOfRange` is as for
+    /// `noteChangeMessages`
+    #[wasm_bindgen(js_name = bulkDumpMessage)]
+    pub fn bulk_dump_message(
+        &self,
+        device_id: u8,
+        preset: u8,
+        name: &str,
+        out_of_range: Option<String>,
+    ) -> Result<Vec<u8>, JsError> {
+        self.make_bulk_dump_message(device_id, preset, name, out_of_range.as_deref())
+            .map_err(to_js_error)
+    }
+}
+
+impl Tuning {
+    fn parse(scl: &str, kbm: Option<&str>) -> Result<Self> {
+        let scl_file = scl.parse::<SclFile>()?;
+        let keyboard_mapping = match kbm {
+            Some(kbm) => kbm.parse::<KbmFile>()?.keyboard_mapping().clone(),
+            None => KeyboardMapping::new_full_linear(&Reference::default())?,
+        };
+        Ok(Self {
+            scl_file,
+            keyboard_mapping,
+        })
+    }
+
+    fn compute_frequencies(&self) -> Result<Vec<f64>> {
+        let mut frequencies = vec![f64::NAN; KEY_COUNT];
+        for mapping in compute_direct(self.scl_file.scale(), &self.keyboard_mapping)? {
+            frequencies[mapping.key.to_u8() as usize] = mapping.frequency.0;
+        }
+        Ok(frequencies)
+    }
+
+    fn entries(&self, out_of_range: Option<&str>) -> Result<Vec<NoteChangeEntry>> {
+        let policy = match out_of_range {
+            Some(s) => s.parse()?,
+            None => OutOfRangePolicy::default(),
+        };
+        Ok(
+            make_note_change_entries(self.scl_file.scale(), &self.keyboard_mapping, policy)?
+                .into_iter()
+                .map(|(_, entry)| entry)
+                .collect(),
+        )
+    }
+
+    fn make_note_change_messages(
+        &self,
+        device_id: u8,
+        preset: u8,
+        bank: Option<u8>,
+        chunk_size: u8,
+        out_of_range: Option<&str>,
+    ) -> Result<Vec<u8>> {
+        let device_id = DeviceId::try_from(device_id)?;
+        let preset = Preset::try_from(preset)?;
+        let chunk_size = ChunkSize::try_from(chunk_size)?;
+
+        let entries = self.entries(out_of_range)?;
+        let messages = match bank {
+            Some(bank) => make_note_change_with_bank_messages(
+                device_id,
+                MidiValue::try_from(bank)?,
+                preset,
+                &entries,
+                chunk_size,
+            )?,
+            None => make_note_change_messages(device_id, preset, &entries, chunk_size)?,
+        };
+        Ok(messages.concat())
+    }
+
+    fn make_bulk_dump_message(
+        &self,
+        device_id: u8,
+        preset: u8,
+        name: &str,
+        out_of_range: Option<&str>,
+    ) -> Result<Vec<u8>> {
+        make_bulk_dump_message(
+            DeviceId::try_from(device_id)?,
+            Preset::try_from(preset)?,
+            name.parse()?,
+            &self.entries(out_of_range)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tuning;
+    use anyhow::Result;
+
+    const SCL: &str = "! 12edo.scl\n12-EDO\n12\n!\n100.\n200.\n300.\n400.\n500.\n600.\n700.\n800.\n900.\n1000.\n1100.\n2/1\n";
+
+    #[test]
+    fn frequencies() -> Result<()> {
+        let tuning = Tuning::parse(SCL, None)?;
+        assert_eq!("12-EDO", tuning.scl_file.description());
+        let frequencies = tuning.compute_frequencies()?;
+        assert_eq!(128, frequencies.len());
+        assert_eq!(440f64, frequencies[69]);
+        Ok(())
+    }
+
+    #[test]
+    fn note_change_messages() -> Result<()> {
+        let tuning = Tuning::parse(SCL, None)?;
+        let bytes = tuning.make_note_change_messages(0, 8, None, 64, None)?;
+        assert_eq!(2, bytes.iter().filter(|b| **b == 0xf0).count());
+        assert_eq!(&[0xf0, 0x7f, 0x00, 0x08, 0x02, 0x08, 0x40], &bytes[..7]);
+        assert!(tuning
+            .make_note_change_messages(0, 8, None, 0, None)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_range() -> Result<()> {
+        // Keys above the reference rise by two octaves per key
+        let tuning = Tuning::parse("! wide.scl\nWide\n1\n!\n4/1\n", None)?;
+        assert!(tuning
+            .make_note_change_messages(0, 8, None, 127, Some("fail"))
+            .is_err());
+        assert!(tuning
+            .make_bulk_dump_message(0, 8, "Wide", Some("unchanged"))
+            .is_ok());
+        assert!(tuning
+            .make_note_change_messages(0, 8, None, 127, Some("wrap"))
+            .is_err());
+        Ok(())
+    }
+}
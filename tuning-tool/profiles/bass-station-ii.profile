@@ -0,0 +1,10 @@
+! Novation Bass Station II
+name = bass-station-ii
+description = Novation Bass Station II
+manufacturer-id = 00 20 29
+message-types = note-change
+presets = 0-127
+default-preset = 8
+max-chunk-size = 127
+message-delay-ms = 0
+commit = 00 33 00 48
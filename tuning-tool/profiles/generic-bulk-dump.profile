@@ -0,0 +1,8 @@
+! Any device accepting MIDI Tuning Standard bulk tuning dumps
+name = generic-bulk-dump
+description = Generic MTS device (bulk tuning dump)
+message-types = bulk-dump
+presets = 0-127
+default-preset = 0
+max-chunk-size = 127
+message-delay-ms = 0
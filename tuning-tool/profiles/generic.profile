@@ -0,0 +1,9 @@
+! Any device implementing MIDI Tuning Standard real-time single note tuning change
+name = generic
+description = Generic MTS device (real-time single note tuning change)
+message-types = note-change, note-change-bank, bulk-dump
+presets = 0-127
+banks = 0-127
+default-preset = 0
+max-chunk-size = 127
+message-delay-ms = 0
@@ -0,0 +1,9 @@
+! Arturia MicroFreak: one tuning, retuned in real time
+name = microfreak
+description = Arturia MicroFreak
+manufacturer-id = 00 20 6B
+message-types = note-change
+presets = 0
+default-preset = 0
+max-chunk-size = 127
+message-delay-ms = 0
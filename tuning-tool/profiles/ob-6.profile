@@ -0,0 +1,9 @@
+! Oberheim OB-6: bulk tuning dumps to alternative tunings 1-16 (presets 0-15)
+name = ob-6
+description = Oberheim OB-6
+manufacturer-id = 01
+message-types = bulk-dump
+presets = 0-15
+default-preset = 0
+max-chunk-size = 127
+message-delay-ms = 100
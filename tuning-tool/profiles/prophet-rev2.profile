@@ -0,0 +1,9 @@
+! Sequential Prophet Rev2: bulk tuning dumps to alternative tunings 1-16 (presets 0-15)
+name = prophet-rev2
+description = Sequential Prophet Rev2
+manufacturer-id = 01
+message-types = bulk-dump
+presets = 0-15
+default-preset = 0
+max-chunk-size = 127
+message-delay-ms = 100
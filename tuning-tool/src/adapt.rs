@@ -0,0 +1,212 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::adaptive_tuner::AdaptiveTuner;
+use crate::devices::{connect_midi_input, MidiPort};
+use crate::mpe_channels::MpeChannels;
+use crate::paced_output::PacedOutput;
+use crate::pacing::Pacing;
+use anyhow::{Error, Result};
+use log::error;
+use midly::live::LiveEvent;
+use midly::num::{u4, u7};
+use midly::{MidiMessage, PitchBend};
+use std::sync::mpsc::{channel, SendError, Sender};
+use tuning_tool_core::frequency::Frequency;
+use tuning_tool_core::mts_messages::make_note_change_messages;
+use tuning_tool_core::note_change_entry::NoteChangeEntry;
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::types::{ChunkSize, DeviceId, KeyNumber, Preset};
+
+enum Retuning {
+    Mts {
+        device_id: DeviceId,
+        preset: Preset,
+        out_of_range: OutOfRangePolicy,
+    },
+    PitchBend {
+        range: u8,
+        channels: MpeChannels,
+    },
+}
+
+struct Adapter {
+    tuner: AdaptiveTuner,
+    conn: PacedOutput,
+    retuning: Retuning,
+}
+
+impl Adapter {
+    fn handle(&mut self, bytes: &[u8]) -> Result<()> {
+        let Ok(LiveEvent::Midi { channel, message }) = LiveEvent::parse(bytes) else {
+            return self.conn.send(bytes);
+        };
+
+        match message {
+            MidiMessage::NoteOn { key, vel } if vel > 0 => {
+                match self.tuner.note_on(KeyNumber::try_from(key.as_int())?) {
+                    Some(frequency) => self.note_on(channel, key, vel, frequency),
+                    None => self.conn.send(bytes),
+                }
+            }
+            MidiMessage::NoteOn { key, .. } | MidiMessage::NoteOff { key, .. } => {
+                self.tuner.note_off(KeyNumber::try_from(key.as_int())?);
+                let member = match &mut self.retuning {
+                    Retuning::PitchBend { channels, .. } => channels.release(channel, key),
+                    Retuning::Mts { .. } => None,
+                };
+                self.send_on(member, channel, message, bytes)
+            }
+            MidiMessage::Aftertouch { key, .. } => {
+                let member = match &self.retuning {
+                    Retuning::PitchBend { channels, .. } => channels.find(channel, key),
+                    Retuning::Mts { .. } => None,
+                };
+                self.send_on(member, channel, message, bytes)
+            }
+            _ => self.conn.send(bytes),
+        }
+    }
+
+    fn note_on(&mut self, channel: u4, key: u7, vel: u7, frequency: Frequency) -> Result<()> {
+        match &mut self.retuning {
+            Retuning::PitchBend { range, channels } => {
+                let semitones = frequency.to_semitones_with_ignore_limit(true).0;
+                let bend = (semitones - key.as_int() as f64) / *range as f64;
+                let (member, stolen) = channels.allocate(channel, key);
+                self.bend_note(member, key, vel, bend, stolen)
+            }
+            Retuning::Mts {
+                device_id,
+                preset,
+                out_of_range,
+            } => {
+                let entry = NoteChangeEntry {
+                    key_number: KeyNumber::try_from(key.as_int())?,
+                    mts: out_of_range.to_mts_entry(frequency)?.0,
+                };
+                for message in
+                    make_note_change_messages(*device_id, *preset, &[entry], ChunkSize::ONE)?
+                {
+                    self.conn.send(&message)?;
+                }
+                self.send_message(channel, MidiMessage::NoteOn { key, vel })
+            }
+        }
+    }
+
+    fn bend_note(
+        &mut self,
+        member: u4,
+        key: u7,
+        vel: u7,
+        bend: f64,
+        stolen: Option<u7>,
+    ) -> Result<()> {
+        if let Some(stolen) = stolen {
+            self.tuner.note_off(KeyNumber::try_from(stolen.as_int())?);
+            self.send_message(
+                member,
+                MidiMessage::NoteOff {
+                    key: stolen,
+                    vel: u7::new(0),
+                },
+            )?;
+        }
+        self.send_message(
+            member,
+            MidiMessage::PitchBend {
+                bend: PitchBend::from_f64(bend.clamp(-1f64, 1f64)),
+            },
+        )?;
+        self.send_message(member, MidiMessage::NoteOn { key, vel })
+    }
+
+    // Sends message on member channel if note was allocated one, otherwise
+    // forwards it unchanged
+    fn send_on(
+        &mut self,
+        member: Option<u4>,
+        channel: u4,
+        message: MidiMessage,
+        bytes: &[u8],
+    ) -> Result<()> {
+        match member {
+            Some(member) if member != channel => self.send_message(member, message),
+            _ => self.conn.send(bytes),
+        }
+    }
+
+    fn send_message(&mut self, channel: u4, message: MidiMessage) -> Result<()> {
+        let mut bytes = Vec::new();
+        LiveEvent::Midi { channel, message }.write_std(&mut bytes)?;
+        self.conn.send(&bytes)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn adapt(
+    input_port: &MidiPort,
+    output_port: &MidiPort,
+    reference: Vec<Option<Frequency>>,
+    device_id: DeviceId,
+    preset: Preset,
+    max_drift: f64,
+    pitch_bend_range: Option<u8>,
+    out_of_range: OutOfRangePolicy,
+    pacing: &Pacing,
+) -> Result<()> {
+    fn callback_wrapper(_timestamp: u64, bytes: &[u8], data: &mut (Adapter, Sender<Error>)) {
+        let (adapter, tx) = data;
+        if let Err(e) = adapter.handle(bytes) {
+            if let Err(SendError(e)) = tx.send(e) {
+                error!("Failed to handle MIDI message: {e}");
+            }
+        }
+    }
+
+    let conn = PacedOutput::connect(output_port, pacing)?;
+
+    let retuning = match pitch_bend_range {
+        Some(range) => Retuning::PitchBend {
+            range,
+            channels: MpeChannels::new(),
+        },
+        None => Retuning::Mts {
+            device_id,
+            preset,
+            out_of_range,
+        },
+    };
+
+    let adapter = Adapter {
+        tuner: AdaptiveTuner::new(reference, max_drift)?,
+        conn,
+        retuning,
+    };
+
+    let (tx, rx) = channel();
+    let _conn = connect_midi_input(input_port, callback_wrapper, (adapter, tx))?;
+    let e = rx.recv()?;
+    println!("Failed with error {e:?}");
+    Ok(())
+}
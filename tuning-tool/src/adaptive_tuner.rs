@@ -0,0 +1,220 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use tuning_tool_core::frequency::Frequency;
+use tuning_tool_core::interval::Interval;
+use tuning_tool_core::midi_note::MidiNote;
+use tuning_tool_core::ratio::Ratio;
+use tuning_tool_core::types::KeyNumber;
+
+// 5-limit just intervals within the octave, including the octave itself
+const JUST_INTERVALS: [&str; 13] = [
+    "1/1", "16/15", "9/8", "6/5", "5/4", "4/3", "45/32", "3/2", "8/5", "5/3", "9/5", "15/8", "2/1",
+];
+
+fn cents(a: Frequency, b: Frequency) -> f64 {
+    1200f64 * (a.0 / b.0).log2()
+}
+
+// Retunes each incoming note so that it forms just intervals with the notes
+// already held, while keeping each note within a maximum drift (in cents) of
+// its frequency in the reference tuning
+pub(crate) struct AdaptiveTuner {
+    reference: Vec<Option<Frequency>>,
+    just_ratios: Vec<Ratio>,
+    max_drift: f64,
+    drift: f64,
+    held: BTreeMap<u8, Frequency>,
+}
+
+impl AdaptiveTuner {
+    // Reference is the frequency of each of the 128 MIDI keys, or None for
+    // keys that should be left alone
+    pub(crate) fn new(reference: Vec<Option<Frequency>>, max_drift: f64) -> Result<Self> {
+        let just_ratios = JUST_INTERVALS
+            .iter()
+            .map(|s| Ok(s.parse::<Interval>()?.as_ratio()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            reference,
+            just_ratios,
+            max_drift: max_drift.abs(),
+            drift: 0f64,
+            held: BTreeMap::new(),
+        })
+    }
+
+    // 12-EDO reference tuning used when no tuning is given
+    pub(crate) fn equal_temperament() -> Vec<Option<Frequency>> {
+        MidiNote::ALL.iter().map(|n| Some(n.frequency())).collect()
+    }
+
+    // Returns None for keys that have no frequency in the reference tuning
+    pub(crate) fn note_on(&mut self, key: KeyNumber) -> Option<Frequency> {
+        let reference_frequency = self.reference_frequency(key.to_u8())?;
+
+        let frequency = self
+            .held
+            .keys()
+            .filter(|k| **k != key.to_u8())
+            .filter_map(|k| self.just_target(key.to_u8(), *k))
+            .map(|candidate| (self.cost(key, candidate), candidate))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, candidate)| candidate)
+            .unwrap_or(reference_frequency * Ratio(2f64.powf(self.drift / 1200f64)));
+
+        self.drift = cents(frequency, reference_frequency).clamp(-self.max_drift, self.max_drift);
+        let frequency = reference_frequency * Ratio(2f64.powf(self.drift / 1200f64));
+        _ = self.held.insert(key.to_u8(), frequency);
+        Some(frequency)
+    }
+
+    pub(crate) fn note_off(&mut self, key: KeyNumber) {
+        _ = self.held.remove(&key.to_u8());
+    }
+
+    fn reference_frequency(&self, key: u8) -> Option<Frequency> {
+        self.reference.get(key as usize).copied().flatten()
+    }
+
+    // Just interval nearest to the interval between the two keys in the
+    // reference tuning
+    fn just_ratio(&self, interval: f64) -> Ratio {
+        let octaves = (interval / 1200f64).floor();
+        let reduced = interval - octaves * 1200f64;
+        let ratio = self
+            .just_ratios
+            .iter()
+            .min_by(|a, b| {
+                (1200f64 * a.0.log2() - reduced)
+                    .abs()
+                    .total_cmp(&(1200f64 * b.0.log2() - reduced).abs())
+            })
+            .expect("Just intervals must not be empty");
+        Ratio(ratio.0 * 2f64.powf(octaves))
+    }
+
+    // Frequency for key that is just against held key
+    fn just_target(&self, key: u8, held_key: u8) -> Option<Frequency> {
+        let interval = cents(
+            self.reference_frequency(key)?,
+            self.reference_frequency(held_key)?,
+        );
+        let held_frequency = self.held.get(&held_key)?;
+        Some(*held_frequency * self.just_ratio(interval))
+    }
+
+    // Total deviation in cents from just intonation against all held notes
+    fn cost(&self, key: KeyNumber, candidate: Frequency) -> f64 {
+        self.held
+            .keys()
+            .filter(|k| **k != key.to_u8())
+            .filter_map(|k| self.just_target(key.to_u8(), *k))
+            .map(|just| cents(candidate, just).abs())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adaptive_tuner::AdaptiveTuner;
+    use anyhow::Result;
+    use tuning_tool_core::frequency::Frequency;
+    use tuning_tool_core::midi_note::MidiNote;
+    use tuning_tool_core::types::KeyNumber;
+
+    const EPSILON: f64 = 0.000001f64;
+
+    fn equal_tuner(max_drift: f64) -> Result<AdaptiveTuner> {
+        AdaptiveTuner::new(AdaptiveTuner::equal_temperament(), max_drift)
+    }
+
+    #[test]
+    fn single_note_is_equal_tempered() -> Result<()> {
+        let mut tuner = equal_tuner(50f64)?;
+        let f = tuner.note_on(KeyNumber::constant::<69>()).unwrap();
+        assert!((440f64 - f.0).abs() < EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn major_triad_is_just() -> Result<()> {
+        let mut tuner = equal_tuner(50f64)?;
+        let c = tuner.note_on(KeyNumber::constant::<60>()).unwrap();
+        let e = tuner.note_on(KeyNumber::constant::<64>()).unwrap();
+        let g = tuner.note_on(KeyNumber::constant::<67>()).unwrap();
+        assert!((c.0 * 5f64 / 4f64 - e.0).abs() < EPSILON);
+        assert!((c.0 * 3f64 / 2f64 - g.0).abs() < EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn drift_is_limited() -> Result<()> {
+        let mut tuner = equal_tuner(0f64)?;
+        _ = tuner.note_on(KeyNumber::constant::<60>());
+        let e = tuner.note_on(KeyNumber::constant::<64>()).unwrap();
+        assert!((MidiNote::ALL[64].frequency().0 - e.0).abs() < EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn drift_carries_over_after_release() -> Result<()> {
+        let mut tuner = equal_tuner(50f64)?;
+        let c = tuner.note_on(KeyNumber::constant::<60>()).unwrap();
+        let e = tuner.note_on(KeyNumber::constant::<64>()).unwrap();
+        tuner.note_off(KeyNumber::constant::<60>());
+        tuner.note_off(KeyNumber::constant::<64>());
+        let e2 = tuner.note_on(KeyNumber::constant::<64>()).unwrap();
+        assert!((e.0 - e2.0).abs() < EPSILON);
+        assert!(e2.0 < c.0 * 2f64.powf(4f64 / 12f64));
+        Ok(())
+    }
+
+    #[test]
+    fn reference_tuning_is_used() -> Result<()> {
+        // 24-EDO: keys 60 and 68 are a major third (400 cents) apart
+        let reference = (0..128)
+            .map(|k| {
+                Some(Frequency(
+                    261.625565 * 2f64.powf((k as f64 - 60f64) / 24f64),
+                ))
+            })
+            .collect();
+        let mut tuner = AdaptiveTuner::new(reference, 50f64)?;
+        let c = tuner.note_on(KeyNumber::constant::<60>()).unwrap();
+        assert!((261.625565 - c.0).abs() < EPSILON);
+        let e = tuner.note_on(KeyNumber::constant::<68>()).unwrap();
+        assert!((c.0 * 5f64 / 4f64 - e.0).abs() < EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn unmapped_keys_are_ignored() -> Result<()> {
+        let mut reference = AdaptiveTuner::equal_temperament();
+        reference[61] = None;
+        let mut tuner = AdaptiveTuner::new(reference, 50f64)?;
+        assert!(tuner.note_on(KeyNumber::constant::<61>()).is_none());
+        Ok(())
+    }
+}
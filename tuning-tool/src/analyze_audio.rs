@@ -0,0 +1,267 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::{bail, Result};
+use hound::{SampleFormat, WavReader};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tuning_tool_core::frequency::Frequency;
+use tuning_tool_core::frequency_table::FrequencyTable;
+use tuning_tool_core::interval::Interval;
+use tuning_tool_core::midi_note::MidiNote;
+use tuning_tool_core::pitch_detection::{detect_pitch, split_notes};
+use tuning_tool_core::scale::Scale;
+use tuning_tool_core::scl_file::SclFile;
+use tuning_tool_core::types::KeyNumber;
+
+pub(crate) fn analyze_audio(
+    wav_paths: &[PathBuf],
+    onsets: bool,
+    table_path: &Option<PathBuf>,
+    start_key: KeyNumber,
+    scl_path: &Option<PathBuf>,
+    root: usize,
+    equave: &Interval,
+) -> Result<()> {
+    let mut frequencies = Vec::new();
+    for wav_path in wav_paths {
+        let (samples, sample_rate) = read_wav(wav_path)?;
+        let mut notes = Vec::new();
+        if onsets {
+            notes.extend(split_notes(&samples, sample_rate));
+        } else {
+            notes.push(0..samples.len());
+        }
+
+        for (i, note) in notes.into_iter().enumerate() {
+            let seconds = note.start as f64 / sample_rate as f64;
+            let Some(frequency) = detect_pitch(&samples[note], sample_rate) else {
+                println!(
+                    "{path} at {seconds:.2} s: no pitch detected",
+                    path = wav_path.display()
+                );
+                continue;
+            };
+
+            let semitones = frequency.to_semitones_with_ignore_limit(true).0;
+            let nearest = semitones.round().clamp(0f64, 127f64);
+            println!(
+                "{path} note {i} at {seconds:.2} s: {frequency:.3} Hz ({name} {cents:+.1} cents)",
+                path = wav_path.display(),
+                frequency = frequency.0,
+                name = MidiNote::ALL[nearest as usize].name(),
+                cents = (semitones - nearest) * 100f64
+            );
+            frequencies.push(frequency);
+        }
+    }
+
+    if frequencies.is_empty() {
+        bail!("No pitches detected")
+    }
+
+    if let Some(table_path) = table_path {
+        write_frequency_table(table_path, &frequencies, start_key)?;
+    }
+
+    if let Some(scl_path) = scl_path {
+        write_scale(scl_path, &frequencies, root, equave)?;
+    }
+
+    Ok(())
+}
+
+// Mixes all channels down to mono samples between -1 and 1
+fn read_wav(wav_path: &Path) -> Result<(Vec<f32>, u32)> {
+    let mut reader = WavReader::open(wav_path)?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+
+    let channels = spec.channels.max(1) as usize;
+    let mono = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    Ok((mono, spec.sample_rate))
+}
+
+// Places detected notes on consecutive keys, leaving other keys untouched
+fn write_frequency_table(
+    table_path: &Path,
+    frequencies: &[Frequency],
+    start_key: KeyNumber,
+) -> Result<()> {
+    let start = start_key.to_u8() as usize;
+    if start + frequencies.len() > MidiNote::ALL.len() {
+        bail!(
+            "{count} notes starting at key {start} do not fit on the keyboard",
+            count = frequencies.len()
+        )
+    }
+
+    let mut table = vec![None; MidiNote::ALL.len()];
+    for (i, frequency) in frequencies.iter().enumerate() {
+        table[start + i] = Some(*frequency);
+    }
+
+    let frequency_table = FrequencyTable::new("Measured with analyze-audio", table)?;
+    let mut f = File::create_new(table_path)?;
+    write!(f, "{frequency_table}")?;
+    println!(
+        "Wrote {count} notes from key {start} to {path}",
+        count = frequencies.len(),
+        path = table_path.display()
+    );
+    Ok(())
+}
+
+// Measured notes closer together than this, after folding into the equave,
+// are treated as the same scale degree
+const DUPLICATE_CENTS: f64 = 5f64;
+
+// Scale of the notes relative to the root, folded into the equave, which
+// is written as the period
+fn write_scale(
+    scl_path: &Path,
+    frequencies: &[Frequency],
+    root: usize,
+    equave: &Interval,
+) -> Result<()> {
+    let Some(root_frequency) = frequencies.get(root) else {
+        bail!(
+            "Root {root} is out of range: only {} notes detected",
+            frequencies.len()
+        )
+    };
+
+    let root_semitones = root_frequency.to_semitones_with_ignore_limit(true).0;
+    let cents = fold_into_equave(
+        frequencies
+            .iter()
+            .map(|f| (f.to_semitones_with_ignore_limit(true).0 - root_semitones) * 100f64),
+        equave,
+    )?;
+    if cents.is_empty() {
+        bail!("No notes detected apart from the root and its equaves")
+    }
+
+    let scale = Scale::new(
+        cents
+            .into_iter()
+            .map(Interval::from_cents)
+            .chain([equave.clone()])
+            .collect(),
+    )?;
+    let count = scale.intervals().len();
+    let scl_file = SclFile::new(
+        scl_path
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string()),
+        &format!(
+            "Measured with analyze-audio, root {:.3} Hz",
+            root_frequency.0
+        ),
+        scale,
+    );
+    let mut f = File::create_new(scl_path)?;
+    write!(f, "{scl_file}")?;
+    println!(
+        "Wrote {count}-note scale relative to {root_frequency:.3} Hz to {path}",
+        root_frequency = root_frequency.0,
+        path = scl_path.display()
+    );
+    Ok(())
+}
+
+// Reduces intervals in cents into the equave, dropping the root and its
+// equaves and merging near-duplicates into their mean, in ascending order
+fn fold_into_equave(cents: impl Iterator<Item = f64>, equave: &Interval) -> Result<Vec<f64>> {
+    let equave_cents = 1200f64 * equave.as_ratio().0.log2();
+    if !equave_cents.is_finite() || equave_cents <= DUPLICATE_CENTS {
+        bail!("Invalid equave {equave}")
+    }
+
+    let mut folded = cents
+        .map(|c| c.rem_euclid(equave_cents))
+        .filter(|c| *c > DUPLICATE_CENTS && *c < equave_cents - DUPLICATE_CENTS)
+        .collect::<Vec<_>>();
+    folded.sort_by(f64::total_cmp);
+
+    let mut groups = Vec::<Vec<f64>>::new();
+    for c in folded {
+        match groups.last_mut() {
+            Some(group) if c - group[0] < DUPLICATE_CENTS => group.push(c),
+            _ => groups.push(vec![c]),
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|group| group.iter().sum::<f64>() / group.len() as f64)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze_audio::fold_into_equave;
+    use anyhow::Result;
+    use rstest::rstest;
+    use tuning_tool_core::interval::Interval;
+
+    const EPSILON: f64 = 0.0001f64;
+
+    #[rstest]
+    #[case(vec![200f64, 400f64], vec![0f64, 200f64, 400f64, 1200f64], "2/1")]
+    #[case(vec![200f64, 400f64], vec![400f64, 1400f64, 200f64, 2400f64], "2/1")]
+    #[case(vec![200f64, 400f64], vec![-1000f64, -800f64], "2/1")]
+    #[case(vec![201f64, 400f64], vec![199f64, 203f64, 400f64], "2/1")]
+    #[case(vec![100f64, 1100f64], vec![100f64, 3001.955f64], "3/1")]
+    #[case(vec![100f64], vec![100f64, 1000f64, 1001f64], "1000.0")]
+    fn fold_into_equave_basics(
+        #[case] expected: Vec<f64>,
+        #[case] cents: Vec<f64>,
+        #[case] equave: &str,
+    ) -> Result<()> {
+        let actual = fold_into_equave(cents.into_iter(), &equave.parse::<Interval>()?)?;
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual) {
+            assert!((e - a).abs() < EPSILON, "{e} != {a}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fold_into_equave_invalid() -> Result<()> {
+        assert!(fold_into_equave([100f64].into_iter(), &"1/1".parse::<Interval>()?).is_err());
+        Ok(())
+    }
+}
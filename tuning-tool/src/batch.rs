@@ -0,0 +1,287 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::input_paths::InputPaths;
+use crate::keyboard_mapping_source::KeyboardMappingSource;
+use crate::scala_files::read_scl_file;
+use crate::send_tuning::make_preset_name;
+use crate::tuning_tool_args::BatchFormat;
+use anyhow::{bail, Result};
+use rayon::prelude::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, write};
+use std::path::{Path, PathBuf};
+use tuning_tool_core::frequency_list::FrequencyListWriter;
+use tuning_tool_core::mts_messages::{make_bulk_dump_message, make_note_change_entries};
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::tuning_format::{Tuning, TuningWriter};
+use tuning_tool_core::types::{DeviceId, Preset};
+
+struct BatchOptions<'a> {
+    keyboard_mapping_source: &'a KeyboardMappingSource,
+    format: &'a BatchFormat,
+    device_id: DeviceId,
+    preset: Preset,
+    out_of_range: OutOfRangePolicy,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn batch(
+    input: &str,
+    output_dir: &Path,
+    keyboard_mapping_source: &KeyboardMappingSource,
+    format: &BatchFormat,
+    device_id: DeviceId,
+    preset: Preset,
+    out_of_range: OutOfRangePolicy,
+    force: bool,
+) -> Result<()> {
+    let input_paths = InputPaths::find(input, &["scl"])?;
+    if input_paths.paths.is_empty() {
+        bail!("No .scl files match {input}")
+    }
+
+    let jobs = plan_output_paths(&input_paths, output_dir, extension(format), force)?;
+
+    let options = BatchOptions {
+        keyboard_mapping_source,
+        format,
+        device_id,
+        preset,
+        out_of_range,
+    };
+    let results = jobs
+        .par_iter()
+        .map(|(scl_path, output_path)| convert_scale(scl_path, output_path, &options))
+        .collect::<Vec<_>>();
+
+    let failures = jobs
+        .iter()
+        .zip(results)
+        .filter_map(|((scl_path, _), result)| result.err().map(|e| (scl_path, e)))
+        .collect::<Vec<_>>();
+
+    println!(
+        "Converted {count} of {total} scales into {output_dir}",
+        count = jobs.len() - failures.len(),
+        total = jobs.len(),
+        output_dir = output_dir.display()
+    );
+
+    if !failures.is_empty() {
+        println!("Failures:");
+        for (scl_path, e) in &failures {
+            println!("  {path}: {e:#}", path = scl_path.display());
+        }
+        bail!(
+            "{count} of {total} scales failed",
+            count = failures.len(),
+            total = jobs.len()
+        )
+    }
+
+    Ok(())
+}
+
+const fn extension(format: &BatchFormat) -> &'static str {
+    match format {
+        BatchFormat::Syx => "syx",
+        BatchFormat::Table => "txt",
+        BatchFormat::Json => "json",
+    }
+}
+
+// Mirrors the input directory structure below the output directory and
+// checks for clashing or existing output files before anything is written
+fn plan_output_paths(
+    input_paths: &InputPaths,
+    output_dir: &Path,
+    extension: &str,
+    force: bool,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let jobs = input_paths
+        .paths
+        .iter()
+        .map(|scl_path| {
+            let output_path = output_dir
+                .join(input_paths.relative_path(scl_path))
+                .with_extension(extension);
+            (scl_path.clone(), output_path)
+        })
+        .collect::<Vec<_>>();
+
+    let mut seen = HashMap::new();
+    for (scl_path, output_path) in &jobs {
+        let key = output_path.to_string_lossy().to_lowercase();
+        if let Some(other_scl_path) = seen.insert(key, scl_path) {
+            bail!(
+                "{path} and {other_path} would both be written to {output_path}",
+                path = other_scl_path.display(),
+                other_path = scl_path.display(),
+                output_path = output_path.display()
+            )
+        }
+    }
+
+    if !force {
+        let existing_count = jobs
+            .iter()
+            .filter(|(_, output_path)| output_path.exists())
+            .count();
+        if existing_count > 0 {
+            bail!(
+                "{existing_count} output file(s) already exist in {output_dir}: use --force to overwrite them",
+                output_dir = output_dir.display()
+            )
+        }
+    }
+
+    for (_, output_path) in &jobs {
+        if let Some(parent) = output_path.parent() {
+            create_dir_all(parent)?;
+        }
+    }
+
+    Ok(jobs)
+}
+
+fn convert_scale(scl_path: &Path, output_path: &Path, options: &BatchOptions) -> Result<()> {
+    let scl_file = read_scl_file(scl_path)?;
+    let scale = scl_file.scale();
+    let keyboard_mapping = options
+        .keyboard_mapping_source
+        .make_keyboard_mapping(scale)?;
+
+    let bytes = match options.format {
+        BatchFormat::Syx => {
+            let entries = make_note_change_entries(scale, &keyboard_mapping, options.out_of_range)?
+                .into_iter()
+                .map(|x| x.1)
+                .collect::<Vec<_>>();
+            make_bulk_dump_message(
+                options.device_id,
+                options.preset,
+                make_preset_name(scl_path)?,
+                &entries,
+            )?
+        }
+        BatchFormat::Table => {
+            let tuning = Tuning::new(scl_file.description(), scale.clone(), keyboard_mapping);
+            FrequencyListWriter.write_tuning(&tuning)?.into_bytes()
+        }
+        BatchFormat::Json => {
+            let tuning = Tuning::new(scl_file.description(), scale.clone(), keyboard_mapping);
+            let keys = tuning
+                .frequencies()?
+                .into_iter()
+                .enumerate()
+                .map(|(key, frequency)| {
+                    let Some(frequency) = frequency else {
+                        return Ok(json!({ "key": key }));
+                    };
+                    let (mts_entry, treatment) = options
+                        .out_of_range
+                        .to_mts_entry(frequency)
+                        .map_err(|e| e.context(format!("Key {key}")))?;
+                    Ok(json!({
+                        "key": key,
+                        "frequency": frequency.0,
+                        "mts": [
+                            mts_entry.note_number.to_u8(),
+                            mts_entry.msb.to_u8(),
+                            mts_entry.lsb.to_u8(),
+                        ],
+                        "out_of_range": treatment.map(|t| t.to_string()),
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let value = json!({
+                "description": scl_file.description(),
+                "interval_count": scale.intervals().len(),
+                "keys": keys,
+            });
+            format!("{value:#}\n").into_bytes()
+        }
+    };
+
+    write(output_path, bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batch::plan_output_paths;
+    use crate::input_paths::InputPaths;
+    use anyhow::Result;
+    use std::fs::{create_dir, write};
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn plan_output_paths_keeps_subdirectories() -> Result<()> {
+        let input_paths = InputPaths {
+            base_dir: PathBuf::from("/scales"),
+            paths: vec![
+                PathBuf::from("/scales/a/major.scl"),
+                PathBuf::from("/scales/b/major.scl"),
+            ],
+        };
+        let output_dir = tempdir()?;
+        let jobs = plan_output_paths(&input_paths, output_dir.path(), "syx", false)?;
+        assert_eq!(output_dir.path().join("a/major.syx"), jobs[0].1);
+        assert_eq!(output_dir.path().join("b/major.syx"), jobs[1].1);
+        assert!(output_dir.path().join("a").is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn plan_output_paths_rejects_collisions() -> Result<()> {
+        let input_paths = InputPaths {
+            base_dir: PathBuf::from("/scales"),
+            paths: vec![
+                PathBuf::from("/scales/major.scl"),
+                PathBuf::from("/scales/major.SCL"),
+            ],
+        };
+        let output_dir = tempdir()?;
+        assert!(plan_output_paths(&input_paths, output_dir.path(), "syx", true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn plan_output_paths_requires_force_to_overwrite() -> Result<()> {
+        let input_paths = InputPaths {
+            base_dir: PathBuf::from("/scales"),
+            paths: vec![PathBuf::from("/scales/sub/major.scl")],
+        };
+        let output_dir = tempdir()?;
+        create_dir(output_dir.path().join("sub"))?;
+        write(output_dir.path().join("sub/major.syx"), b"")?;
+        assert!(plan_output_paths(&input_paths, output_dir.path(), "syx", false).is_err());
+        assert_eq!(
+            1,
+            plan_output_paths(&input_paths, output_dir.path(), "syx", true)?.len()
+        );
+        Ok(())
+    }
+}
@@ -20,13 +20,14 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::frequency::Frequency;
-use crate::midi_note::MidiNote;
-use crate::reference::Reference;
-use crate::types::KeyNumber;
+use crate::preview::Chord;
 use path_absolutize::Absolutize;
 use std::path::PathBuf;
 use std::result::Result as StdResult;
+use tuning_tool_core::frequency::Frequency;
+use tuning_tool_core::midi_note::MidiNote;
+use tuning_tool_core::reference::Reference;
+use tuning_tool_core::types::KeyNumber;
 
 pub(crate) fn parse_absolute_path(s: &str) -> StdResult<PathBuf, String> {
     PathBuf::from(s)
@@ -35,15 +36,29 @@ pub(crate) fn parse_absolute_path(s: &str) -> StdResult<PathBuf, String> {
         .map(|p| p.to_path_buf())
 }
 
-pub(crate) fn parse_reference(s: &str) -> StdResult<Reference, String> {
-    fn parse_key_number(s: &str) -> StdResult<KeyNumber, String> {
-        if let Ok(midi_note) = s.parse::<MidiNote>() {
-            return Ok(KeyNumber::from_u8_lossy(midi_note.note_number().to_u8()));
-        }
+pub(crate) fn parse_key_number(s: &str) -> StdResult<KeyNumber, String> {
+    if let Ok(midi_note) = s.parse::<MidiNote>() {
+        return Ok(KeyNumber::from_u8_lossy(midi_note.note_number().to_u8()));
+    }
 
-        Err(format!("Invalid key {s}"))
+    Err(format!("Invalid key {s}"))
+}
+
+pub(crate) fn parse_chord(s: &str) -> StdResult<Chord, String> {
+    s.split(',')
+        .map(|key| parse_key_number(key.trim()))
+        .collect::<StdResult<Vec<_>, _>>()
+        .map(Chord)
+}
+
+pub(crate) fn parse_positive_f64(s: &str) -> StdResult<f64, String> {
+    match s.parse::<f64>() {
+        Ok(value) if value.is_finite() && value > 0f64 => Ok(value),
+        _ => Err(format!("Invalid positive number {s}")),
     }
+}
 
+pub(crate) fn parse_reference(s: &str) -> StdResult<Reference, String> {
     if s.to_lowercase() == "default" {
         Ok(Reference::default())
     } else {
@@ -70,11 +85,11 @@ pub(crate) fn parse_reference(s: &str) -> StdResult<Reference, String> {
 
 #[cfg(test)]
 mod tests {
-    use crate::cli::{parse_absolute_path, parse_reference};
-    use crate::frequency::Frequency;
-    use crate::reference::Reference;
-    use crate::types::KeyNumber;
+    use crate::cli::{parse_absolute_path, parse_chord, parse_positive_f64, parse_reference};
     use rstest::rstest;
+    use tuning_tool_core::frequency::Frequency;
+    use tuning_tool_core::reference::Reference;
+    use tuning_tool_core::types::KeyNumber;
 
     #[test]
     fn parse_absolute_path_basics() {
@@ -89,4 +104,36 @@ mod tests {
     fn parse_reference_basics(#[case] expected: Reference, #[case] input: &str) {
         assert_eq!(expected, parse_reference(input).expect("Must succeed"));
     }
+
+    #[rstest]
+    #[case(vec![60, 64, 67], "60,64,67")]
+    #[case(vec![60, 64, 67], "c4, e4, G4")]
+    #[case(vec![69], "a4")]
+    fn parse_chord_basics(#[case] expected: Vec<u8>, #[case] input: &str) {
+        let actual = parse_chord(input).expect("Must succeed");
+        assert_eq!(
+            expected,
+            actual.0.iter().map(|k| k.to_u8()).collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("60,,64")]
+    #[case("c4,h4")]
+    fn parse_chord_fails(#[case] input: &str) {
+        assert!(parse_chord(input).is_err());
+    }
+
+    #[rstest]
+    #[case(Some(0.5f64), "0.5")]
+    #[case(Some(2f64), "2")]
+    #[case(None, "0")]
+    #[case(None, "-1")]
+    #[case(None, "inf")]
+    #[case(None, "NaN")]
+    #[case(None, "abc")]
+    fn parse_positive_f64_basics(#[case] expected: Option<f64>, #[case] input: &str) {
+        assert_eq!(expected, parse_positive_f64(input).ok());
+    }
 }
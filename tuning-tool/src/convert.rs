@@ -0,0 +1,97 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::scala_files::read_kbm_file;
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs::{read, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tuning_tool_core::tuning_format::{KbmWriter, TuningFormat, TuningWriter};
+
+pub(crate) fn convert(
+    input_path: &Path,
+    output_path: &Path,
+    kbm_path: &Option<PathBuf>,
+    output_kbm_path: &Option<PathBuf>,
+    from: Option<TuningFormat>,
+    to: Option<TuningFormat>,
+) -> Result<()> {
+    let from = resolve_format(input_path, from)?;
+    let to = resolve_format(output_path, to)?;
+
+    if kbm_path.is_some() && from != TuningFormat::Scala {
+        bail!("--kbm only applies to {} input", TuningFormat::Scala)
+    }
+
+    let keyboard_mapping = kbm_path
+        .as_ref()
+        .map(|kbm_path| read_kbm_file(kbm_path).map(|f| f.keyboard_mapping().clone()))
+        .transpose()?;
+
+    let reader = from.reader(keyboard_mapping);
+    let tuning = reader
+        .read_tuning(&read(input_path)?)
+        .with_context(|| format!("Failed to parse {path}", path = input_path.display()))?;
+
+    let writer = to.writer();
+    let mut f = File::create_new(output_path)?;
+    write!(f, "{}", writer.write_tuning(&tuning)?)?;
+
+    if let Some(output_kbm_path) = output_kbm_path {
+        let mut f = File::create_new(output_kbm_path)?;
+        write!(f, "{}", KbmWriter.write_tuning(&tuning)?)?;
+    }
+
+    println!(
+        "Converted {input_path} ({from}) to {output_path} ({to})",
+        input_path = input_path.display(),
+        output_path = output_path.display()
+    );
+
+    // The .kbm file keeps the keyboard mapping that .scl output would lose
+    let writer_losses = if to == TuningFormat::Scala && output_kbm_path.is_some() {
+        &[]
+    } else {
+        writer.losses()
+    };
+    for loss in reader.losses().iter().chain(writer_losses) {
+        println!("Note: {loss}");
+    }
+
+    Ok(())
+}
+
+fn resolve_format(path: &Path, format: Option<TuningFormat>) -> Result<TuningFormat> {
+    if let Some(format) = format {
+        return Ok(format);
+    }
+
+    path.extension()
+        .and_then(|e| e.to_str())
+        .and_then(TuningFormat::from_extension)
+        .ok_or_else(|| {
+            anyhow!(
+                "Cannot determine tuning format of {path}: specify it with --from or --to",
+                path = path.display()
+            )
+        })
+}
@@ -20,11 +20,11 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::bulk_dump_reply::BulkDumpReply;
 use anyhow::Result;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use tuning_tool_core::bulk_dump_reply::BulkDumpReply;
 
 pub(crate) fn decode_bulk_dump(syx_path: &Path) -> Result<()> {
     let mut f = File::open(syx_path)?;
@@ -38,7 +38,10 @@ pub(crate) fn decode_bulk_dump(syx_path: &Path) -> Result<()> {
     );
     println!("Preset: {preset}", preset = bulk_dump_reply.preset());
     for (i, entry) in bulk_dump_reply.entries().iter().enumerate() {
-        println!("{i:>3}: {frequency} Hz", frequency = entry.to_frequency().0);
+        match entry {
+            Some(entry) => println!("{i:>3}: {frequency} Hz", frequency = entry.to_frequency().0),
+            None => println!("{i:>3}: no change"),
+        }
     }
     Ok(())
 }
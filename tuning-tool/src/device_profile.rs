@@ -0,0 +1,432 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::mts_message_type::MtsMessageType;
+use anyhow::{anyhow, bail, Error, Result};
+use log::trace;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::read_to_string;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+use std::time::Duration;
+use tuning_tool_core::syx::to_sysex;
+use tuning_tool_core::types::{ChunkSize, MidiValue, Preset};
+
+const BUILTIN_PROFILES: [&str; 7] = [
+    include_str!("../profiles/bass-station-ii.profile"),
+    include_str!("../profiles/generic.profile"),
+    include_str!("../profiles/generic-bulk-dump.profile"),
+    include_str!("../profiles/microfreak.profile"),
+    include_str!("../profiles/ob-6.profile"),
+    include_str!("../profiles/prophet-6.profile"),
+    include_str!("../profiles/prophet-rev2.profile"),
+];
+
+fn parse_range(s: &str) -> Result<RangeInclusive<u8>> {
+    let (start, end) = s.split_once('-').unwrap_or((s, s));
+    let start = start.trim().parse::<u8>()?;
+    let end = end.trim().parse::<u8>()?;
+    if start > end || end > 0x7f {
+        bail!("Invalid range {s}")
+    }
+    Ok(start..=end)
+}
+
+fn parse_midi_values(s: &str) -> Result<Vec<MidiValue>> {
+    s.split_whitespace()
+        .map(|t| Ok(MidiValue::try_from(u8::from_str_radix(t, 16)?)?))
+        .collect()
+}
+
+// Describes how to send tunings to a particular synth: the MTS message types
+// it understands, its preset and bank ranges, how fast it can receive SysEx
+// and how to commit received tunings to memory
+#[derive(Clone, Debug)]
+pub(crate) struct DeviceProfile {
+    name: String,
+    description: String,
+    manufacturer_id: Vec<MidiValue>,
+    message_types: Vec<MtsMessageType>,
+    presets: RangeInclusive<u8>,
+    banks: Option<RangeInclusive<u8>>,
+    default_preset: Preset,
+    max_chunk_size: ChunkSize,
+    message_delay: Duration,
+    commit: Option<Vec<MidiValue>>,
+}
+
+impl DeviceProfile {
+    pub(crate) fn builtins() -> Result<Vec<Self>> {
+        BUILTIN_PROFILES.iter().map(|s| s.parse()).collect()
+    }
+
+    pub(crate) fn find(name_or_path: &str) -> Result<Self> {
+        if let Some(profile) = Self::builtins()?
+            .into_iter()
+            .find(|p| p.name == name_or_path)
+        {
+            return Ok(profile);
+        }
+
+        let path = Path::new(name_or_path);
+        if path.is_file() {
+            return Self::read(path);
+        }
+
+        bail!("No device profile with name or path {name_or_path} found")
+    }
+
+    pub(crate) fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        trace!(
+            "Reading device profile {path}",
+            path = path.as_ref().display()
+        );
+        read_to_string(path)?.parse()
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub(crate) fn message_type(&self) -> MtsMessageType {
+        self.message_types[0]
+    }
+
+    // Giving a bank selects the bank form of the single note tuning change
+    // since no other message type can address one
+    pub(crate) fn message_type_for_bank(&self, bank: Option<MidiValue>) -> Result<MtsMessageType> {
+        let Some(bank) = bank else {
+            let message_type = self.message_type();
+            if message_type == MtsMessageType::NoteChangeWithBank {
+                self.check_bank(MidiValue::ZERO)?;
+            }
+            return Ok(message_type);
+        };
+
+        if !self
+            .message_types
+            .contains(&MtsMessageType::NoteChangeWithBank)
+        {
+            bail!(
+                "Device {name} does not support single note tuning changes with bank select: remove --bank",
+                name = self.name
+            )
+        }
+
+        self.check_bank(bank)?;
+        Ok(MtsMessageType::NoteChangeWithBank)
+    }
+
+    pub(crate) fn message_types(&self) -> &Vec<MtsMessageType> {
+        &self.message_types
+    }
+
+    pub(crate) const fn default_preset(&self) -> Preset {
+        self.default_preset
+    }
+
+    pub(crate) const fn message_delay(&self) -> Duration {
+        self.message_delay
+    }
+
+    pub(crate) fn check_preset(&self, preset: Preset) -> Result<()> {
+        if !self.presets.contains(&preset.to_u8()) {
+            bail!(
+                "Preset {preset} is not supported by device {name}: choose from {start}-{end}",
+                name = self.name,
+                start = self.presets.start(),
+                end = self.presets.end()
+            )
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_bank(&self, bank: MidiValue) -> Result<()> {
+        let Some(banks) = &self.banks else {
+            bail!("Device {name} does not support banks", name = self.name)
+        };
+
+        if !banks.contains(&bank.to_u8()) {
+            bail!(
+                "Bank {bank} is not supported by device {name}: choose from {start}-{end}",
+                name = self.name,
+                start = banks.start(),
+                end = banks.end()
+            )
+        }
+        Ok(())
+    }
+
+    pub(crate) fn chunk_size(&self, chunk_size: ChunkSize) -> ChunkSize {
+        if chunk_size.to_u8() > self.max_chunk_size.to_u8() {
+            self.max_chunk_size
+        } else {
+            chunk_size
+        }
+    }
+
+    // Only sent by save-tunings
+    #[cfg_attr(not(feature = "midi-io"), allow(dead_code))]
+    pub(crate) fn commit_message(&self) -> Result<Vec<u8>> {
+        let Some(commit) = &self.commit else {
+            bail!(
+                "Device {name} does not support committing tunings to memory",
+                name = self.name
+            )
+        };
+
+        let values = self
+            .manufacturer_id
+            .iter()
+            .chain(commit.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        to_sysex(&values)
+    }
+}
+
+impl Display for DeviceProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{name}: {description} ({message_types})",
+            name = self.name,
+            description = self.description,
+            message_types = self
+                .message_types
+                .iter()
+                .map(MtsMessageType::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl FromStr for DeviceProfile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        let mut name = None;
+        let mut description = None;
+        let mut manufacturer_id = Vec::new();
+        let mut message_types = Vec::new();
+        let mut presets = 0..=0x7f;
+        let mut banks = None;
+        let mut default_preset = Preset::ZERO;
+        let mut max_chunk_size = ChunkSize::MAX;
+        let mut message_delay = Duration::ZERO;
+        let mut commit = None;
+
+        for line in s.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with("!") {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("Invalid device profile line {line}")
+            };
+
+            let value = value.trim();
+            match key.trim() {
+                "name" => name = Some(String::from(value)),
+                "description" => description = Some(String::from(value)),
+                "manufacturer-id" => manufacturer_id = parse_midi_values(value)?,
+                "message-types" => {
+                    message_types = value
+                        .split(',')
+                        .map(|s| s.trim().parse())
+                        .collect::<Result<Vec<_>>>()?
+                }
+                "presets" => presets = parse_range(value)?,
+                "banks" => banks = Some(parse_range(value)?),
+                "default-preset" => default_preset = value.parse()?,
+                "max-chunk-size" => max_chunk_size = value.parse()?,
+                "message-delay-ms" => message_delay = Duration::from_millis(value.parse()?),
+                "commit" => commit = Some(parse_midi_values(value)?),
+                key => bail!("Unsupported device profile key {key}"),
+            }
+        }
+
+        let name = name.ok_or_else(|| anyhow!("Device profile has no name"))?;
+
+        if message_types.is_empty() {
+            bail!("Device profile {name} supports no MTS message types")
+        }
+
+        if message_types.contains(&MtsMessageType::NoteChangeWithBank) && banks.is_none() {
+            bail!("Device profile {name} supports banks but has no bank range")
+        }
+
+        if max_chunk_size == ChunkSize::ZERO {
+            bail!("Device profile {name} has invalid maximum chunk size")
+        }
+
+        if commit.is_some() && manufacturer_id.is_empty() {
+            bail!("Device profile {name} has commit message but no manufacturer ID")
+        }
+
+        Ok(Self {
+            description: description.unwrap_or_else(|| name.clone()),
+            name,
+            manufacturer_id,
+            message_types,
+            presets,
+            banks,
+            default_preset,
+            max_chunk_size,
+            message_delay,
+            commit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device_profile::DeviceProfile;
+    use crate::mts_message_type::MtsMessageType;
+    use anyhow::Result;
+    use rstest::rstest;
+    use std::time::Duration;
+    use tuning_tool_core::syx::from_hex_dump;
+    use tuning_tool_core::types::{ChunkSize, MidiValue, Preset};
+
+    #[test]
+    fn builtins() -> Result<()> {
+        let profiles = DeviceProfile::builtins()?;
+        assert_eq!(7, profiles.len());
+        Ok(())
+    }
+
+    #[test]
+    fn bass_station_ii() -> Result<()> {
+        let profile = DeviceProfile::find("bass-station-ii")?;
+        assert_eq!("bass-station-ii", profile.name());
+        assert_eq!(MtsMessageType::NoteChange, profile.message_type());
+        assert_eq!(Preset::constant::<8>(), profile.default_preset());
+        assert_eq!(
+            from_hex_dump("F0 00 20 29 00 33 00 48 F7")?,
+            profile.commit_message()?
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("ob-6")]
+    #[case("prophet-6")]
+    #[case("prophet-rev2")]
+    fn sequential(#[case] name: &str) -> Result<()> {
+        let profile = DeviceProfile::find(name)?;
+        assert_eq!(MtsMessageType::BulkDump, profile.message_type());
+        assert!(profile.check_preset(Preset::constant::<15>()).is_ok());
+        assert!(profile.check_preset(Preset::constant::<16>()).is_err());
+        assert!(profile.commit_message().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn microfreak() -> Result<()> {
+        let profile = DeviceProfile::find("microfreak")?;
+        assert_eq!(MtsMessageType::NoteChange, profile.message_type());
+        assert!(profile.check_preset(Preset::ZERO).is_ok());
+        assert!(profile.check_preset(Preset::constant::<1>()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn limits() -> Result<()> {
+        let profile = "
+            name = test
+            message-types = note-change-bank, note-change
+            presets = 4-8
+            banks = 1
+            max-chunk-size = 16
+            message-delay-ms = 20
+        "
+        .parse::<DeviceProfile>()?;
+        assert_eq!(MtsMessageType::NoteChangeWithBank, profile.message_type());
+        assert!(profile.check_preset(Preset::constant::<4>()).is_ok());
+        assert!(profile.check_preset(Preset::constant::<9>()).is_err());
+        assert!(profile.check_bank(MidiValue::ONE).is_ok());
+        assert!(profile.check_bank(MidiValue::ZERO).is_err());
+        assert_eq!(
+            ChunkSize::constant::<16>(),
+            profile.chunk_size(ChunkSize::MAX)
+        );
+        assert_eq!(ChunkSize::ONE, profile.chunk_size(ChunkSize::ONE));
+        assert_eq!(Duration::from_millis(20), profile.message_delay());
+        assert!(profile.commit_message().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn message_type_for_bank() -> Result<()> {
+        let profile = DeviceProfile::find("bass-station-ii")?;
+        assert_eq!(
+            MtsMessageType::NoteChange,
+            profile.message_type_for_bank(None)?
+        );
+        assert!(profile.message_type_for_bank(Some(MidiValue::ONE)).is_err());
+
+        let profile = DeviceProfile::find("generic")?;
+        assert_eq!(
+            MtsMessageType::NoteChange,
+            profile.message_type_for_bank(None)?
+        );
+        assert_eq!(
+            MtsMessageType::NoteChangeWithBank,
+            profile.message_type_for_bank(Some(MidiValue::ONE))?
+        );
+
+        let profile = "
+            name = test
+            message-types = note-change-bank
+            banks = 1-2
+        "
+        .parse::<DeviceProfile>()?;
+        assert!(profile.message_type_for_bank(None).is_err());
+        assert!(profile.message_type_for_bank(Some(MidiValue::ONE)).is_ok());
+        assert!(profile
+            .message_type_for_bank(Some(MidiValue::ZERO))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("message-types = note-change"
+            .parse::<DeviceProfile>()
+            .is_err());
+        assert!("name = test".parse::<DeviceProfile>().is_err());
+        assert!("name = test\nmessage-types = note-change-bank"
+            .parse::<DeviceProfile>()
+            .is_err());
+        assert!("name = test\nmessage-types = note-change\ncommit = 01"
+            .parse::<DeviceProfile>()
+            .is_err());
+    }
+}
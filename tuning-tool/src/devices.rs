@@ -20,11 +20,48 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use anyhow::{bail, Result};
-use midir::{MidiInput, MidiInputPort, MidiOutput, MidiOutputPort};
+use crate::midi_input_ex::MidiInputEx;
+use crate::midi_output_ex::MidiOutputEx;
+use crate::port_selector::{select_port, PortAliases};
+#[cfg(not(unix))]
+use anyhow::bail;
+use anyhow::Result;
+use midir::{
+    MidiInput, MidiInputConnection, MidiInputPort, MidiOutput, MidiOutputConnection, MidiOutputPort,
+};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::stdin;
 
 const MIDI_INPUT_NAME: &str = "tuning-tool-midi-input";
 const MIDI_OUTPUT_NAME: &str = "tuning-tool-midi-output";
+const CONNECTION_NAME: &str = "tuning-tool";
+
+// Either an existing port to connect to or a virtual port that tuning-tool
+// creates for other software to connect to (ALSA, JACK and Core MIDI only)
+#[derive(Clone, Debug)]
+pub(crate) enum MidiPort {
+    Existing(String),
+    Virtual(String),
+}
+
+impl MidiPort {
+    pub(crate) fn new(name: &str, is_virtual: bool) -> Self {
+        if is_virtual {
+            Self::Virtual(String::from(name))
+        } else {
+            Self::Existing(String::from(name))
+        }
+    }
+}
+
+impl Display for MidiPort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Existing(name) => write!(f, "{name}"),
+            Self::Virtual(name) => write!(f, "{name} (virtual)"),
+        }
+    }
+}
 
 pub(crate) fn make_midi_input() -> Result<MidiInput> {
     Ok(MidiInput::new(MIDI_INPUT_NAME)?)
@@ -34,30 +71,84 @@ pub(crate) fn make_midi_output() -> Result<MidiOutput> {
     Ok(MidiOutput::new(MIDI_OUTPUT_NAME)?)
 }
 
-pub(crate) fn get_midi_input_port(midi_input: &MidiInput, name: &str) -> Result<MidiInputPort> {
-    let mut names = Vec::new();
-    for p in midi_input.ports() {
-        let temp = midi_input.port_name(&p)?;
-        if midi_input.port_name(&p)? == name {
-            return Ok(p);
+// Ports sorted by name, as numbered by list-ports
+pub(crate) fn midi_input_ports(midi_input: &MidiInput) -> Result<Vec<(String, MidiInputPort)>> {
+    let mut ports = midi_input
+        .ports()
+        .into_iter()
+        .map(|p| Ok((midi_input.port_name(&p)?, p)))
+        .collect::<Result<Vec<_>>>()?;
+    ports.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(ports)
+}
+
+// Ports sorted by name, as numbered by list-ports
+pub(crate) fn midi_output_ports(midi_output: &MidiOutput) -> Result<Vec<(String, MidiOutputPort)>> {
+    let mut ports = midi_output
+        .ports()
+        .into_iter()
+        .map(|p| Ok((midi_output.port_name(&p)?, p)))
+        .collect::<Result<Vec<_>>>()?;
+    ports.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(ports)
+}
+
+fn get_midi_input_port(midi_input: &MidiInput, selector: &str) -> Result<MidiInputPort> {
+    let mut ports = midi_input_ports(midi_input)?;
+    let names = ports.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>();
+    let index = select_port("input", &names, selector, &PortAliases::read_default()?)?;
+    Ok(ports.swap_remove(index).1)
+}
+
+fn get_midi_output_port(midi_output: &MidiOutput, selector: &str) -> Result<MidiOutputPort> {
+    let mut ports = midi_output_ports(midi_output)?;
+    let names = ports.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>();
+    let index = select_port("output", &names, selector, &PortAliases::read_default()?)?;
+    Ok(ports.swap_remove(index).1)
+}
+
+pub(crate) fn connect_midi_input<F, T: Send>(
+    port: &MidiPort,
+    callback: F,
+    data: T,
+) -> Result<MidiInputConnection<T>>
+where
+    F: FnMut(u64, &[u8], &mut T) + Send + 'static,
+{
+    let midi_input = make_midi_input()?;
+    match port {
+        MidiPort::Existing(name) => {
+            let midi_input_port = get_midi_input_port(&midi_input, name)?;
+            Ok(midi_input.connect_ex(&midi_input_port, CONNECTION_NAME, callback, data)?)
         }
-        names.push(temp);
+        #[cfg(unix)]
+        MidiPort::Virtual(name) => Ok(midi_input.create_virtual_ex(name, callback, data)?),
+        #[cfg(not(unix))]
+        MidiPort::Virtual(_) => bail!("Virtual MIDI ports are not supported on this platform"),
     }
-
-    let s = names.join(", ");
-    bail!("No MIDI input port with name {name} found: choose from {s}");
 }
 
-pub(crate) fn get_midi_output_port(midi_output: &MidiOutput, name: &str) -> Result<MidiOutputPort> {
-    let mut names = Vec::new();
-    for p in midi_output.ports() {
-        let temp = midi_output.port_name(&p)?;
-        if midi_output.port_name(&p)? == name {
-            return Ok(p);
+pub(crate) fn connect_midi_output(port: &MidiPort) -> Result<MidiOutputConnection> {
+    let midi_output = make_midi_output()?;
+    match port {
+        MidiPort::Existing(name) => {
+            let midi_output_port = get_midi_output_port(&midi_output, name)?;
+            Ok(midi_output.connect_ex(&midi_output_port, CONNECTION_NAME)?)
         }
-        names.push(temp);
+        #[cfg(unix)]
+        MidiPort::Virtual(name) => Ok(midi_output.create_virtual_ex(name)?),
+        #[cfg(not(unix))]
+        MidiPort::Virtual(_) => bail!("Virtual MIDI ports are not supported on this platform"),
     }
+}
 
-    let s = names.join(", ");
-    bail!("No MIDI output port with name {name} found: choose from {s}");
+// Nothing is connected to a newly created virtual output port, so messages
+// sent straight away would be lost: wait until the user has connected the
+// receiving software
+pub(crate) fn wait_for_virtual_output(port: &MidiPort) -> Result<()> {
+    if let MidiPort::Virtual(_) = port {
+        eprintln!("Created MIDI output port {port}: connect to it, then press Enter to send");
+        stdin().read_line(&mut String::new())?;
+    }
+    Ok(())
 }
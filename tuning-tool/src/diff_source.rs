@@ -0,0 +1,259 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+#[cfg(feature = "midi-io")]
+use crate::devices::{connect_midi_input, connect_midi_output, MidiPort};
+use crate::send_tuning_output::SendTuningOutput;
+use crate::tuning_state::TuningState;
+use crate::tuning_tool_args::DiffSourceGroup;
+use anyhow::{bail, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+#[cfg(feature = "midi-io")]
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+#[cfg(feature = "midi-io")]
+use std::time::{Duration, Instant};
+use tuning_tool_core::bulk_dump_reply::BulkDumpReply;
+#[cfg(feature = "midi-io")]
+use tuning_tool_core::mts_messages::make_bulk_dump_request_message;
+use tuning_tool_core::types::{DeviceId, Preset};
+
+#[cfg(feature = "midi-io")]
+const BULK_DUMP_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) enum DiffSource {
+    StatePath(PathBuf),
+    BulkDumpPath(PathBuf),
+    Device(String),
+}
+
+// Sends a bulk dump request for the preset to the output port and waits for
+// the device's reply on the input port, ignoring any other messages
+#[cfg(feature = "midi-io")]
+fn request_bulk_dump(
+    output_port: &str,
+    input_port: &str,
+    device_id: DeviceId,
+    preset: Preset,
+) -> Result<BulkDumpReply> {
+    fn callback(_timestamp: u64, bytes: &[u8], tx: &mut Sender<Vec<u8>>) {
+        _ = tx.send(bytes.to_vec());
+    }
+
+    let (tx, rx) = channel();
+    let _input_conn = connect_midi_input(&MidiPort::new(input_port, false), callback, tx)?;
+    let mut output_conn = connect_midi_output(&MidiPort::new(output_port, false))?;
+    output_conn.send(&make_bulk_dump_request_message(device_id, preset)?)?;
+
+    let deadline = Instant::now() + BULK_DUMP_TIMEOUT;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let bytes = match rx.recv_timeout(remaining) {
+            Ok(bytes) => bytes,
+            Err(RecvTimeoutError::Timeout) => {
+                bail!("Timed out waiting for bulk dump from {input_port}")
+            }
+            Err(RecvTimeoutError::Disconnected) => bail!("Input port {input_port} disconnected"),
+        };
+        if let Ok(bulk_dump_reply) = BulkDumpReply::from_bytes((&bytes[..]).bytes()) {
+            return Ok(bulk_dump_reply);
+        }
+    }
+}
+
+#[cfg(not(feature = "midi-io"))]
+fn request_bulk_dump(
+    _output_port: &str,
+    _input_port: &str,
+    _device_id: DeviceId,
+    _preset: Preset,
+) -> Result<BulkDumpReply> {
+    bail!("Requesting bulk dump from device requires tuning-tool to be built with the midi-io feature")
+}
+
+fn check_bulk_dump_reply(
+    bulk_dump_reply: &BulkDumpReply,
+    source: &str,
+    device_id: DeviceId,
+    preset: Preset,
+) -> Result<()> {
+    if bulk_dump_reply.device_id() != device_id || bulk_dump_reply.preset() != preset {
+        bail!(
+            "Baseline dump {source} is for device {dump_device_id} preset {dump_preset}, not device {device_id} preset {preset}",
+            dump_device_id = bulk_dump_reply.device_id(),
+            dump_preset = bulk_dump_reply.preset()
+        )
+    }
+    Ok(())
+}
+
+impl DiffSource {
+    // State to compare the tuning being sent to the device and preset with:
+    // a baseline dump of another device or preset is an error, while a state
+    // file that only knows about other presets is merely reported; a device
+    // baseline is requested via the output the tuning is sent to
+    pub(crate) fn read_state(
+        &self,
+        device_id: DeviceId,
+        preset: Preset,
+        output: &SendTuningOutput,
+    ) -> Result<TuningState> {
+        match self {
+            Self::StatePath(state_path) => {
+                let state = TuningState::read(state_path)?;
+                if !state.is_empty() && !state.has_preset(device_id, preset) {
+                    eprintln!(
+                        "Warning: tuning state file {path} has no tuning for device {device_id} preset {preset}: sending all keys",
+                        path = state_path.display()
+                    );
+                }
+                Ok(state)
+            }
+            Self::BulkDumpPath(syx_path) => {
+                let mut f = File::open(syx_path)?;
+                let mut bytes = Vec::new();
+                f.read_to_end(&mut bytes)?;
+                let bulk_dump_reply = BulkDumpReply::from_bytes(bytes.bytes())?;
+                check_bulk_dump_reply(
+                    &bulk_dump_reply,
+                    &syx_path.display().to_string(),
+                    device_id,
+                    preset,
+                )?;
+                Ok(TuningState::from_bulk_dump_reply(&bulk_dump_reply))
+            }
+            Self::Device(input_port) => {
+                let SendTuningOutput::OutputPort {
+                    name: output_port, ..
+                } = output
+                else {
+                    bail!("--baseline requires --output to send the bulk dump request to")
+                };
+                let bulk_dump_reply =
+                    request_bulk_dump(output_port, input_port, device_id, preset)?;
+                check_bulk_dump_reply(
+                    &bulk_dump_reply,
+                    &format!("from {input_port}"),
+                    device_id,
+                    preset,
+                )?;
+                Ok(TuningState::from_bulk_dump_reply(&bulk_dump_reply))
+            }
+        }
+    }
+
+    pub(crate) fn state_path(&self) -> Option<&PathBuf> {
+        match self {
+            Self::StatePath(state_path) => Some(state_path),
+            Self::BulkDumpPath(_) | Self::Device(_) => None,
+        }
+    }
+}
+
+impl From<DiffSourceGroup> for Option<DiffSource> {
+    fn from(value: DiffSourceGroup) -> Self {
+        match (value.state_path, value.bulk_dump_path, value.baseline_port) {
+            (Some(state_path), None, None) => Some(DiffSource::StatePath(state_path)),
+            (None, Some(bulk_dump_path), None) => Some(DiffSource::BulkDumpPath(bulk_dump_path)),
+            (None, None, Some(baseline_port)) => Some(DiffSource::Device(baseline_port)),
+            (None, None, None) => None,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff_source::DiffSource;
+    use crate::send_tuning_output::SendTuningOutput;
+    use crate::tuning_state::TuningState;
+    use anyhow::Result;
+    use std::fs::write;
+    use tempfile::tempdir;
+    use tuning_tool_core::frequency::Frequency;
+    use tuning_tool_core::mts_messages::make_bulk_dump_message;
+    use tuning_tool_core::note_change_entry::NoteChangeEntry;
+    use tuning_tool_core::types::{DeviceId, KeyNumber, Preset};
+
+    #[test]
+    fn bulk_dump_for_other_preset() -> Result<()> {
+        let dir = tempdir()?;
+        let syx_path = dir.path().join("baseline.syx");
+        let entries = [NoteChangeEntry {
+            key_number: KeyNumber::constant::<69>(),
+            mts: Frequency(440f64).to_mts_entry()?,
+        }];
+        write(
+            &syx_path,
+            make_bulk_dump_message(
+                DeviceId::ZERO,
+                Preset::constant::<5>(),
+                "Baseline".parse()?,
+                &entries,
+            )?,
+        )?;
+
+        let diff_source = DiffSource::BulkDumpPath(syx_path);
+        let output = SendTuningOutput::Stdout;
+        let state = diff_source.read_state(DeviceId::ZERO, Preset::constant::<5>(), &output)?;
+        assert!(state
+            .changed_entries(DeviceId::ZERO, Preset::constant::<5>(), &entries)
+            .is_empty());
+        assert!(diff_source
+            .read_state(DeviceId::ZERO, Preset::constant::<8>(), &output)
+            .is_err());
+        assert!(diff_source
+            .read_state(DeviceId::ONE, Preset::constant::<5>(), &output)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn state_for_other_preset() -> Result<()> {
+        let dir = tempdir()?;
+        let state_path = dir.path().join("state.txt");
+        let diff_source = DiffSource::StatePath(state_path.clone());
+        let output = SendTuningOutput::Stdout;
+        assert!(diff_source
+            .read_state(DeviceId::ZERO, Preset::ZERO, &output)?
+            .is_empty());
+
+        write(&state_path, "0 5 69 69 0 0\n")?;
+        let state: TuningState = diff_source.read_state(DeviceId::ZERO, Preset::ZERO, &output)?;
+        assert!(state.has_preset(DeviceId::ZERO, Preset::constant::<5>()));
+        assert!(!state.has_preset(DeviceId::ZERO, Preset::ZERO));
+        Ok(())
+    }
+
+    #[test]
+    fn device_baseline_requires_output_port() {
+        let diff_source = DiffSource::Device(String::from("Synth"));
+        let e = diff_source
+            .read_state(DeviceId::ZERO, Preset::ZERO, &SendTuningOutput::Stdout)
+            .unwrap_err();
+        assert_eq!(
+            "--baseline requires --output to send the bulk dump request to",
+            e.to_string()
+        );
+    }
+}
@@ -20,90 +20,192 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::evaluation_strategy::Symbolic;
-use crate::key_frequency_mapping::{compute_symbolic, KeyFrequencyMapping};
 use crate::keyboard_mapping_source::KeyboardMappingSource;
-use crate::scl_file::SclFile;
+use crate::scala_files::read_scl_file;
+#[cfg(feature = "python")]
 use crate::sympy::Sympy;
+use crate::tuning_source::TuningSource;
 use crate::tuning_tool_args::DumpTuningTableFormat;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::fs::File;
 use std::io::{stdout, Write};
 use std::iter::zip;
 use std::path::{Path, PathBuf};
+use tuning_tool_core::evaluate::Evaluate;
+use tuning_tool_core::evaluation_strategy::Symbolic;
+use tuning_tool_core::frequency::Frequency;
+use tuning_tool_core::frequency_table::FrequencyTable;
+use tuning_tool_core::key_frequency_mapping::{compute_symbolic, KeyFrequencyMapping};
+use tuning_tool_core::midi_note::MidiNote;
+use tuning_tool_core::out_of_range::{OutOfRange, OutOfRangePolicy};
+use tuning_tool_core::types::KeyNumber;
+
+#[cfg(feature = "python")]
+fn simplify(mappings: &[KeyFrequencyMapping<Symbolic>]) -> Result<Vec<String>> {
+    let inputs = mappings
+        .iter()
+        .map(|m| m.frequency().to_string())
+        .collect::<Vec<_>>();
+    Sympy::new()?.simplify_vec(&inputs)
+}
+
+#[cfg(not(feature = "python"))]
+fn simplify(_mappings: &[KeyFrequencyMapping<Symbolic>]) -> Result<Vec<String>> {
+    bail!("--sympy requires tuning-tool to be built with the python feature")
+}
 
 pub(crate) fn dump_tuning_table(
-    scl_path: &Path,
-    keyboard_mapping_source: &KeyboardMappingSource,
+    tuning_source: &TuningSource,
     output_path: &Option<PathBuf>,
     format: DumpTuningTableFormat,
     sympy: bool,
+    out_of_range: OutOfRangePolicy,
 ) -> Result<()> {
-    fn dump(
-        out: &mut dyn Write,
-        scl_path: &Path,
-        keyboard_mapping_source: &KeyboardMappingSource,
-        mappings: &Vec<KeyFrequencyMapping<Symbolic>>,
-        format: DumpTuningTableFormat,
-        sympy: &Option<Sympy>,
-    ) -> Result<()> {
-        match format {
-            DumpTuningTableFormat::Brief => {
-                for mapping in mappings {
-                    writeln!(out, "{f}", f = mapping.frequency)?;
-                }
-            }
-            DumpTuningTableFormat::Detailed => {
-                writeln!(out, "# Scale file: {path}", path = scl_path.display())?;
-                writeln!(out, "# {keyboard_mapping_source}")?;
+    let mut out: Box<dyn Write> = match output_path {
+        Some(output_path) => Box::new(File::create_new(output_path)?),
+        None => Box::new(stdout()),
+    };
 
-                if let Some(sympy) = sympy {
-                    let inputs = mappings
-                        .iter()
-                        .map(|m| m.frequency().to_string())
-                        .collect::<Vec<_>>();
-                    let exprs = sympy.simplify_vec(&inputs)?;
-                    for (mapping, expr) in zip(mappings, exprs) {
-                        writeln!(out, "{mapping:<95}  {expr}", mapping = mapping.to_string())?;
-                    }
-                } else {
-                    for mapping in mappings {
-                        writeln!(out, "{mapping}")?;
-                    }
-                }
+    match tuning_source {
+        TuningSource::Scale {
+            scl_path,
+            keyboard_mapping_source,
+        } => {
+            let scl_file = read_scl_file(scl_path)?;
+            let scale = scl_file.scale();
+            let keyboard_mapping = keyboard_mapping_source.make_keyboard_mapping(scale)?;
+            let mappings = compute_symbolic(scale, &keyboard_mapping)?;
+            let treatments = mappings
+                .iter()
+                .map(|m| treatment(m.key, Frequency(m.frequency.as_f64()), out_of_range))
+                .collect::<Result<Vec<_>>>()?;
+            let simplified = match sympy {
+                true => Some(simplify(&mappings)?),
+                false => None,
+            };
+            dump_scale(
+                &mut out,
+                scl_path,
+                keyboard_mapping_source,
+                &mappings,
+                &treatments,
+                format,
+                &simplified,
+            )
+        }
+        TuningSource::FrequencyTable(table_path) => {
+            if sympy {
+                bail!("--sympy only applies to scales")
             }
+            let frequency_table = FrequencyTable::read(table_path)?;
+            let treatments = zip(KeyNumber::all(), frequency_table.frequencies())
+                .map(|(key, frequency)| {
+                    frequency
+                        .map(|frequency| treatment(key, frequency, out_of_range))
+                        .transpose()
+                        .map(Option::flatten)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            dump_frequency_table(&mut out, table_path, &frequency_table, &treatments, format)
         }
-        Ok(())
     }
+}
 
-    let sympy = match sympy {
-        true => Some(Sympy::new()?),
-        false => None,
-    };
+// Treatment of key's frequency if it is outside the MTS range
+fn treatment(
+    key: KeyNumber,
+    frequency: Frequency,
+    out_of_range: OutOfRangePolicy,
+) -> Result<Option<OutOfRange>> {
+    Ok(out_of_range
+        .to_mts_entry(frequency)
+        .map_err(|e| e.context(format!("Key {key}")))?
+        .1)
+}
 
-    let scl_file = SclFile::read(scl_path)?;
-    let scale = scl_file.scale();
-    let keyboard_mapping = keyboard_mapping_source.make_keyboard_mapping(scale)?;
-    let mappings = compute_symbolic(scale, &keyboard_mapping)?;
+fn suffix(treatment: &Option<OutOfRange>) -> String {
+    match treatment {
+        Some(treatment) => format!("  [{treatment}]"),
+        None => String::new(),
+    }
+}
 
-    match output_path {
-        Some(output_path) => dump(
-            &mut File::create_new(output_path)?,
-            scl_path,
-            keyboard_mapping_source,
-            &mappings,
-            format,
-            &sympy,
-        )?,
-        None => dump(
-            &mut stdout(),
-            scl_path,
-            keyboard_mapping_source,
-            &mappings,
-            format,
-            &sympy,
-        )?,
+fn dump_scale(
+    out: &mut dyn Write,
+    scl_path: &Path,
+    keyboard_mapping_source: &KeyboardMappingSource,
+    mappings: &Vec<KeyFrequencyMapping<Symbolic>>,
+    treatments: &[Option<OutOfRange>],
+    format: DumpTuningTableFormat,
+    simplified: &Option<Vec<String>>,
+) -> Result<()> {
+    match format {
+        DumpTuningTableFormat::Brief => {
+            for mapping in mappings {
+                writeln!(out, "{f}", f = mapping.frequency)?;
+            }
+        }
+        DumpTuningTableFormat::Detailed => {
+            writeln!(out, "# Scale file: {path}", path = scl_path.display())?;
+            writeln!(out, "# {keyboard_mapping_source}")?;
+
+            if let Some(exprs) = simplified {
+                for ((mapping, expr), treatment) in zip(zip(mappings, exprs), treatments) {
+                    writeln!(
+                        out,
+                        "{mapping:<95}  {expr}{suffix}",
+                        mapping = mapping.to_string(),
+                        suffix = suffix(treatment)
+                    )?;
+                }
+            } else {
+                for (mapping, treatment) in zip(mappings, treatments) {
+                    writeln!(out, "{mapping}{suffix}", suffix = suffix(treatment))?;
+                }
+            }
+        }
     }
+    Ok(())
+}
 
+fn dump_frequency_table(
+    out: &mut dyn Write,
+    table_path: &Path,
+    frequency_table: &FrequencyTable,
+    treatments: &[Option<OutOfRange>],
+    format: DumpTuningTableFormat,
+) -> Result<()> {
+    let frequencies = zip(
+        zip(&MidiNote::ALL, frequency_table.frequencies()),
+        treatments,
+    )
+    .filter_map(|((note, frequency), treatment)| {
+        frequency.map(|frequency| (note, frequency, treatment))
+    });
+    match format {
+        DumpTuningTableFormat::Brief => {
+            for (_, frequency, _) in frequencies {
+                writeln!(out, "{f}", f = frequency.0)?;
+            }
+        }
+        DumpTuningTableFormat::Detailed => {
+            writeln!(
+                out,
+                "# Frequency table: {path}",
+                path = table_path.display()
+            )?;
+            writeln!(out, "# {}", frequency_table.description())?;
+            for (note, frequency, treatment) in frequencies {
+                writeln!(
+                    out,
+                    "{key:<3}  {name:<4}  {f:>9.2} Hz{suffix}",
+                    key = note.note_number().to_u8(),
+                    name = note.name(),
+                    f = frequency.0,
+                    suffix = suffix(treatment)
+                )?;
+            }
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,92 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::syx_file::split_syx_file;
+use anyhow::{bail, Result};
+use std::fs::{read, File};
+use std::io::Write;
+use std::path::Path;
+use tuning_tool_core::decoded_message::DecodedMessage;
+use tuning_tool_core::dump_import::ImportedTuning;
+use tuning_tool_core::tuning_format::{KbmWriter, SclWriter, TuningWriter};
+
+pub(crate) fn import_dump(syx_path: &Path, scl_path: &Path, kbm_path: &Path) -> Result<()> {
+    let bytes = read(syx_path)?;
+    let dumps = split_syx_file(syx_path, &bytes)?
+        .into_iter()
+        .filter_map(|message| match DecodedMessage::decode(message) {
+            Ok(DecodedMessage::BulkDump {
+                name,
+                entries,
+                checksum_valid,
+                ..
+            }) => Some((name, entries, checksum_valid)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let [(name, entries, checksum_valid)] = dumps.as_slice() else {
+        bail!(
+            "Expected exactly one bulk dump in {syx_path}, found {count}",
+            syx_path = syx_path.display(),
+            count = dumps.len()
+        )
+    };
+
+    if !checksum_valid {
+        bail!("Bulk dump checksum validation failed")
+    }
+
+    let description = if name.trim().is_empty() {
+        format!("Imported from {}", syx_path.display())
+    } else {
+        name.trim().to_string()
+    };
+
+    let imported = ImportedTuning::from_entries(&description, entries)?;
+
+    let tuning = imported.tuning();
+    let mut f = File::create_new(scl_path)?;
+    write!(f, "{}", SclWriter.write_tuning(tuning)?)?;
+    let mut f = File::create_new(kbm_path)?;
+    write!(f, "{}", KbmWriter.write_tuning(tuning)?)?;
+
+    let intervals = tuning.scale().intervals();
+    if imported.is_periodic() {
+        println!(
+            "Found {count}-note scale with equave {equave}",
+            count = intervals.len(),
+            equave = intervals[intervals.len() - 1]
+        );
+    } else {
+        println!(
+            "No repeating pattern found: wrote {count}-note scale with linear mapping",
+            count = intervals.len()
+        );
+    }
+    println!(
+        "Reference: {reference}",
+        reference = tuning.keyboard_mapping().reference()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::Result;
+use glob::{glob, Pattern};
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+
+// Files matched by a command-line input together with the directory that
+// output paths should be made relative to
+pub(crate) struct InputPaths {
+    pub(crate) base_dir: PathBuf,
+    pub(crate) paths: Vec<PathBuf>,
+}
+
+impl InputPaths {
+    // A directory means every file with one of the given extensions directly
+    // inside it: anything else is treated as a glob pattern whose base
+    // directory is the part before the first wildcard
+    pub(crate) fn find(input: &str, extensions: &[&str]) -> Result<Self> {
+        let (base_dir, pattern) = if Path::new(input).is_dir() {
+            (
+                PathBuf::from(input),
+                format!("{dir}/*", dir = Pattern::escape(input)),
+            )
+        } else {
+            (glob_base_dir(input), input.to_string())
+        };
+
+        let mut paths = glob(&pattern)?
+            .filter_map(|entry| entry.ok())
+            .filter(|path| {
+                path.is_file()
+                    && extensions
+                        .iter()
+                        .any(|extension| has_extension(path, extension))
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        Ok(Self { base_dir, paths })
+    }
+
+    // Path relative to base directory, or just the file name if the path is
+    // somehow outside it
+    pub(crate) fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.base_dir)
+            .ok()
+            .filter(|p| p.components().next().is_some())
+            .or_else(|| path.file_name().map(Path::new))
+            .unwrap_or(path)
+    }
+}
+
+pub(crate) fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+// Directory of test resources shared by the workspace crates
+#[cfg(test)]
+pub(crate) fn test_resources_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("resources")
+        .join("test")
+}
+
+fn glob_base_dir(pattern: &str) -> PathBuf {
+    let path = Path::new(pattern);
+    let component_count = path.components().count();
+    path.components()
+        .take(component_count.saturating_sub(1))
+        .take_while(|c| match c {
+            Component::Normal(s) => !s.to_string_lossy().contains(GLOB_CHARS),
+            _ => true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_paths::{glob_base_dir, has_extension, test_resources_dir, InputPaths};
+    use anyhow::Result;
+    use rstest::rstest;
+    use std::path::{Path, PathBuf};
+
+    #[rstest]
+    #[case("scales", "scales/*.scl")]
+    #[case("scales", "scales/**/*.scl")]
+    #[case("/a/b", "/a/b/c*/d/*.scl")]
+    #[case("a", "a/b.scl")]
+    #[case("", "*.scl")]
+    fn glob_base_dir_basics(#[case] expected: &str, #[case] input: &str) {
+        assert_eq!(PathBuf::from(expected), glob_base_dir(input));
+    }
+
+    #[rstest]
+    #[case(true, "a.scl", "scl")]
+    #[case(true, "a.SCL", "scl")]
+    #[case(false, "a.kbm", "scl")]
+    #[case(false, "scl", "scl")]
+    fn has_extension_basics(#[case] expected: bool, #[case] path: &str, #[case] extension: &str) {
+        assert_eq!(expected, has_extension(Path::new(path), extension));
+    }
+
+    #[test]
+    fn find_directory_and_glob() -> Result<()> {
+        let dir = test_resources_dir();
+        let dir = dir.to_str().expect("Must be valid UTF-8");
+
+        let input_paths = InputPaths::find(dir, &["scl"])?;
+        assert!(input_paths
+            .paths
+            .iter()
+            .any(|p| p.ends_with("carlos_super.scl")));
+        assert!(input_paths.paths.iter().all(|p| has_extension(p, "scl")));
+        assert!(input_paths.paths.is_sorted());
+
+        let input_paths = InputPaths::find(dir, &["scl", "kbm"])?;
+        assert!(input_paths.paths.iter().any(|p| p.ends_with("22edo2.kbm")));
+        assert!(input_paths.paths.iter().any(|p| p.ends_with("22edo2.scl")));
+        assert!(!input_paths
+            .paths
+            .iter()
+            .any(|p| p.ends_with("carlos_super.syx")));
+
+        let input_paths = InputPaths::find(&format!("{dir}/carlos_*.scl"), &["scl"])?;
+        assert_eq!(1, input_paths.paths.len());
+        assert_eq!(
+            Path::new("carlos_super.scl"),
+            input_paths.relative_path(&input_paths.paths[0])
+        );
+
+        assert!(InputPaths::find(&format!("{dir}/*.nothing"), &["scl"])?
+            .paths
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn find_recursive_glob_keeps_subdirectories() -> Result<()> {
+        let dir = test_resources_dir();
+        let dir = dir.to_str().expect("Must be valid UTF-8");
+
+        let input_paths =
+            InputPaths::find(&format!("{dir}/sevish-tunings-pack/**/*.scl"), &["scl"])?;
+        assert!(!input_paths.paths.is_empty());
+        assert!(input_paths.paths.iter().all(|p| input_paths
+            .relative_path(p)
+            .components()
+            .count()
+            > 1));
+        Ok(())
+    }
+}
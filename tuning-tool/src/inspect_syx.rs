@@ -0,0 +1,175 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::message_json::to_json;
+use crate::syx_file::split_syx_file;
+use anyhow::Result;
+use serde_json::json;
+use std::fs::read;
+use std::path::Path;
+use tuning_tool_core::decoded_message::DecodedMessage;
+use tuning_tool_core::syx::to_hex_dump;
+
+pub(crate) fn inspect_syx(syx_path: &Path, json: bool) -> Result<()> {
+    let bytes = read(syx_path)?;
+    let messages = split_syx_file(syx_path, &bytes)?;
+    for line in inspect(&bytes, &messages, json)? {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+// One JSON object per message, or a text description of each message
+// followed by a count
+fn inspect(bytes: &[u8], messages: &[&[u8]], json: bool) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for (i, message) in messages.iter().enumerate() {
+        // Stray bytes skipped between messages still count towards offsets
+        let offset = message.as_ptr() as usize - bytes.as_ptr() as usize;
+        let decoded = DecodedMessage::decode(message);
+        if json {
+            let mut value = match &decoded {
+                Ok(decoded) => to_json(decoded),
+                Err(e) => json!({
+                    "error": e.to_string(),
+                    "bytes": to_hex_dump(message, Some(message.len()))?,
+                }),
+            };
+            value["index"] = json!(i);
+            value["offset"] = json!(offset);
+            value["len"] = json!(message.len());
+            lines.push(value.to_string());
+        } else {
+            lines.push(format!(
+                "Message {i} at offset {offset} ({len} bytes)",
+                len = message.len()
+            ));
+            match decoded {
+                Ok(decoded) => lines.push(decoded.to_string()),
+                Err(e) => lines.push(format!(
+                    "Failed to decode message: {e}\n{hex_dump}",
+                    hex_dump = to_hex_dump(message, None)?
+                )),
+            }
+        }
+    }
+
+    if !json {
+        lines.push(format!("{count} message(s)", count = messages.len()));
+    }
+
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inspect_syx::inspect;
+    use anyhow::Result;
+    use serde_json::Value;
+    use tuning_tool_core::frequency::Frequency;
+    use tuning_tool_core::mts_messages::{make_bulk_dump_message, make_note_change_messages};
+    use tuning_tool_core::note_change_entry::NoteChangeEntry;
+    use tuning_tool_core::syx::split_messages;
+    use tuning_tool_core::types::{ChunkSize, DeviceId, KeyNumber, Preset};
+
+    // Two note changes and a bulk dump, as written by send-tuning and
+    // save-tunings
+    fn make_messages() -> Result<Vec<Vec<u8>>> {
+        let entries = [
+            NoteChangeEntry {
+                key_number: KeyNumber::constant::<60>(),
+                mts: Frequency(261.625565f64).to_mts_entry()?,
+            },
+            NoteChangeEntry {
+                key_number: KeyNumber::constant::<69>(),
+                mts: Frequency(440f64).to_mts_entry()?,
+            },
+        ];
+        let mut messages =
+            make_note_change_messages(DeviceId::ZERO, Preset::ZERO, &entries, ChunkSize::ONE)?;
+        messages.push(make_bulk_dump_message(
+            DeviceId::ZERO,
+            Preset::ZERO,
+            "Test".parse()?,
+            &entries,
+        )?);
+        Ok(messages)
+    }
+
+    fn parse_json(lines: &[String]) -> Result<Vec<Value>> {
+        Ok(lines
+            .iter()
+            .map(|line| serde_json::from_str(line))
+            .collect::<Result<Vec<Value>, _>>()?)
+    }
+
+    #[test]
+    fn multiple_messages() -> Result<()> {
+        let messages = make_messages()?;
+        let bytes = messages.concat();
+
+        let lines = inspect(&bytes, &split_messages(&bytes)?, false)?;
+        assert_eq!(Some(&String::from("3 message(s)")), lines.last());
+        assert!(lines.iter().all(|line| !line.contains("Failed")));
+
+        let values = parse_json(&inspect(&bytes, &split_messages(&bytes)?, true)?)?;
+        assert_eq!(3, values.len());
+        let mut offset = 0;
+        for (i, (value, message)) in values.iter().zip(&messages).enumerate() {
+            assert_eq!(i, value["index"]);
+            assert_eq!(offset, value["offset"]);
+            assert_eq!(message.len(), value["len"]);
+            assert!(value.get("error").is_none());
+            offset += message.len();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn junk_between_messages() -> Result<()> {
+        let messages = make_messages()?;
+        let junk = [0x0d, 0x0a];
+        let bytes = [
+            &junk[..],
+            &messages[0],
+            &junk,
+            &messages[1],
+            &[0x00],
+            &messages[2],
+            &junk,
+        ]
+        .concat();
+
+        let values = parse_json(&inspect(&bytes, &split_messages(&bytes)?, true)?)?;
+        assert_eq!(3, values.len());
+        let offsets = [
+            2,
+            4 + messages[0].len(),
+            5 + messages[0].len() + messages[1].len(),
+        ];
+        for (value, offset) in values.iter().zip(offsets) {
+            assert_eq!(offset, value["offset"]);
+            assert!(value.get("error").is_none());
+        }
+        Ok(())
+    }
+}
@@ -20,17 +20,17 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::kbm_file::KbmFile;
-use crate::key_mapping::KeyMapping;
-use crate::key_mappings::KeyMappings;
-use crate::keyboard_mapping::KeyboardMapping;
-use crate::midi_note::MidiNote;
-use crate::reference::Reference;
-use crate::scale::Scale;
+use crate::scala_files::read_kbm_file;
 use crate::tuning_tool_args::KeyboardMappingSourceGroup;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::path::PathBuf;
+use tuning_tool_core::key_mapping::KeyMapping;
+use tuning_tool_core::key_mappings::KeyMappings;
+use tuning_tool_core::keyboard_mapping::KeyboardMapping;
+use tuning_tool_core::midi_note::MidiNote;
+use tuning_tool_core::reference::Reference;
+use tuning_tool_core::scale::Scale;
 
 pub(crate) enum KeyboardMappingSource {
     KbmFile(PathBuf),
@@ -42,14 +42,14 @@ impl KeyboardMappingSource {
     pub(crate) fn make_keyboard_mapping(&self, scale: &Scale) -> Result<KeyboardMapping> {
         match self {
             Self::KbmFile(kbm_path) => {
-                let kbm_file = KbmFile::read(kbm_path)?;
+                let kbm_file = read_kbm_file(kbm_path)?;
                 Ok(kbm_file.keyboard_mapping().clone())
             }
             Self::Linear(reference) => KeyboardMapping::new_full_linear(reference),
             Self::WhiteKeys(reference) => {
                 let interval_count = scale.intervals().len();
                 if interval_count != 7 {
-                    todo!("--white not implemented for interval count {interval_count}");
+                    bail!("--white not implemented for interval count {interval_count}");
                 }
 
                 let mut degree = 0;
@@ -101,3 +101,34 @@ impl From<KeyboardMappingSourceGroup> for KeyboardMappingSource {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::keyboard_mapping_source::KeyboardMappingSource;
+    use anyhow::Result;
+    use tuning_tool_core::interval::Interval;
+    use tuning_tool_core::reference::Reference;
+    use tuning_tool_core::scale::Scale;
+
+    fn equal_scale(step_count: usize) -> Result<Scale> {
+        Scale::new(
+            (1..=step_count)
+                .map(|step| format!("{cents}.0", cents = step * 1200 / step_count).parse())
+                .collect::<Result<Vec<Interval>>>()?,
+        )
+    }
+
+    #[test]
+    fn white_keys_heptatonic() -> Result<()> {
+        let source = KeyboardMappingSource::WhiteKeys(Reference::default());
+        assert!(source.make_keyboard_mapping(&equal_scale(7)?).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn white_keys_other_interval_count_is_error() -> Result<()> {
+        let source = KeyboardMappingSource::WhiteKeys(Reference::default());
+        assert!(source.make_keyboard_mapping(&equal_scale(12)?).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::input_paths::{has_extension, InputPaths};
+use anyhow::{bail, Result};
+use std::fs::read;
+use std::path::Path;
+use tuning_tool_core::diagnostic::Diagnostic;
+use tuning_tool_core::kbm_file::KbmFile;
+use tuning_tool_core::scl_file::SclFile;
+
+pub(crate) fn lint(inputs: &[String], deny_warnings: bool) -> Result<()> {
+    let mut paths = Vec::new();
+    for input in inputs {
+        let matches = InputPaths::find(input, &["scl", "kbm"])?.paths;
+        if matches.is_empty() {
+            bail!("No .scl or .kbm files match {input}")
+        }
+        paths.extend(matches);
+    }
+
+    let mut error_count = 0;
+    let mut warning_count = 0;
+    let mut failed_file_count = 0;
+    for path in &paths {
+        let diagnostics = lint_file(path);
+        let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+        let warnings = diagnostics.len() - errors;
+        for diagnostic in &diagnostics {
+            println!("{path}:{diagnostic}", path = path.display());
+        }
+
+        if errors > 0 || (deny_warnings && warnings > 0) {
+            failed_file_count += 1;
+        }
+        error_count += errors;
+        warning_count += warnings;
+    }
+
+    println!(
+        "Checked {count} files: {error_count} error(s), {warning_count} warning(s)",
+        count = paths.len()
+    );
+
+    if failed_file_count > 0 {
+        bail!(
+            "{failed_file_count} of {count} files failed",
+            count = paths.len()
+        )
+    }
+
+    Ok(())
+}
+
+// Diagnostics for the file, with a single error if it cannot be read so
+// that one bad file does not stop the others being checked
+fn lint_file(path: &Path) -> Vec<Diagnostic> {
+    let s = match read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(e) => return vec![Diagnostic::error(1, 1, format!("Cannot read file: {e}"))],
+    };
+    if has_extension(path, "kbm") {
+        KbmFile::parse(&s).diagnostics
+    } else {
+        SclFile::parse(&s).diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_paths::test_resources_dir;
+    use crate::lint::lint_file;
+
+    #[test]
+    fn lint_file_clean() {
+        let dir = test_resources_dir();
+        assert!(lint_file(&dir.join("22edo2.scl")).is_empty());
+        assert!(lint_file(&dir.join("22edo2.kbm")).is_empty());
+    }
+
+    #[test]
+    fn lint_file_unreadable() {
+        let diagnostics = lint_file(&test_resources_dir().join("missing.scl"));
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].is_error());
+        assert!(diagnostics[0].message.starts_with("Cannot read file"));
+    }
+}
@@ -20,39 +20,29 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::devices::{make_midi_input, make_midi_output};
-use anyhow::{anyhow, Result};
+use crate::devices::{make_midi_input, make_midi_output, midi_input_ports, midi_output_ports};
+use anyhow::Result;
 
 pub(crate) fn list_ports() -> Result<()> {
     let midi_input = make_midi_input()?;
-    if midi_input.port_count() == 0 {
+    let input_ports = midi_input_ports(&midi_input)?;
+    if input_ports.is_empty() {
         println!("(You have no MIDI input ports)");
     } else {
-        let mut names = midi_input
-            .ports()
-            .iter()
-            .map(|p| midi_input.port_name(p).map_err(|e| anyhow!(e)))
-            .collect::<Result<Vec<_>>>()?;
-        names.sort();
         println!("MIDI inputs:");
-        for name in names {
-            println!("  {}", name);
+        for (i, (name, _)) in input_ports.iter().enumerate() {
+            println!("  {i}: {name}");
         }
     }
 
     let midi_output = make_midi_output()?;
-    if midi_output.port_count() == 0 {
+    let output_ports = midi_output_ports(&midi_output)?;
+    if output_ports.is_empty() {
         println!("(You have no MIDI output ports)");
     } else {
-        let mut names = midi_output
-            .ports()
-            .iter()
-            .map(|p| midi_output.port_name(p).map_err(|e| anyhow!(e)))
-            .collect::<Result<Vec<_>>>()?;
-        names.sort();
         println!("MIDI outputs:");
-        for name in names {
-            println!("  {}", name);
+        for (i, (name, _)) in output_ports.iter().enumerate() {
+            println!("  {i}: {name}");
         }
     }
     Ok(())
@@ -20,56 +20,67 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-#![allow(clippy::wrong_self_convention)]
-
-mod approx_eq;
-mod bulk_dump_reply;
-mod checksum_calculator;
+#[cfg(feature = "midi-io")]
+mod adapt;
+#[cfg(feature = "midi-io")]
+mod adaptive_tuner;
+mod analyze_audio;
+mod batch;
 mod cli;
-mod consts;
+mod convert;
 mod decode_bulk_dump;
+mod device_profile;
+#[cfg(feature = "midi-io")]
 mod devices;
+mod diff_source;
 mod dump_tuning_table;
-mod evaluate;
-mod evaluation_strategy;
 mod experimental;
-mod frequency;
-mod fs;
-mod hex_dump;
-mod interval;
-mod kbm_file;
-mod key_frequency_mapping;
-mod key_mapping;
-mod key_mappings;
-mod keyboard_mapping;
+mod import_dump;
+mod input_paths;
+mod inspect_syx;
 mod keyboard_mapping_source;
+mod lint;
+mod list_device_profiles;
+#[cfg(feature = "midi-io")]
 mod list_ports;
+mod message_json;
+#[cfg(feature = "midi-io")]
 mod midi_input_ex;
-mod midi_message_builder;
-mod midi_note;
+#[cfg(feature = "midi-io")]
 mod midi_output_ex;
+#[cfg(feature = "midi-io")]
 mod monitor_port;
-mod mts_entry;
-mod note_change;
-mod note_change_entry;
-mod note_number;
-mod num;
-mod preset_name;
+#[cfg(feature = "midi-io")]
+mod mpe_channels;
+mod mts_message_type;
+#[cfg(feature = "midi-io")]
+mod paced_output;
+mod pacing;
+#[cfg(feature = "midi-io")]
+mod perform;
+#[cfg(feature = "midi-io")]
+mod port_selector;
+mod preview;
+#[cfg(feature = "python")]
 mod python;
-mod ratio;
-mod read;
-mod reference;
-mod resources;
+mod recording;
+#[cfg(feature = "midi-io")]
+mod replay;
 mod run;
+#[cfg(feature = "midi-io")]
 mod save_tunings;
-mod scale;
-mod scl_file;
-mod semitones;
+mod scala_files;
 mod send_tuning;
 mod send_tuning_output;
+#[cfg(feature = "python")]
 mod sympy;
+mod syx_file;
+#[cfg(feature = "midi-io")]
+mod timeline;
+mod tuning_source;
+mod tuning_state;
 mod tuning_tool_args;
-mod types;
+mod verify;
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
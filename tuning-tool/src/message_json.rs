@@ -0,0 +1,164 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use serde_json::{json, Value};
+use tuning_tool_core::decoded_message::{DecodedMessage, TunedKey};
+use tuning_tool_core::mts_entry::MtsEntry;
+
+pub(crate) fn to_json(message: &DecodedMessage) -> Value {
+    fn tuned_key_json(key: u8, mts: Option<MtsEntry>) -> Value {
+        let tuned_key = TunedKey::new(key, mts);
+        json!({
+            "key": tuned_key.key,
+            "frequency": tuned_key.frequency.map(|f| f.0),
+            "cents": tuned_key.cents,
+        })
+    }
+
+    let mut value = match message {
+        DecodedMessage::NoteChange {
+            real_time,
+            device_id,
+            bank,
+            preset,
+            entries,
+        } => json!({
+            "real_time": real_time,
+            "device_id": device_id.to_u8(),
+            "bank": bank.map(|b| b.to_u8()),
+            "preset": preset.to_u8(),
+            "keys": entries
+                .iter()
+                .map(|e| tuned_key_json(e.key_number.to_u8(), e.mts.to_option()))
+                .collect::<Vec<_>>(),
+        }),
+        DecodedMessage::DumpRequest {
+            device_id,
+            bank,
+            preset,
+        } => json!({
+            "device_id": device_id.to_u8(),
+            "bank": bank.map(|b| b.to_u8()),
+            "preset": preset.to_u8(),
+        }),
+        DecodedMessage::BulkDump {
+            device_id,
+            bank,
+            preset,
+            name,
+            entries,
+            checksum_valid,
+        } => json!({
+            "device_id": device_id.to_u8(),
+            "bank": bank.map(|b| b.to_u8()),
+            "preset": preset.to_u8(),
+            "name": name,
+            "checksum_valid": checksum_valid,
+            "keys": entries
+                .iter()
+                .enumerate()
+                .map(|(key, e)| tuned_key_json(key as u8, *e))
+                .collect::<Vec<_>>(),
+        }),
+        DecodedMessage::ScaleOctaveDump {
+            device_id,
+            bank,
+            preset,
+            name,
+            cents,
+            checksum_valid,
+        } => json!({
+            "device_id": device_id.to_u8(),
+            "bank": bank.to_u8(),
+            "preset": preset.to_u8(),
+            "name": name,
+            "checksum_valid": checksum_valid,
+            "cents": cents,
+        }),
+        DecodedMessage::ScaleOctave {
+            real_time,
+            device_id,
+            channels,
+            cents,
+        } => json!({
+            "real_time": real_time,
+            "device_id": device_id.to_u8(),
+            "channels": channels.iter().map(|c| c + 1).collect::<Vec<_>>(),
+            "cents": cents,
+        }),
+        DecodedMessage::Handshake {
+            device_id,
+            handshake,
+            packet,
+        } => json!({
+            "device_id": device_id.to_u8(),
+            "handshake": handshake.to_string(),
+            "packet": packet.to_u8(),
+        }),
+        DecodedMessage::SysEx {
+            manufacturer_id,
+            device_id,
+            len,
+        } => json!({
+            "manufacturer_id": manufacturer_id,
+            "device_id": device_id.map(|d| d.to_u8()),
+            "len": len,
+        }),
+        DecodedMessage::Channel { channel, .. } => json!({
+            "channel": channel + 1,
+            "message": message.to_string(),
+        }),
+        DecodedMessage::System(_) => json!({
+            "message": message.to_string(),
+        }),
+    };
+
+    value["kind"] = json!(message.kind().to_string());
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message_json::to_json;
+    use anyhow::Result;
+    use serde_json::json;
+    use tuning_tool_core::decoded_message::DecodedMessage;
+
+    #[test]
+    fn note_change() -> Result<()> {
+        let message = DecodedMessage::decode(&[
+            0xf0, 0x7f, 0x00, 0x08, 0x02, 0x00, 0x01, 0x45, 0x45, 0x00, 0x00, 0xf7,
+        ])?;
+        assert_eq!(
+            json!({
+                "kind": "note-change",
+                "real_time": true,
+                "device_id": 0,
+                "bank": null,
+                "preset": 0,
+                "keys": [{ "key": 69, "frequency": 440.0, "cents": 0.0 }],
+            }),
+            to_json(&message)
+        );
+        Ok(())
+    }
+}
@@ -22,6 +22,8 @@
 
 // Workaround for https://github.com/Boddlnagg/midir/issues/55
 
+#[cfg(unix)]
+use midir::os::unix::VirtualInput;
 use midir::{ConnectError, MidiInput, MidiInputConnection, MidiInputPort};
 use std::result::Result as StdResult;
 
@@ -35,6 +37,16 @@ pub(crate) trait MidiInputEx {
     ) -> StdResult<MidiInputConnection<T>, ConnectError<()>>
     where
         F: FnMut(u64, &[u8], &mut T) + Send + 'static;
+
+    #[cfg(unix)]
+    fn create_virtual_ex<F, T: Send>(
+        self,
+        port_name: &str,
+        callback: F,
+        data: T,
+    ) -> StdResult<MidiInputConnection<T>, ConnectError<()>>
+    where
+        F: FnMut(u64, &[u8], &mut T) + Send + 'static;
 }
 
 impl MidiInputEx for MidiInput {
@@ -51,4 +63,18 @@ impl MidiInputEx for MidiInput {
         self.connect(port, port_name, callback, data)
             .map_err(|e| ConnectError::new(e.kind(), ()))
     }
+
+    #[cfg(unix)]
+    fn create_virtual_ex<F, T: Send>(
+        self,
+        port_name: &str,
+        callback: F,
+        data: T,
+    ) -> StdResult<MidiInputConnection<T>, ConnectError<()>>
+    where
+        F: FnMut(u64, &[u8], &mut T) + Send + 'static,
+    {
+        self.create_virtual(port_name, callback, data)
+            .map_err(|e| ConnectError::new(e.kind(), ()))
+    }
 }
@@ -22,6 +22,8 @@
 
 // Workaround for https://github.com/Boddlnagg/midir/issues/55
 
+#[cfg(unix)]
+use midir::os::unix::VirtualOutput;
 use midir::{ConnectError, MidiOutput, MidiOutputConnection, MidiOutputPort};
 use std::result::Result as StdResult;
 
@@ -31,6 +33,12 @@ pub(crate) trait MidiOutputEx {
         port: &MidiOutputPort,
         port_name: &str,
     ) -> StdResult<MidiOutputConnection, ConnectError<()>>;
+
+    #[cfg(unix)]
+    fn create_virtual_ex(
+        self,
+        port_name: &str,
+    ) -> StdResult<MidiOutputConnection, ConnectError<()>>;
 }
 
 impl MidiOutputEx for MidiOutput {
@@ -42,4 +50,13 @@ impl MidiOutputEx for MidiOutput {
         self.connect(port, port_name)
             .map_err(|e| ConnectError::new(e.kind(), ()))
     }
+
+    #[cfg(unix)]
+    fn create_virtual_ex(
+        self,
+        port_name: &str,
+    ) -> StdResult<MidiOutputConnection, ConnectError<()>> {
+        self.create_virtual(port_name)
+            .map_err(|e| ConnectError::new(e.kind(), ()))
+    }
 }
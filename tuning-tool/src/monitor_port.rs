@@ -20,32 +20,121 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::devices::{get_midi_input_port, make_midi_input};
-use crate::hex_dump::to_hex_dump;
-use crate::midi_input_ex::MidiInputEx;
+use crate::devices::{connect_midi_input, MidiPort};
+use crate::message_json::to_json;
+use crate::recording::Recorder;
 use anyhow::{Error, Result};
+use serde_json::json;
+use std::path::PathBuf;
 use std::sync::mpsc::{channel, Sender};
+use tuning_tool_core::decoded_message::{DecodedMessage, MessageKind};
+use tuning_tool_core::syx::to_hex_dump;
 
-pub(crate) fn monitor_port(input_port: &str) -> Result<()> {
-    fn callback_wrapper(timestamp: u64, bytes: &[u8], tx: &mut Sender<Error>) {
-        if let Err(e) = callback(timestamp, bytes) {
-            tx.send(e).expect("Send failed");
+enum MonitorEvent {
+    Received(u64, Vec<u8>),
+    Failed(Error),
+    Interrupted,
+}
+
+struct MonitorState {
+    filter: Vec<MessageKind>,
+    json: bool,
+    record: bool,
+    tx: Sender<MonitorEvent>,
+}
+
+pub(crate) fn monitor_port(
+    input_port: &MidiPort,
+    filter: &[MessageKind],
+    json: bool,
+    record_path: &Option<PathBuf>,
+) -> Result<()> {
+    fn callback_wrapper(timestamp: u64, bytes: &[u8], state: &mut MonitorState) {
+        // Recording happens on the main thread to keep file I/O off the
+        // MIDI input thread
+        if state.record {
+            _ = state
+                .tx
+                .send(MonitorEvent::Received(timestamp, bytes.to_vec()));
+        }
+
+        if let Err(e) = callback(timestamp, bytes, &state.filter, state.json) {
+            _ = state.tx.send(MonitorEvent::Failed(e));
         }
     }
 
-    fn callback(timestamp: u64, bytes: &[u8]) -> Result<()> {
-        println!(
-            "{timestamp:08x} {hex_dump}",
-            hex_dump = to_hex_dump(bytes, None)?
-        );
+    fn callback(timestamp: u64, bytes: &[u8], filter: &[MessageKind], json: bool) -> Result<()> {
+        let message = DecodedMessage::decode(bytes);
+
+        if !filter.is_empty() {
+            match &message {
+                Ok(message) if filter.contains(&message.kind()) => {}
+                _ => return Ok(()),
+            }
+        }
+
+        if json {
+            let mut value = match &message {
+                Ok(message) => to_json(message),
+                Err(e) => json!({ "error": e.to_string() }),
+            };
+            value["timestamp"] = json!(timestamp);
+            value["bytes"] = json!(to_hex_dump(bytes, Some(bytes.len().max(1)))?);
+            println!("{value}");
+        } else {
+            println!(
+                "{timestamp:08x} {hex_dump}",
+                hex_dump = to_hex_dump(bytes, None)?
+            );
+            match message {
+                Ok(message) => println!("{message}"),
+                Err(e) => println!("Failed to decode message: {e}"),
+            }
+        }
+
         Ok(())
     }
 
-    let midi_input = make_midi_input()?;
-    let midi_input_port = get_midi_input_port(&midi_input, input_port)?;
+    let mut recorder = record_path.as_deref().map(Recorder::create).transpose()?;
+
     let (tx, rx) = channel();
-    let _conn = midi_input.connect_ex(&midi_input_port, "tuning-tool", callback_wrapper, tx)?;
-    let e = rx.recv()?;
-    println!("Failed with error {e:?}");
+    let interrupt_tx = tx.clone();
+    ctrlc::set_handler(move || _ = interrupt_tx.send(MonitorEvent::Interrupted))?;
+
+    let state = MonitorState {
+        filter: filter.to_vec(),
+        json,
+        record: recorder.is_some(),
+        tx,
+    };
+    let _conn = connect_midi_input(input_port, callback_wrapper, state)?;
+    if !json {
+        println!("Monitoring {input_port}");
+        if let Some(record_path) = record_path {
+            println!("Recording to {}", record_path.display());
+        }
+    }
+    let result = loop {
+        match rx.recv()? {
+            MonitorEvent::Received(timestamp, bytes) => {
+                if let Some(recorder) = &mut recorder {
+                    if let Err(e) = recorder.record(timestamp, &bytes) {
+                        break Err(e);
+                    }
+                }
+            }
+            MonitorEvent::Failed(e) => break Err(e),
+            MonitorEvent::Interrupted => break Ok(()),
+        }
+    };
+
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
+
+    if let Err(e) = result {
+        println!("Failed with error {e:?}");
+    }
+
     Ok(())
 }
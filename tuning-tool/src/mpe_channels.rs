@@ -0,0 +1,123 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use midly::num::{u4, u7};
+
+// Number of member channels in an MPE lower zone using all of channels 2-16
+const MEMBER_CHANNEL_COUNT: u8 = 15;
+
+// Assigns each sounding note its own MPE member channel so that per-note
+// pitch bends do not interfere, reusing the least recently used channel
+// and stealing the oldest note once all channels are busy
+pub(crate) struct MpeChannels {
+    // Member channels, least recently allocated first
+    order: Vec<u4>,
+    // Incoming channel and key sounding on each member channel
+    notes: Vec<Option<(u4, u7)>>,
+}
+
+impl MpeChannels {
+    pub(crate) fn new() -> Self {
+        Self {
+            order: (1..=MEMBER_CHANNEL_COUNT).map(u4::new).collect(),
+            notes: vec![None; MEMBER_CHANNEL_COUNT as usize],
+        }
+    }
+
+    // Returns the member channel for the note and the key of any note that
+    // had to be stolen from it
+    pub(crate) fn allocate(&mut self, channel: u4, key: u7) -> (u4, Option<u7>) {
+        let index = self
+            .order
+            .iter()
+            .position(|member| self.note(*member).is_none())
+            .unwrap_or(0);
+        let member = self.order.remove(index);
+        self.order.push(member);
+
+        let stolen = self.notes[Self::slot(member)]
+            .replace((channel, key))
+            .map(|(_, key)| key);
+        (member, stolen)
+    }
+
+    // Returns the member channel the note was sounding on, if any
+    pub(crate) fn release(&mut self, channel: u4, key: u7) -> Option<u4> {
+        let member = self.find(channel, key)?;
+        self.notes[Self::slot(member)] = None;
+        Some(member)
+    }
+
+    pub(crate) fn find(&self, channel: u4, key: u7) -> Option<u4> {
+        self.order
+            .iter()
+            .copied()
+            .find(|member| self.note(*member) == Some((channel, key)))
+    }
+
+    fn note(&self, member: u4) -> Option<(u4, u7)> {
+        self.notes[Self::slot(member)]
+    }
+
+    fn slot(member: u4) -> usize {
+        member.as_int() as usize - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mpe_channels::MpeChannels;
+    use midly::num::{u4, u7};
+
+    #[test]
+    fn simultaneous_notes_get_separate_channels() {
+        let mut channels = MpeChannels::new();
+        let (a, _) = channels.allocate(u4::new(0), u7::new(60));
+        let (b, _) = channels.allocate(u4::new(0), u7::new(64));
+        assert_ne!(a, b);
+        assert_eq!(Some(a), channels.release(u4::new(0), u7::new(60)));
+        assert_eq!(Some(b), channels.find(u4::new(0), u7::new(64)));
+        assert_eq!(None, channels.release(u4::new(0), u7::new(60)));
+    }
+
+    #[test]
+    fn least_recently_used_channel_is_reused() {
+        let mut channels = MpeChannels::new();
+        let (a, _) = channels.allocate(u4::new(0), u7::new(60));
+        _ = channels.release(u4::new(0), u7::new(60));
+        let (b, _) = channels.allocate(u4::new(0), u7::new(62));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn oldest_note_is_stolen() {
+        let mut channels = MpeChannels::new();
+        let (first, _) = channels.allocate(u4::new(0), u7::new(40));
+        for key in 41..55 {
+            assert_eq!(None, channels.allocate(u4::new(0), u7::new(key)).1);
+        }
+        assert_eq!(
+            (first, Some(u7::new(40))),
+            channels.allocate(u4::new(0), u7::new(55))
+        );
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::devices::{connect_midi_input, connect_midi_output, MidiPort};
+use crate::pacing::Pacing;
+use anyhow::{bail, Result};
+use log::trace;
+use midir::{MidiInputConnection, MidiOutputConnection};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use tuning_tool_core::syx::{EOX, SYSEX, UNIVERSAL_NON_REAL_TIME};
+
+const ACK: u8 = 0x7f;
+const NAK: u8 = 0x7e;
+const CANCEL: u8 = 0x7d;
+const WAIT: u8 = 0x7c;
+
+// MIDI generic handshaking messages (F0 7E <device> <sub-ID> <packet> F7)
+#[derive(Debug, PartialEq)]
+enum Handshake {
+    Ack,
+    Nak,
+    Cancel,
+    Wait,
+}
+
+impl Handshake {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let [SYSEX, universal_non_real_time, _device_id, sub_id, _packet, EOX] = bytes else {
+            return None;
+        };
+
+        if *universal_non_real_time != UNIVERSAL_NON_REAL_TIME.to_u8() {
+            return None;
+        }
+
+        match *sub_id {
+            ACK => Some(Self::Ack),
+            NAK => Some(Self::Nak),
+            CANCEL => Some(Self::Cancel),
+            WAIT => Some(Self::Wait),
+            _ => None,
+        }
+    }
+}
+
+struct AckListener {
+    _conn: MidiInputConnection<Sender<Vec<u8>>>,
+    rx: Receiver<Vec<u8>>,
+    timeout: Duration,
+}
+
+impl AckListener {
+    fn connect(input_port: &str, timeout: Duration) -> Result<Self> {
+        fn callback(_timestamp: u64, bytes: &[u8], tx: &mut Sender<Vec<u8>>) {
+            _ = tx.send(bytes.to_vec());
+        }
+
+        let (tx, rx) = channel();
+        let conn = connect_midi_input(&MidiPort::new(input_port, false), callback, tx)?;
+        Ok(Self {
+            _conn: conn,
+            rx,
+            timeout,
+        })
+    }
+
+    fn wait(&self) -> Result<()> {
+        let mut deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let bytes = match self.rx.recv_timeout(remaining) {
+                Ok(bytes) => bytes,
+                Err(RecvTimeoutError::Timeout) => bail!("Timed out waiting for ACK from device"),
+                Err(RecvTimeoutError::Disconnected) => bail!("ACK input port disconnected"),
+            };
+
+            match Handshake::parse(&bytes) {
+                Some(Handshake::Ack) => return Ok(()),
+                Some(Handshake::Nak) => bail!("Device rejected message (NAK)"),
+                Some(Handshake::Cancel) => bail!("Device cancelled transfer"),
+                Some(Handshake::Wait) => {
+                    trace!("Device requested wait");
+                    deadline = Instant::now() + self.timeout;
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+// MIDI output connection that spaces SysEx messages out according to a
+// fixed delay and/or byte rate and optionally waits for a device handshake
+// after each one: other messages, such as notes forwarded by adapt, are
+// sent straight away
+pub(crate) struct PacedOutput {
+    conn: MidiOutputConnection,
+    pacing: Pacing,
+    ack_listener: Option<AckListener>,
+    next_send: Option<Instant>,
+}
+
+impl PacedOutput {
+    pub(crate) fn connect(output_port: &MidiPort, pacing: &Pacing) -> Result<Self> {
+        let ack_listener = pacing
+            .ack_port()
+            .map(|ack_port| AckListener::connect(ack_port, pacing.ack_timeout()))
+            .transpose()?;
+
+        let conn = connect_midi_output(output_port)?;
+
+        Ok(Self {
+            conn,
+            pacing: pacing.clone(),
+            ack_listener,
+            next_send: None,
+        })
+    }
+
+    pub(crate) fn send(&mut self, message: &[u8]) -> Result<()> {
+        if message.first() != Some(&SYSEX) {
+            self.conn.send(message)?;
+            return Ok(());
+        }
+
+        if let Some(next_send) = self.next_send {
+            sleep(next_send.saturating_duration_since(Instant::now()));
+        }
+
+        self.conn.send(message)?;
+
+        if let Some(ack_listener) = &self.ack_listener {
+            ack_listener.wait()?;
+        }
+
+        self.next_send = Some(Instant::now() + self.pacing.wait_after(message.len()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::paced_output::Handshake;
+    use anyhow::Result;
+    use rstest::rstest;
+    use tuning_tool_core::syx::from_hex_dump;
+
+    #[rstest]
+    #[case(Some(Handshake::Ack), "F0 7E 00 7F 00 F7")]
+    #[case(Some(Handshake::Nak), "F0 7E 01 7E 05 F7")]
+    #[case(Some(Handshake::Cancel), "F0 7E 00 7D 00 F7")]
+    #[case(Some(Handshake::Wait), "F0 7E 00 7C 00 F7")]
+    #[case(None, "F0 7F 00 7F 00 F7")]
+    #[case(None, "F0 7E 00 7F 00")]
+    #[case(None, "90 40 7F")]
+    fn handshake_parse(#[case] expected: Option<Handshake>, #[case] input: &str) -> Result<()> {
+        assert_eq!(expected, Handshake::parse(&from_hex_dump(input)?));
+        Ok(())
+    }
+}
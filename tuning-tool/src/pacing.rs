@@ -0,0 +1,95 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::tuning_tool_args::PacingGroup;
+use std::cmp::max;
+use std::time::Duration;
+
+// Only consumed by MIDI output connections, but send-tuning builds it from
+// its flags and device profile either way
+#[cfg_attr(not(feature = "midi-io"), allow(dead_code))]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Pacing {
+    message_delay: Duration,
+    bytes_per_second: Option<u32>,
+    ack_port: Option<String>,
+    ack_timeout: Duration,
+}
+
+impl Pacing {
+    #[cfg_attr(not(feature = "midi-io"), allow(dead_code))]
+    pub(crate) fn ack_port(&self) -> Option<&str> {
+        self.ack_port.as_deref()
+    }
+
+    #[cfg_attr(not(feature = "midi-io"), allow(dead_code))]
+    pub(crate) const fn ack_timeout(&self) -> Duration {
+        self.ack_timeout
+    }
+
+    pub(crate) fn with_min_message_delay(mut self, message_delay: Duration) -> Self {
+        self.message_delay = max(self.message_delay, message_delay);
+        self
+    }
+
+    #[cfg_attr(not(feature = "midi-io"), allow(dead_code))]
+    pub(crate) fn wait_after(&self, message_len: usize) -> Duration {
+        let transmit_time = self
+            .bytes_per_second
+            .map(|bps| Duration::from_secs_f64(message_len as f64 / bps as f64))
+            .unwrap_or_default();
+        max(self.message_delay, transmit_time)
+    }
+}
+
+impl From<PacingGroup> for Pacing {
+    fn from(value: PacingGroup) -> Self {
+        Self {
+            message_delay: Duration::from_millis(value.delay_ms),
+            bytes_per_second: value.rate,
+            ack_port: value.ack_port,
+            ack_timeout: Duration::from_millis(value.ack_timeout_ms),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pacing::Pacing;
+    use std::time::Duration;
+
+    #[test]
+    fn wait_after() {
+        let pacing = Pacing::default();
+        assert_eq!(Duration::ZERO, pacing.wait_after(100));
+
+        let pacing = pacing.with_min_message_delay(Duration::from_millis(20));
+        assert_eq!(Duration::from_millis(20), pacing.wait_after(100));
+
+        let pacing = Pacing {
+            bytes_per_second: Some(1000),
+            ..pacing
+        };
+        assert_eq!(Duration::from_millis(20), pacing.wait_after(10));
+        assert_eq!(Duration::from_millis(100), pacing.wait_after(100));
+    }
+}
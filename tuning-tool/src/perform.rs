@@ -0,0 +1,177 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::devices::{connect_midi_input, MidiPort};
+use crate::keyboard_mapping_source::KeyboardMappingSource;
+use crate::paced_output::PacedOutput;
+use crate::pacing::Pacing;
+use crate::scala_files::read_scl_file;
+use crate::timeline::{Cue, Timeline, Trigger};
+use crate::tuning_state::TuningState;
+use anyhow::{bail, Result};
+use midly::live::LiveEvent;
+use std::path::Path;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::sleep;
+use std::time::Instant;
+use tuning_tool_core::mts_messages::{make_note_change_entries, make_note_change_messages};
+use tuning_tool_core::note_change_entry::NoteChangeEntry;
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::reference::Reference;
+use tuning_tool_core::types::{ChunkSize, DeviceId, Preset};
+
+struct PreparedCue<'a> {
+    cue: &'a Cue,
+    entries: Vec<NoteChangeEntry>,
+}
+
+impl<'a> PreparedCue<'a> {
+    fn new(cue: &'a Cue, out_of_range: OutOfRangePolicy) -> Result<Self> {
+        let scl_file = read_scl_file(&cue.scl_path)?;
+        let scale = scl_file.scale();
+        let keyboard_mapping_source = match &cue.kbm_path {
+            Some(kbm_path) => KeyboardMappingSource::KbmFile(kbm_path.clone()),
+            None => KeyboardMappingSource::Linear(Reference::default()),
+        };
+        let keyboard_mapping = keyboard_mapping_source.make_keyboard_mapping(scale)?;
+        let entries = make_note_change_entries(scale, &keyboard_mapping, out_of_range)?
+            .into_iter()
+            .map(|x| x.1)
+            .collect();
+        Ok(Self { cue, entries })
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn perform(
+    timeline_path: &Path,
+    output_port: &MidiPort,
+    input_port: &Option<MidiPort>,
+    device_id: DeviceId,
+    preset: Preset,
+    chunk_size: ChunkSize,
+    diff: bool,
+    out_of_range: OutOfRangePolicy,
+    pacing: &Pacing,
+) -> Result<()> {
+    type CallbackData = (Vec<Trigger>, Sender<usize>);
+
+    fn callback(_timestamp: u64, bytes: &[u8], data: &mut CallbackData) {
+        let (triggers, tx) = data;
+        let Ok(event) = LiveEvent::parse(bytes) else {
+            return;
+        };
+
+        for (i, trigger) in triggers.iter().enumerate() {
+            if trigger.matches(&event) {
+                _ = tx.send(i);
+            }
+        }
+    }
+
+    let timeline = Timeline::read(timeline_path)?;
+    if input_port.is_none() && timeline.has_midi_triggers() {
+        bail!(
+            "Timeline {path} has program change or control change cues: use --input to select the MIDI input port to listen on",
+            path = timeline_path.display()
+        )
+    }
+
+    let cues = timeline
+        .cues()
+        .iter()
+        .map(|cue| PreparedCue::new(cue, out_of_range))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut timed_cues = cues
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| match c.cue.trigger {
+            Trigger::Time(offset) => Some((offset, i)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    timed_cues.sort_by_key(|(offset, _)| *offset);
+    let mut timed_cues = timed_cues.into_iter().peekable();
+
+    let mut conn = PacedOutput::connect(output_port, pacing)?;
+
+    let (tx, rx) = channel();
+    let _input_conn = match input_port {
+        Some(input_port) => {
+            let triggers = cues.iter().map(|c| c.cue.trigger.clone()).collect();
+            Some(connect_midi_input(input_port, callback, (triggers, tx))?)
+        }
+        None => {
+            drop(tx);
+            None
+        }
+    };
+
+    let start = Instant::now();
+    let mut state = TuningState::default();
+    loop {
+        let index = match timed_cues.peek() {
+            Some((offset, i)) => {
+                let elapsed = start.elapsed();
+                if *offset <= elapsed {
+                    let i = *i;
+                    _ = timed_cues.next();
+                    i
+                } else {
+                    match rx.recv_timeout(*offset - elapsed) {
+                        Ok(i) => i,
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        Err(RecvTimeoutError::Disconnected) => {
+                            sleep(offset.saturating_sub(start.elapsed()));
+                            continue;
+                        }
+                    }
+                }
+            }
+            None => match rx.recv() {
+                Ok(i) => i,
+                Err(_) => break,
+            },
+        };
+
+        let PreparedCue { cue, entries } = &cues[index];
+        let entries = if diff {
+            state.changed_entries(device_id, preset, entries)
+        } else {
+            entries.clone()
+        };
+
+        println!(
+            "[{elapsed:>9.3?}] {cue}: sending {count} keys",
+            elapsed = start.elapsed(),
+            count = entries.len()
+        );
+
+        for message in make_note_change_messages(device_id, preset, &entries, chunk_size)? {
+            conn.send(&message)?;
+        }
+        state.update(device_id, preset, &entries);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::{anyhow, bail, Error, Result};
+use glob::Pattern;
+use log::trace;
+use regex::Regex;
+use std::collections::HashMap;
+use std::env::var_os;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+const PORT_ALIASES_ENV_NAME: &str = "TUNING_TOOL_PORT_ALIASES";
+const PORT_ALIASES_FILE_NAME: &str = "ports.toml";
+
+// User-defined port names read from a TOML table such as
+// bs2 = "Bass Station II:*", each mapping to an exact port name, a
+// list-ports index or a glob pattern
+#[derive(Debug, Default)]
+pub(crate) struct PortAliases(HashMap<String, String>);
+
+impl PortAliases {
+    // Reads aliases from $TUNING_TOOL_PORT_ALIASES or from ports.toml in the
+    // user's tuning-tool configuration directory, if either exists
+    pub(crate) fn read_default() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) if path.is_file() => Self::read(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub(crate) fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        trace!(
+            "Reading port aliases {path}",
+            path = path.as_ref().display()
+        );
+        read_to_string(path)?.parse()
+    }
+
+    fn default_path() -> Option<PathBuf> {
+        if let Some(path) = var_os(PORT_ALIASES_ENV_NAME) {
+            return Some(PathBuf::from(path));
+        }
+
+        let config_dir = var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .or_else(|| var_os("APPDATA").map(PathBuf::from))?;
+        Some(
+            config_dir
+                .join(env!("CARGO_PKG_NAME"))
+                .join(PORT_ALIASES_FILE_NAME),
+        )
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+}
+
+impl FromStr for PortAliases {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        let table = s
+            .parse::<Table>()
+            .map_err(|e| anyhow!("Invalid port aliases: {e}"))?;
+        let mut aliases = HashMap::new();
+        for (name, value) in table {
+            let value = match value {
+                Value::String(value) if !value.is_empty() => value,
+                Value::Integer(index) if index >= 0 => index.to_string(),
+                _ => bail!("Port alias {name} must be a port name, pattern or index"),
+            };
+            aliases.insert(name, value);
+        }
+        Ok(Self(aliases))
+    }
+}
+
+// Picks one of the given port names, which must be sorted in the order shown
+// by list-ports. The selector is tried in turn as an exact port name, an
+// alias, an index from list-ports and finally a regular expression that
+// must match exactly one port name. Aliases resolve to an exact port name,
+// an index or a glob pattern that must match exactly one whole port name.
+pub(crate) fn select_port(
+    kind: &str,
+    names: &[String],
+    selector: &str,
+    aliases: &PortAliases,
+) -> Result<usize> {
+    if let Some(index) = names.iter().position(|n| n == selector) {
+        return Ok(index);
+    }
+
+    if let Some(value) = aliases.get(selector) {
+        trace!("Port alias {selector} resolves to {value}");
+        if let Some(index) = names.iter().position(|n| n == value) {
+            return Ok(index);
+        }
+        if let Some(index) = parse_index(kind, names, value)? {
+            return Ok(index);
+        }
+        let pattern = Pattern::new(value)
+            .map_err(|e| anyhow!("Invalid pattern {value} for port alias {selector}: {e}"))?;
+        return select_match(kind, names, value, |n| pattern.matches(n));
+    }
+
+    if let Some(index) = parse_index(kind, names, selector)? {
+        return Ok(index);
+    }
+
+    let regex = Regex::new(selector)?;
+    select_match(kind, names, selector, |n| regex.is_match(n))
+}
+
+fn parse_index(kind: &str, names: &[String], s: &str) -> Result<Option<usize>> {
+    let Ok(index) = s.parse::<usize>() else {
+        return Ok(None);
+    };
+    if index >= names.len() {
+        bail!(
+            "No MIDI {kind} port with index {index}: choose from 0 to {max}",
+            max = names.len() as isize - 1
+        )
+    }
+    Ok(Some(index))
+}
+
+fn select_match<F: Fn(&str) -> bool>(
+    kind: &str,
+    names: &[String],
+    selector: &str,
+    is_match: F,
+) -> Result<usize> {
+    let matches = names
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| is_match(n))
+        .collect::<Vec<_>>();
+    match matches.as_slice() {
+        [(index, _)] => Ok(*index),
+        [] => bail!(
+            "No MIDI {kind} port matching {selector} found: choose from {s}",
+            s = names.join(", ")
+        ),
+        _ => bail!(
+            "MIDI {kind} port selector {selector} is ambiguous: matches {s}",
+            s = matches
+                .iter()
+                .map(|(_, n)| n.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::port_selector::{select_port, PortAliases};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    const NAMES: [&str; 4] = [
+        "Bass Station II:Bass Station II MIDI 1 20:0",
+        "Midi Through:Midi Through Port-0 14:0",
+        "Surge XT:Surge XT MIDI In 128:0",
+        "Surge XT:Surge XT MIDI Out 128:1",
+    ];
+
+    fn names() -> Vec<String> {
+        NAMES.iter().map(|n| String::from(*n)).collect()
+    }
+
+    fn aliases() -> Result<PortAliases> {
+        r#"
+# My ports
+bs2 = "Bass Station II:*" # Glob, not a regular expression
+through = "Midi Through:Midi Through Port-0 14:0"
+second = 1
+surge-in = "Surge XT:* In ???:?"
+dot = "Midi.Through*"
+"#
+        .parse()
+    }
+
+    #[rstest]
+    #[case(1, "Midi Through:Midi Through Port-0 14:0")]
+    #[case(0, "Bass Station")]
+    #[case(2, "MIDI In")]
+    #[case(3, "^Surge.*Out")]
+    #[case(2, "2")]
+    #[case(0, "bs2")]
+    #[case(1, "through")]
+    #[case(1, "second")]
+    #[case(2, "surge-in")]
+    fn select_port_basics(#[case] expected: usize, #[case] input: &str) -> Result<()> {
+        assert_eq!(
+            expected,
+            select_port("input", &names(), input, &aliases()?)?
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("4")]
+    #[case("Surge XT")]
+    #[case("Novation")]
+    #[case("(")]
+    #[case("dot")]
+    fn select_port_fails(#[case] input: &str) -> Result<()> {
+        assert!(select_port("input", &names(), input, &aliases()?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn select_port_ambiguous() -> Result<()> {
+        let e = select_port("output", &names(), "Surge", &aliases()?)
+            .expect_err("must be ambiguous")
+            .to_string();
+        assert!(e.contains("ambiguous"));
+        assert!(e.contains("Surge XT MIDI In"));
+        assert!(e.contains("Surge XT MIDI Out"));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("bs2")]
+    #[case("bs2 = ")]
+    #[case("bs2 = a")]
+    #[case("bs2 = \"\"")]
+    #[case("bs2 = -1")]
+    #[case("bs2 = \"a\"\nbs2 = \"b\"")]
+    fn port_aliases_invalid(#[case] input: &str) {
+        assert!(input.parse::<PortAliases>().is_err());
+    }
+}
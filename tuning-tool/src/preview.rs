@@ -0,0 +1,229 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::recording::read_recording;
+use crate::scala_files::read_scl_file;
+use crate::tuning_source::TuningSource;
+use anyhow::{bail, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use midly::live::LiveEvent;
+use midly::MidiMessage;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tuning_tool_core::frequency_table::FrequencyTable;
+use tuning_tool_core::synth::{render, SynthNote};
+use tuning_tool_core::tuning_format::Tuning;
+use tuning_tool_core::types::KeyNumber;
+
+// Harmonics of the default additive tone
+const HARMONICS: usize = 6;
+
+// Run length for frequency tables, which have no equave: an octave of keys
+const DEFAULT_RUN_LENGTH: usize = 13;
+
+const DEFAULT_START_KEY: u8 = 60;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Chord(pub(crate) Vec<KeyNumber>);
+
+pub(crate) enum PreviewSource {
+    Run {
+        start_key: Option<KeyNumber>,
+        count: Option<usize>,
+    },
+    Chords(Vec<Chord>),
+    Midi(PathBuf),
+}
+
+impl PreviewSource {
+    pub(crate) fn new(
+        start_key: Option<KeyNumber>,
+        count: Option<usize>,
+        chords: Vec<Chord>,
+        midi_path: Option<PathBuf>,
+    ) -> Self {
+        match (chords.is_empty(), midi_path) {
+            (true, None) => Self::Run { start_key, count },
+            (false, None) => Self::Chords(chords),
+            (true, Some(midi_path)) => Self::Midi(midi_path),
+            _ => unreachable!(),
+        }
+    }
+}
+
+pub(crate) fn preview(
+    tuning_source: &TuningSource,
+    preview_source: &PreviewSource,
+    wav_path: &Path,
+    note_length: f64,
+    sample_rate: u32,
+    sine: bool,
+) -> Result<()> {
+    let (frequencies, zero_key, keys_per_equave) = match tuning_source {
+        TuningSource::Scale {
+            scl_path,
+            keyboard_mapping_source,
+        } => {
+            let scl_file = read_scl_file(scl_path)?;
+            let scale = scl_file.scale();
+            let keyboard_mapping = keyboard_mapping_source.make_keyboard_mapping(scale)?;
+            let zero_key = keyboard_mapping.reference().zero_key();
+            (
+                Tuning::new(scl_file.description(), scale.clone(), keyboard_mapping)
+                    .frequencies()?,
+                Some(zero_key),
+                Some(scale.intervals().len()),
+            )
+        }
+        TuningSource::FrequencyTable(table_path) => (
+            FrequencyTable::read(table_path)?.frequencies().to_vec(),
+            None,
+            None,
+        ),
+    };
+
+    let frequency = |key: KeyNumber| {
+        let frequency = frequencies[key.to_u8() as usize];
+        if frequency.is_none() {
+            println!("Key {key} is not mapped: skipped");
+        }
+        frequency
+    };
+
+    let notes = match preview_source {
+        PreviewSource::Run { start_key, count } => {
+            let start_key = start_key
+                .or(zero_key)
+                .unwrap_or(KeyNumber::constant::<DEFAULT_START_KEY>());
+            let count = count
+                .or(keys_per_equave.map(|n| n + 1))
+                .unwrap_or(DEFAULT_RUN_LENGTH);
+            (start_key.to_u8() as usize..frequencies.len())
+                .filter_map(|key| frequencies[key])
+                .take(count)
+                .enumerate()
+                .map(|(i, frequency)| SynthNote {
+                    start: i as f64 * note_length,
+                    duration: note_length,
+                    frequency,
+                    velocity: 1f64,
+                })
+                .collect::<Vec<_>>()
+        }
+        PreviewSource::Chords(chords) => chords
+            .iter()
+            .enumerate()
+            .flat_map(|(i, chord)| {
+                chord.0.iter().filter_map(move |key| {
+                    frequency(*key).map(|frequency| SynthNote {
+                        start: i as f64 * note_length,
+                        duration: note_length,
+                        frequency,
+                        velocity: 1f64,
+                    })
+                })
+            })
+            .collect(),
+        PreviewSource::Midi(midi_path) => read_midi_notes(midi_path, note_length)?
+            .into_iter()
+            .filter_map(|(key, start, duration, velocity)| {
+                frequency(key).map(|frequency| SynthNote {
+                    start,
+                    duration,
+                    frequency,
+                    velocity,
+                })
+            })
+            .collect(),
+    };
+
+    if notes.is_empty() {
+        bail!("No mapped notes to render")
+    }
+
+    let samples = render(&notes, sample_rate, if sine { 1 } else { HARMONICS });
+
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::new(File::create_new(wav_path)?, spec)?;
+    for sample in &samples {
+        writer.write_sample((sample * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+
+    println!(
+        "Rendered {count} notes ({seconds:.1} s) to {path}",
+        count = notes.len(),
+        seconds = samples.len() as f64 / sample_rate as f64,
+        path = wav_path.display()
+    );
+    Ok(())
+}
+
+// Returns key, start, duration and velocity of each note in the file, with
+// notes still held at the end lasting for the default note length
+fn read_midi_notes(midi_path: &Path, note_length: f64) -> Result<Vec<(KeyNumber, f64, f64, f64)>> {
+    let mut held = HashMap::new();
+    let mut notes = Vec::new();
+    let mut end = 0f64;
+    for message in read_recording(midi_path)? {
+        let time = message.time.as_secs_f64();
+        end = time;
+        let Ok(LiveEvent::Midi { channel, message }) = LiveEvent::parse(&message.bytes) else {
+            continue;
+        };
+
+        match message {
+            MidiMessage::NoteOn { key, vel } if vel > 0 => {
+                held.insert((channel, key), (time, vel.as_int()));
+            }
+            MidiMessage::NoteOn { key, .. } | MidiMessage::NoteOff { key, .. } => {
+                if let Some((start, vel)) = held.remove(&(channel, key)) {
+                    notes.push((key.as_int(), start, time - start, vel));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for ((_, key), (start, vel)) in held {
+        notes.push((key.as_int(), start, (end - start).max(note_length), vel));
+    }
+
+    notes.sort_by(|a, b| a.1.total_cmp(&b.1));
+    notes
+        .into_iter()
+        .map(|(key, start, duration, vel)| {
+            Ok((
+                KeyNumber::try_from(key)?,
+                start,
+                duration,
+                vel as f64 / 127f64,
+            ))
+        })
+        .collect()
+}
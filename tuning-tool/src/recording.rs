@@ -0,0 +1,325 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::syx_file::split_syx_file;
+use anyhow::{anyhow, bail, Result};
+use midly::live::LiveEvent;
+use midly::num::{u15, u24, u28};
+use midly::{Arena, Format, Header, MetaMessage, Smf, Timing, TrackEvent, TrackEventKind};
+use std::fs::read;
+#[cfg(feature = "midi-io")]
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+#[cfg(feature = "midi-io")]
+use tuning_tool_core::syx::SYSEX;
+
+// One tick per millisecond: 1000 ticks per quarter note at 60 BPM
+const TICKS_PER_QUARTER: u16 = 1000;
+const MICROS_PER_QUARTER: u32 = 1_000_000;
+const DEFAULT_MICROS_PER_QUARTER: u32 = 500_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TimedMessage {
+    pub(crate) time: Duration,
+    pub(crate) bytes: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RecordingFormat {
+    // SysEx messages only, without timing
+    Syx,
+    // Single-track standard MIDI file
+    Smf,
+}
+
+impl RecordingFormat {
+    fn from_path(path: &Path) -> Result<Self> {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("syx") => Ok(Self::Syx),
+            Some("mid" | "midi") => Ok(Self::Smf),
+            _ => bail!(
+                "Recording {path} must have a .syx, .mid or .midi extension",
+                path = path.display()
+            ),
+        }
+    }
+}
+
+// Collects received messages off the MIDI input thread: .syx recordings
+// are appended to as messages arrive while .mid recordings are buffered
+// and written once by finish since the track length precedes the events
+#[cfg(feature = "midi-io")]
+pub(crate) struct Recorder {
+    format: RecordingFormat,
+    first_timestamp: Option<u64>,
+    messages: Vec<TimedMessage>,
+    file: File,
+    warned_non_sysex: bool,
+}
+
+#[cfg(feature = "midi-io")]
+impl Recorder {
+    pub(crate) fn create(path: &Path) -> Result<Self> {
+        let format = RecordingFormat::from_path(path)?;
+        if format == RecordingFormat::Syx {
+            eprintln!(
+                "Warning: {path} will contain SysEx messages only and no timing: record to a .mid file to keep timestamps",
+                path = path.display()
+            );
+        }
+
+        let file = File::create(path)?;
+        Ok(Self {
+            format,
+            first_timestamp: None,
+            messages: Vec::new(),
+            file,
+            warned_non_sysex: false,
+        })
+    }
+
+    pub(crate) fn record(&mut self, timestamp: u64, bytes: &[u8]) -> Result<()> {
+        let first_timestamp = *self.first_timestamp.get_or_insert(timestamp);
+        match self.format {
+            RecordingFormat::Syx => {
+                if bytes.first() == Some(&SYSEX) {
+                    self.file.write_all(bytes)?;
+                    self.file.flush()?;
+                } else if !self.warned_non_sysex {
+                    eprintln!(
+                        "Warning: discarding non-SysEx messages which cannot be stored in a .syx recording"
+                    );
+                    self.warned_non_sysex = true;
+                }
+            }
+            RecordingFormat::Smf => self.messages.push(TimedMessage {
+                time: Duration::from_micros(timestamp.saturating_sub(first_timestamp)),
+                bytes: bytes.to_vec(),
+            }),
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<()> {
+        match self.format {
+            RecordingFormat::Syx => Ok(()),
+            RecordingFormat::Smf => write_smf(&self.messages, self.file),
+        }
+    }
+}
+
+// .syx recordings keep no timestamps so replaying one sends every message
+// as soon as pacing allows
+#[cfg(feature = "midi-io")]
+pub(crate) fn is_timed_recording(path: &Path) -> Result<bool> {
+    Ok(RecordingFormat::from_path(path)? == RecordingFormat::Smf)
+}
+
+pub(crate) fn read_recording(path: &Path) -> Result<Vec<TimedMessage>> {
+    let bytes = read(path)?;
+    match RecordingFormat::from_path(path)? {
+        RecordingFormat::Syx => Ok(split_syx_file(path, &bytes)?
+            .into_iter()
+            .map(|m| TimedMessage {
+                time: Duration::ZERO,
+                bytes: m.to_vec(),
+            })
+            .collect()),
+        RecordingFormat::Smf => read_smf(&bytes),
+    }
+}
+
+#[cfg_attr(not(feature = "midi-io"), allow(dead_code))]
+fn write_smf<W: Write>(messages: &[TimedMessage], writer: W) -> Result<()> {
+    let arena = Arena::new();
+    let mut track = vec![TrackEvent {
+        delta: u28::new(0),
+        kind: TrackEventKind::Meta(MetaMessage::Tempo(u24::new(MICROS_PER_QUARTER))),
+    }];
+
+    let mut last_tick = 0;
+    for message in messages {
+        let Ok(event) = LiveEvent::parse(&message.bytes) else {
+            eprintln!(
+                "Warning: skipping invalid MIDI message {:02X?}",
+                message.bytes
+            );
+            continue;
+        };
+        let tick = u32::try_from(message.time.as_millis()).unwrap_or(u32::MAX);
+        track.push(TrackEvent {
+            delta: u28::try_from(tick.saturating_sub(last_tick)).unwrap_or(u28::max_value()),
+            kind: event.as_track_event(&arena),
+        });
+        last_tick = last_tick.max(tick);
+    }
+
+    track.push(TrackEvent {
+        delta: u28::new(0),
+        kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+    });
+
+    let mut smf = Smf::new(Header::new(
+        Format::SingleTrack,
+        Timing::Metrical(u15::new(TICKS_PER_QUARTER)),
+    ));
+    smf.tracks.push(track);
+    smf.write_std(writer)?;
+    Ok(())
+}
+
+fn read_smf(bytes: &[u8]) -> Result<Vec<TimedMessage>> {
+    let smf = Smf::parse(bytes).map_err(|e| anyhow!("Invalid MIDI file: {e}"))?;
+
+    let mut events = Vec::new();
+    for track in &smf.tracks {
+        let mut tick = 0u64;
+        for event in track {
+            tick += event.delta.as_int() as u64;
+            events.push((tick, event.kind));
+        }
+    }
+
+    // Stable sort keeps the order of simultaneous events from each track
+    events.sort_by_key(|(tick, _)| *tick);
+
+    let mut micros_per_quarter = DEFAULT_MICROS_PER_QUARTER;
+    let mut last_tick = 0u64;
+    let mut micros = 0f64;
+    let mut messages = Vec::new();
+    for (tick, kind) in events {
+        micros += (tick - last_tick) as f64
+            * match smf.header.timing {
+                Timing::Metrical(ticks_per_quarter) => {
+                    micros_per_quarter as f64 / ticks_per_quarter.as_int() as f64
+                }
+                Timing::Timecode(fps, subframes) => {
+                    1_000_000f64 / (fps.as_f32() as f64 * subframes as f64)
+                }
+            };
+        last_tick = tick;
+
+        let bytes = match kind {
+            TrackEventKind::Meta(MetaMessage::Tempo(value)) => {
+                micros_per_quarter = value.as_int();
+                continue;
+            }
+            TrackEventKind::Meta(_) => continue,
+            TrackEventKind::Escape(bytes) => bytes.to_vec(),
+            _ => {
+                let Some(event) = kind.as_live_event() else {
+                    eprintln!("Warning: skipping split SysEx message at tick {tick}");
+                    continue;
+                };
+                let mut bytes = Vec::new();
+                event.write_std(&mut bytes)?;
+                bytes
+            }
+        };
+
+        messages.push(TimedMessage {
+            time: Duration::from_micros(micros.round() as u64),
+            bytes,
+        });
+    }
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::recording::{read_smf, write_smf, RecordingFormat, TimedMessage};
+    use anyhow::Result;
+    use rstest::rstest;
+    use std::path::Path;
+    use std::time::Duration;
+
+    #[rstest]
+    #[case(Some(RecordingFormat::Syx), "session.syx")]
+    #[case(Some(RecordingFormat::Smf), "session.mid")]
+    #[case(Some(RecordingFormat::Smf), "SESSION.MIDI")]
+    #[case(None, "session.txt")]
+    #[case(None, "session")]
+    fn from_path(#[case] expected: Option<RecordingFormat>, #[case] input: &str) {
+        assert_eq!(expected, RecordingFormat::from_path(Path::new(input)).ok());
+    }
+
+    #[test]
+    fn smf_round_trip() -> Result<()> {
+        let messages = vec![
+            TimedMessage {
+                time: Duration::ZERO,
+                bytes: vec![
+                    0xf0, 0x7f, 0x00, 0x08, 0x02, 0x00, 0x01, 0x45, 0x45, 0x00, 0x00, 0xf7,
+                ],
+            },
+            TimedMessage {
+                time: Duration::from_millis(250),
+                bytes: vec![0x90, 0x45, 0x64],
+            },
+            TimedMessage {
+                time: Duration::from_millis(1250),
+                bytes: vec![0x80, 0x45, 0x00],
+            },
+            TimedMessage {
+                time: Duration::from_millis(1300),
+                bytes: vec![0xf8],
+            },
+        ];
+
+        let mut bytes = Vec::new();
+        write_smf(&messages, &mut bytes)?;
+        assert_eq!(messages, read_smf(&bytes)?);
+        Ok(())
+    }
+
+    #[test]
+    fn smf_out_of_order_timestamps() -> Result<()> {
+        let messages = vec![
+            TimedMessage {
+                time: Duration::from_millis(100),
+                bytes: vec![0x90, 0x45, 0x64],
+            },
+            TimedMessage {
+                time: Duration::from_millis(50),
+                bytes: vec![0x80, 0x45, 0x00],
+            },
+        ];
+
+        let mut bytes = Vec::new();
+        write_smf(&messages, &mut bytes)?;
+        let result = read_smf(&bytes)?;
+        assert_eq!(2, result.len());
+        assert!(result
+            .iter()
+            .all(|message| message.time == Duration::from_millis(100)));
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::devices::MidiPort;
+use crate::paced_output::PacedOutput;
+use crate::pacing::Pacing;
+use crate::recording::{is_timed_recording, read_recording};
+use anyhow::Result;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::Instant;
+
+pub(crate) fn replay(recording_path: &Path, output_port: &MidiPort, pacing: &Pacing) -> Result<()> {
+    if !is_timed_recording(recording_path)? {
+        eprintln!(
+            "Warning: {path} has no timing information: messages will be sent without their original timing",
+            path = recording_path.display()
+        );
+    }
+
+    let messages = read_recording(recording_path)?;
+    let mut conn = PacedOutput::connect(output_port, pacing)?;
+
+    println!(
+        "Replaying {count} message(s) to {output_port}",
+        count = messages.len()
+    );
+
+    let start = Instant::now();
+    for message in &messages {
+        sleep((start + message.time).saturating_duration_since(Instant::now()));
+        conn.send(&message.bytes)?;
+    }
+
+    Ok(())
+}
@@ -20,52 +20,295 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
+#[cfg(feature = "midi-io")]
+use crate::adapt::adapt;
+#[cfg(feature = "midi-io")]
+use crate::adaptive_tuner::AdaptiveTuner;
+use crate::analyze_audio::analyze_audio;
+use crate::batch::batch;
+use crate::convert::convert;
 use crate::decode_bulk_dump::decode_bulk_dump;
+#[cfg(feature = "midi-io")]
+use crate::devices::MidiPort;
 use crate::dump_tuning_table::dump_tuning_table;
 use crate::experimental::experimental;
+use crate::import_dump::import_dump;
+use crate::inspect_syx::inspect_syx;
+use crate::lint::lint;
+use crate::list_device_profiles::list_device_profiles;
+#[cfg(feature = "midi-io")]
 use crate::list_ports::list_ports;
+#[cfg(feature = "midi-io")]
 use crate::monitor_port::monitor_port;
+#[cfg(feature = "midi-io")]
+use crate::perform::perform;
+use crate::preview::{preview, PreviewSource};
+#[cfg(feature = "midi-io")]
+use crate::replay::replay;
+#[cfg(feature = "midi-io")]
 use crate::save_tunings::save_tunings;
 use crate::send_tuning::send_tuning;
+use crate::send_tuning_output::SendTuningOutput;
+use crate::tuning_source::TuningSource;
 use crate::tuning_tool_args::Command::*;
+#[cfg(feature = "midi-io")]
+use crate::tuning_tool_args::KeyboardMappingSourceGroup;
 use crate::tuning_tool_args::TuningToolArgs;
+use crate::verify::verify;
+#[cfg(feature = "midi-io")]
+use anyhow::bail;
 use anyhow::Result;
 use clap::Parser;
+#[cfg(feature = "midi-io")]
+use std::path::PathBuf;
+#[cfg(feature = "midi-io")]
+use tuning_tool_core::frequency::Frequency;
 
 pub(crate) fn run() -> Result<()> {
     match TuningToolArgs::parse().command {
+        #[cfg(feature = "midi-io")]
+        Adapt {
+            input_port,
+            output_port,
+            scl_path,
+            keyboard_mapping_source,
+            table_path,
+            virtual_input,
+            virtual_output,
+            device_id,
+            preset,
+            max_drift,
+            pitch_bend_range,
+            out_of_range,
+            pacing,
+        } => adapt(
+            &MidiPort::new(&input_port, virtual_input),
+            &MidiPort::new(&output_port, virtual_output),
+            adapt_reference(scl_path, keyboard_mapping_source, table_path)?,
+            device_id,
+            preset,
+            max_drift,
+            pitch_bend_range,
+            out_of_range.policy,
+            &pacing.into(),
+        ),
+        AnalyzeAudio {
+            wav_paths,
+            onsets,
+            table_path,
+            start_key,
+            scl_path,
+            root,
+            equave,
+        } => analyze_audio(
+            &wav_paths,
+            onsets,
+            &table_path,
+            start_key,
+            &scl_path,
+            root,
+            &equave,
+        ),
+        Batch {
+            input,
+            output_dir,
+            keyboard_mapping_source,
+            format,
+            device_id,
+            preset,
+            out_of_range,
+            force,
+        } => batch(
+            &input,
+            &output_dir,
+            &keyboard_mapping_source.into(),
+            &format,
+            device_id,
+            preset,
+            out_of_range.policy,
+            force,
+        ),
+        Convert {
+            input_path,
+            output_path,
+            kbm_path,
+            output_kbm_path,
+            from,
+            to,
+        } => convert(
+            &input_path,
+            &output_path,
+            &kbm_path,
+            &output_kbm_path,
+            from,
+            to,
+        ),
         DecodeBulkDump { syx_path } => decode_bulk_dump(&syx_path),
         DumpTuningTable {
             scl_path,
             keyboard_mapping_source,
+            table_path,
             output_path,
             format,
             sympy,
+            out_of_range,
         } => dump_tuning_table(
-            &scl_path,
-            &keyboard_mapping_source.into(),
+            &TuningSource::new(scl_path, keyboard_mapping_source, table_path),
             &output_path,
             format,
             sympy,
+            out_of_range.policy,
         ),
         Experimental => experimental(),
+        ImportDump {
+            syx_path,
+            scl_path,
+            kbm_path,
+        } => import_dump(&syx_path, &scl_path, &kbm_path),
+        InspectSyx { syx_path, json } => inspect_syx(&syx_path, json),
+        Lint {
+            inputs,
+            deny_warnings,
+        } => lint(&inputs, deny_warnings),
+        ListDeviceProfiles => list_device_profiles(),
+        #[cfg(feature = "midi-io")]
         ListPorts => list_ports(),
-        MonitorPort { input_port } => monitor_port(&input_port),
-        SaveTunings { output_port } => save_tunings(&output_port),
+        #[cfg(feature = "midi-io")]
+        MonitorPort {
+            input_port,
+            virtual_port,
+            filter,
+            json,
+            record_path,
+        } => monitor_port(
+            &MidiPort::new(&input_port, virtual_port),
+            &filter,
+            json,
+            &record_path,
+        ),
+        #[cfg(feature = "midi-io")]
+        Perform {
+            timeline_path,
+            output_port,
+            input_port,
+            virtual_input,
+            virtual_output,
+            device_id,
+            preset,
+            chunk_size,
+            diff,
+            out_of_range,
+            pacing,
+        } => perform(
+            &timeline_path,
+            &MidiPort::new(&output_port, virtual_output),
+            &input_port.map(|input_port| MidiPort::new(&input_port, virtual_input)),
+            device_id,
+            preset,
+            chunk_size,
+            diff,
+            out_of_range.policy,
+            &pacing.into(),
+        ),
+        #[cfg(feature = "midi-io")]
+        Replay {
+            recording_path,
+            output_port,
+            virtual_output,
+            pacing,
+        } => replay(
+            &recording_path,
+            &MidiPort::new(&output_port, virtual_output),
+            &pacing.into(),
+        ),
+        #[cfg(feature = "midi-io")]
+        SaveTunings {
+            output_port,
+            virtual_output,
+            device_profile,
+            pacing,
+        } => save_tunings(
+            &MidiPort::new(&output_port, virtual_output),
+            &device_profile,
+            &pacing.into(),
+        ),
+        Preview {
+            scl_path,
+            keyboard_mapping_source,
+            table_path,
+            wav_path,
+            start_key,
+            count,
+            chords,
+            midi_path,
+            note_length,
+            sample_rate,
+            sine,
+        } => preview(
+            &TuningSource::new(scl_path, keyboard_mapping_source, table_path),
+            &PreviewSource::new(start_key, count, chords, midi_path),
+            &wav_path,
+            note_length,
+            sample_rate,
+            sine,
+        ),
         SendTuning {
             scl_path,
             keyboard_mapping_source,
+            table_path,
             output,
+            virtual_output,
             device_id,
             preset,
+            bank,
             chunk_size,
+            out_of_range,
+            diff,
+            device_profile,
+            pacing,
         } => send_tuning(
-            &scl_path,
-            &keyboard_mapping_source.into(),
-            &output.into(),
+            &TuningSource::new(scl_path, keyboard_mapping_source, table_path),
+            &SendTuningOutput::new(output, virtual_output),
             device_id,
             preset,
+            bank,
             chunk_size,
+            out_of_range.policy,
+            &diff.into(),
+            &device_profile,
+            &pacing.into(),
+        ),
+        Verify {
+            scl_path,
+            keyboard_mapping_source,
+            table_path,
+            out_of_range,
+            max_error,
+        } => verify(
+            &TuningSource::new(scl_path, keyboard_mapping_source, table_path),
+            out_of_range.policy,
+            max_error,
         ),
     }
 }
+
+// Reference tuning for adapt which defaults to 12-EDO when no tuning is given
+#[cfg(feature = "midi-io")]
+fn adapt_reference(
+    scl_path: Option<PathBuf>,
+    keyboard_mapping_source: KeyboardMappingSourceGroup,
+    table_path: Option<PathBuf>,
+) -> Result<Vec<Option<Frequency>>> {
+    if scl_path.is_some() || table_path.is_some() {
+        return TuningSource::new(scl_path, keyboard_mapping_source, table_path).frequencies();
+    }
+
+    if keyboard_mapping_source.kbm_path.is_some()
+        || keyboard_mapping_source.linear.is_some()
+        || keyboard_mapping_source.white_keys.is_some()
+    {
+        bail!("Keyboard mapping requires a .scl file");
+    }
+
+    Ok(AdaptiveTuner::equal_temperament())
+}
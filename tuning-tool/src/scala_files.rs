@@ -0,0 +1,46 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use tuning_tool_core::diagnostic::Parsed;
+use tuning_tool_core::kbm_file::KbmFile;
+use tuning_tool_core::scl_file::SclFile;
+
+pub(crate) fn read_scl_file(path: &Path) -> Result<SclFile> {
+    report_warnings(path, SclFile::read_parsed(path)?)
+}
+
+pub(crate) fn read_kbm_file(path: &Path) -> Result<KbmFile> {
+    report_warnings(path, KbmFile::read_parsed(path)?)
+}
+
+// Prints warnings in the same format as lint so that tolerated problems
+// are not silently ignored
+fn report_warnings<T>(path: &Path, parsed: Parsed<T>) -> Result<T> {
+    for warning in parsed.warnings() {
+        eprintln!("{path}:{warning}", path = path.display());
+    }
+    parsed
+        .into_result()
+        .with_context(|| format!("Failed to parse {path}", path = path.display()))
+}
@@ -20,110 +20,209 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::devices::{get_midi_output_port, make_midi_output};
-use crate::frequency::Frequency;
-use crate::hex_dump::to_hex_dump;
-use crate::key_frequency_mapping::compute_direct;
-use crate::keyboard_mapping::KeyboardMapping;
-use crate::keyboard_mapping_source::KeyboardMappingSource;
-use crate::midi_output_ex::MidiOutputEx;
-use crate::note_change::NoteChange;
-use crate::note_change_entry::NoteChangeEntry;
-use crate::scale::Scale;
-use crate::scl_file::SclFile;
+use crate::device_profile::DeviceProfile;
+#[cfg(feature = "midi-io")]
+use crate::devices::{wait_for_virtual_output, MidiPort};
+use crate::diff_source::DiffSource;
+use crate::mts_message_type::MtsMessageType;
+#[cfg(feature = "midi-io")]
+use crate::paced_output::PacedOutput;
+use crate::pacing::Pacing;
+use crate::scala_files::read_scl_file;
 use crate::send_tuning_output::SendTuningOutput;
-use crate::types::{ChunkSize, DeviceId, MidiValue, Preset};
+use crate::tuning_source::TuningSource;
+#[cfg(not(feature = "midi-io"))]
+use anyhow::bail;
 use anyhow::Result;
-use midly::live::{LiveEvent, SystemCommon};
-use midly::num::u7;
 use std::fs::File;
 use std::io::Write;
 use std::iter::zip;
 use std::path::Path;
+use tuning_tool_core::frequency_table::FrequencyTable;
+use tuning_tool_core::mts_messages::{
+    make_bulk_dump_message, make_frequency_table_entries, make_note_change_entries,
+    make_note_change_messages, make_note_change_with_bank_messages,
+};
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::preset_name::PresetName;
+use tuning_tool_core::syx::to_hex_dump;
+use tuning_tool_core::types::{ChunkSize, DeviceId, MidiValue, Preset};
 
-fn make_note_change_entries(
-    scale: &Scale,
-    keyboard_mapping: &KeyboardMapping,
-) -> Result<Vec<(Frequency, NoteChangeEntry)>> {
-    compute_direct(scale, keyboard_mapping)?
-        .iter()
-        .enumerate()
-        .map(|(i, mapping)| {
-            let f = Frequency(mapping.frequency.0);
-            Ok((
-                f,
-                NoteChangeEntry {
-                    #[allow(clippy::unnecessary_fallible_conversions)]
-                    key_number: TryInto::<u8>::try_into(i)?.try_into()?,
-                    mts: f.to_mts_entry()?,
-                },
-            ))
-        })
-        .collect::<Result<Vec<_>>>()
+pub(crate) fn make_preset_name(path: &Path) -> Result<PresetName> {
+    let s = path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii() && !c.is_ascii_control())
+        .take(PresetName::LEN)
+        .collect::<String>();
+    s.parse()
 }
 
-fn make_messages(
-    device_id: DeviceId,
-    preset: Preset,
-    entries: &[NoteChangeEntry],
-    chunk_size: ChunkSize,
-) -> Result<Vec<Vec<u8>>> {
-    entries
-        .chunks(chunk_size.to_u8() as usize)
-        .map(|chunk| {
-            let note_change = NoteChange::new(device_id, preset, chunk)?;
-            let vec = note_change.to_vec()?;
-            let u7_slice = u7::slice_from_int(MidiValue::to_u8_slice(&vec));
-            let event = LiveEvent::Common(SystemCommon::SysEx(u7_slice));
-            let mut message = Vec::new();
-            event.write_std(&mut message)?;
-            Ok(message)
-        })
-        .collect()
+#[cfg(feature = "midi-io")]
+fn send_to_output_port(
+    output_port: &str,
+    is_virtual: bool,
+    pacing: &Pacing,
+    messages: &[Vec<u8>],
+) -> Result<()> {
+    let output_port = MidiPort::new(output_port, is_virtual);
+    let mut conn = PacedOutput::connect(&output_port, pacing)?;
+    wait_for_virtual_output(&output_port)?;
+    for message in messages {
+        println!("{}", to_hex_dump(message, None)?);
+        conn.send(message)?;
+    }
+    Ok(())
 }
 
+#[cfg(not(feature = "midi-io"))]
+fn send_to_output_port(
+    _output_port: &str,
+    _is_virtual: bool,
+    _pacing: &Pacing,
+    _messages: &[Vec<u8>],
+) -> Result<()> {
+    bail!("Sending to MIDI output port requires tuning-tool to be built with the midi-io feature")
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn send_tuning(
-    scl_path: &Path,
-    keyboard_mapping_source: &KeyboardMappingSource,
+    tuning_source: &TuningSource,
     output: &SendTuningOutput,
     device_id: DeviceId,
-    preset: Preset,
+    preset: Option<Preset>,
+    bank: Option<MidiValue>,
     chunk_size: ChunkSize,
+    out_of_range: OutOfRangePolicy,
+    diff_source: &Option<DiffSource>,
+    device_profile: &Option<String>,
+    pacing: &Pacing,
 ) -> Result<()> {
-    let scl_file = SclFile::read(scl_path)?;
-    let scale = scl_file.scale();
-    let keyboard_mapping = keyboard_mapping_source.make_keyboard_mapping(scale)?;
-    println!(
-        "Start MIDI note: {value} (0x{value:02x})",
-        value = keyboard_mapping.start_key()
-    );
-    println!(
-        "End MIDI note: {value} (0x{value:02x})",
-        value = keyboard_mapping.end_key()
-    );
-    println!(
-        "Base MIDI note: {value} (0x{value:02x})",
-        value = keyboard_mapping.reference().reference_key()
-    );
-    println!(
-        "Base frequency: {value} Hz",
-        value = keyboard_mapping.reference().reference_frequency()
-    );
+    let device_profile = device_profile
+        .as_deref()
+        .map(DeviceProfile::find)
+        .transpose()?;
+
+    let (message_type, preset, chunk_size, pacing) = match &device_profile {
+        Some(device_profile) => {
+            println!("Device profile: {device_profile}");
+            let preset = preset.unwrap_or(device_profile.default_preset());
+            device_profile.check_preset(preset)?;
+            let message_type = device_profile.message_type_for_bank(bank)?;
+            (
+                message_type,
+                preset,
+                device_profile.chunk_size(chunk_size),
+                pacing
+                    .clone()
+                    .with_min_message_delay(device_profile.message_delay()),
+            )
+        }
+        None => (
+            if bank.is_some() {
+                MtsMessageType::NoteChangeWithBank
+            } else {
+                MtsMessageType::NoteChange
+            },
+            preset.unwrap_or(Preset::constant::<8>()),
+            chunk_size,
+            pacing.clone(),
+        ),
+    };
+
+    let mut data = match tuning_source {
+        TuningSource::Scale {
+            scl_path,
+            keyboard_mapping_source,
+        } => {
+            let scl_file = read_scl_file(scl_path)?;
+            let scale = scl_file.scale();
+            let keyboard_mapping = keyboard_mapping_source.make_keyboard_mapping(scale)?;
+            println!(
+                "Start MIDI note: {value} (0x{value:02x})",
+                value = keyboard_mapping.start_key()
+            );
+            println!(
+                "End MIDI note: {value} (0x{value:02x})",
+                value = keyboard_mapping.end_key()
+            );
+            println!(
+                "Base MIDI note: {value} (0x{value:02x})",
+                value = keyboard_mapping.reference().reference_key()
+            );
+            println!(
+                "Base frequency: {value} Hz",
+                value = keyboard_mapping.reference().reference_frequency()
+            );
+            make_note_change_entries(scale, &keyboard_mapping, out_of_range)?
+        }
+        TuningSource::FrequencyTable(table_path) => {
+            let frequency_table = FrequencyTable::read(table_path)?;
+            println!("{tuning_source}");
+            make_frequency_table_entries(&frequency_table, out_of_range)?
+        }
+    };
+
+    let all_entries = data.iter().map(|x| x.1.clone()).collect::<Vec<_>>();
+
+    let mut state = match diff_source {
+        Some(diff_source) => {
+            let state = diff_source.read_state(device_id, preset, output)?;
+            let changed_entries = state.changed_entries(device_id, preset, &all_entries);
+            data.retain(|(_, entry)| {
+                changed_entries
+                    .iter()
+                    .any(|e| e.key_number == entry.key_number)
+            });
+            println!(
+                "Changed keys: {changed} of {total}",
+                changed = data.len(),
+                total = all_entries.len()
+            );
+            Some(state)
+        }
+        None => None,
+    };
+
+    if data.is_empty() {
+        println!("Tuning is unchanged: nothing to send");
+        return Ok(());
+    }
 
-    let data = make_note_change_entries(scale, &keyboard_mapping)?;
     let frequencies = data.iter().map(|x| x.0).collect::<Vec<_>>();
     let entries = data.into_iter().map(|x| x.1).collect::<Vec<_>>();
 
-    let messages = make_messages(device_id, preset, &entries, chunk_size)?;
+    let messages = match message_type {
+        MtsMessageType::NoteChange => {
+            make_note_change_messages(device_id, preset, &entries, chunk_size)?
+        }
+        MtsMessageType::NoteChangeWithBank => make_note_change_with_bank_messages(
+            device_id,
+            bank.unwrap_or(MidiValue::ZERO),
+            preset,
+            &entries,
+            chunk_size,
+        )?,
+        MtsMessageType::BulkDump => vec![make_bulk_dump_message(
+            device_id,
+            preset,
+            make_preset_name(tuning_source.path())?,
+            &all_entries,
+        )?],
+    };
 
     match output {
-        SendTuningOutput::OutputPort(output_port) => {
-            let midi_output = make_midi_output()?;
-            let midi_output_port = get_midi_output_port(&midi_output, output_port)?;
-            let mut conn = midi_output.connect_ex(&midi_output_port, "tuning-tool")?;
-            for message in messages {
-                println!("{}", to_hex_dump(&message, None)?);
-                conn.send(&message)?;
+        SendTuningOutput::OutputPort { name, is_virtual } => {
+            send_to_output_port(name, *is_virtual, &pacing, &messages)?;
+
+            if let (Some(state), Some(state_path)) = (
+                state.as_mut(),
+                diff_source.as_ref().and_then(DiffSource::state_path),
+            ) {
+                state.update(device_id, preset, &entries);
+                state.write(state_path)?;
             }
         }
         SendTuningOutput::SyxPath(syx_path) => {
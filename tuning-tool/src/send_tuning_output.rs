@@ -24,15 +24,18 @@ use crate::tuning_tool_args::SendTuningOutputGroup;
 use std::path::PathBuf;
 
 pub(crate) enum SendTuningOutput {
-    OutputPort(String),
+    OutputPort { name: String, is_virtual: bool },
     SyxPath(PathBuf),
     Stdout,
 }
 
-impl From<SendTuningOutputGroup> for SendTuningOutput {
-    fn from(value: SendTuningOutputGroup) -> Self {
+impl SendTuningOutput {
+    pub(crate) fn new(value: SendTuningOutputGroup, virtual_output: bool) -> Self {
         match (value.output_port, value.syx_path) {
-            (Some(output_port), None) => Self::OutputPort(output_port),
+            (Some(name), None) => Self::OutputPort {
+                name,
+                is_virtual: virtual_output,
+            },
             (None, Some(syx_path)) => Self::SyxPath(syx_path),
             (None, None) => Self::Stdout,
             _ => unreachable!(),
@@ -20,17 +20,19 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::types::MidiValue;
+use anyhow::Result;
+use std::path::Path;
+use tuning_tool_core::syx::split_messages_with_stray_bytes;
 
-pub(crate) const SYSEX: u8 = 0xf0;
-pub(crate) const UNIVERSAL_REAL_TIME: MidiValue = MidiValue::constant::<0x7f>();
-pub(crate) const UNIVERSAL_NON_REAL_TIME: MidiValue = MidiValue::constant::<0x7e>();
-pub(crate) const MIDI_TUNING: MidiValue = MidiValue::constant::<8>();
-pub(crate) const NOTE_CHANGE: MidiValue = MidiValue::constant::<2>();
-pub(crate) const BULK_DUMP_REPLY: MidiValue = MidiValue::constant::<1>();
-pub(crate) const BULK_DUMP_REPLY_CHECKSUM_COUNT: usize = 405;
-
-#[cfg(test)]
-pub(crate) const BULK_DUMP_REPLY_MESSAGE_SIZE: usize = BULK_DUMP_REPLY_CHECKSUM_COUNT + 1;
-
-pub(crate) const EOX: u8 = 0xf7;
+// Messages in the contents of a .syx file, warning about any stray bytes
+// skipped between them
+pub(crate) fn split_syx_file<'a>(path: &Path, bytes: &'a [u8]) -> Result<Vec<&'a [u8]>> {
+    let (messages, stray_bytes) = split_messages_with_stray_bytes(bytes)?;
+    for stray_bytes in &stray_bytes {
+        eprintln!(
+            "Warning: skipping {stray_bytes} in {path}",
+            path = path.display()
+        );
+    }
+    Ok(messages)
+}
@@ -0,0 +1,274 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::{bail, Error, Result};
+use log::trace;
+use midly::live::LiveEvent;
+use midly::MidiMessage;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Trigger {
+    ProgramChange(u8),
+    ControlChange(u8, u8),
+    Time(Duration),
+}
+
+impl Trigger {
+    pub(crate) fn matches(&self, event: &LiveEvent) -> bool {
+        let LiveEvent::Midi { message, .. } = event else {
+            return false;
+        };
+
+        match (self, message) {
+            (Self::ProgramChange(program), MidiMessage::ProgramChange { program: p }) => {
+                *program == p.as_int()
+            }
+            (
+                Self::ControlChange(controller, value),
+                MidiMessage::Controller {
+                    controller: c,
+                    value: v,
+                },
+            ) => *controller == c.as_int() && *value == v.as_int(),
+            _ => false,
+        }
+    }
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::ProgramChange(program) => write!(f, "program change {program}"),
+            Self::ControlChange(controller, value) => {
+                write!(f, "control change {controller}={value}")
+            }
+            Self::Time(offset) => write!(f, "time {offset:.3?}"),
+        }
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        fn parse_u7(s: &str) -> Result<u8> {
+            let value = s.parse::<u8>()?;
+            if value > 0x7f {
+                bail!("Invalid MIDI value {value}")
+            }
+            Ok(value)
+        }
+
+        let Some((kind, value)) = s.split_once(':') else {
+            bail!("Invalid trigger {s}")
+        };
+
+        match kind {
+            "pc" => Ok(Self::ProgramChange(parse_u7(value)?)),
+            "cc" => {
+                let Some((controller, value)) = value.split_once('=') else {
+                    bail!("Invalid control change trigger {s}")
+                };
+                Ok(Self::ControlChange(parse_u7(controller)?, parse_u7(value)?))
+            }
+            "at" => {
+                let seconds = value.parse::<f64>()?;
+                if seconds < 0f64 {
+                    bail!("Invalid time trigger {s}")
+                }
+                Ok(Self::Time(Duration::from_secs_f64(seconds)))
+            }
+            _ => bail!("Invalid trigger {s}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Cue {
+    pub(crate) trigger: Trigger,
+    pub(crate) scl_path: PathBuf,
+    pub(crate) kbm_path: Option<PathBuf>,
+}
+
+impl Display for Cue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{trigger}: {scl_path}",
+            trigger = self.trigger,
+            scl_path = self.scl_path.display()
+        )?;
+        if let Some(kbm_path) = &self.kbm_path {
+            write!(f, " ({kbm_path})", kbm_path = kbm_path.display())?;
+        }
+        Ok(())
+    }
+}
+
+// Timeline file: one cue per line in the form "<trigger> <scl-path> [<kbm-path>]"
+// where trigger is one of "pc:<program>", "cc:<controller>=<value>" or
+// "at:<seconds>"; blank lines and lines starting with "!" are ignored
+#[derive(Debug)]
+pub(crate) struct Timeline {
+    cues: Vec<Cue>,
+}
+
+impl Timeline {
+    pub(crate) fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        trace!("Reading timeline file {path}", path = path.display());
+        let mut timeline = read_to_string(path)?.parse::<Self>()?;
+
+        // Scale and mapping paths are relative to the timeline file
+        if let Some(dir) = path.parent() {
+            for cue in &mut timeline.cues {
+                cue.scl_path = dir.join(&cue.scl_path);
+                cue.kbm_path = cue.kbm_path.as_ref().map(|p| dir.join(p));
+            }
+        }
+
+        Ok(timeline)
+    }
+
+    pub(crate) fn cues(&self) -> &Vec<Cue> {
+        &self.cues
+    }
+
+    // Program change and control change cues need a MIDI input port
+    pub(crate) fn has_midi_triggers(&self) -> bool {
+        self.cues
+            .iter()
+            .any(|cue| !matches!(cue.trigger, Trigger::Time(_)))
+    }
+}
+
+impl FromStr for Timeline {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        let cues = s
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with("!"))
+            .map(|line| {
+                let mut parts = line.split_whitespace();
+                let Some(trigger) = parts.next() else {
+                    bail!("Invalid cue {line}")
+                };
+                let Some(scl_path) = parts.next() else {
+                    bail!("No .scl file in cue {line}")
+                };
+                let kbm_path = parts.next().map(PathBuf::from);
+                if parts.next().is_some() {
+                    bail!("Invalid cue {line}")
+                }
+
+                let cue = Cue {
+                    trigger: trigger.parse()?,
+                    scl_path: PathBuf::from(scl_path),
+                    kbm_path,
+                };
+                trace!("Parsed cue {cue}");
+                Ok(cue)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if cues.is_empty() {
+            bail!("Timeline contains no cues")
+        }
+
+        Ok(Self { cues })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timeline::{Timeline, Trigger};
+    use anyhow::Result;
+    use midly::live::LiveEvent;
+    use midly::MidiMessage;
+    use rstest::rstest;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[rstest]
+    #[case(Trigger::ProgramChange(5), "pc:5")]
+    #[case(Trigger::ControlChange(64, 127), "cc:64=127")]
+    #[case(Trigger::Time(Duration::from_millis(1500)), "at:1.5")]
+    fn trigger_from_str(#[case] expected: Trigger, #[case] input: &str) -> Result<()> {
+        assert_eq!(expected, input.parse()?);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("pc:128")]
+    #[case("cc:64")]
+    #[case("at:-1")]
+    #[case("note:60")]
+    fn trigger_from_str_fails(#[case] input: &str) {
+        assert!(input.parse::<Trigger>().is_err());
+    }
+
+    #[test]
+    fn trigger_matches() {
+        let event = LiveEvent::Midi {
+            channel: 0.into(),
+            message: MidiMessage::ProgramChange { program: 3.into() },
+        };
+        assert!(Trigger::ProgramChange(3).matches(&event));
+        assert!(!Trigger::ProgramChange(4).matches(&event));
+        assert!(!Trigger::ControlChange(3, 0).matches(&event));
+        assert!(!Trigger::Time(Duration::ZERO).matches(&event));
+    }
+
+    #[test]
+    fn basics() -> Result<()> {
+        let timeline = "
+            ! Live set
+            at:0 12edo.scl
+            pc:1 carlos_super.scl a4.kbm
+
+            cc:20=64 31edo.scl 31edo.kbm
+        "
+        .parse::<Timeline>()?;
+
+        let cues = timeline.cues();
+        assert_eq!(3, cues.len());
+        assert_eq!(Trigger::Time(Duration::ZERO), cues[0].trigger);
+        assert_eq!(PathBuf::from("12edo.scl"), cues[0].scl_path);
+        assert!(cues[0].kbm_path.is_none());
+        assert_eq!(Trigger::ProgramChange(1), cues[1].trigger);
+        assert_eq!(Some(PathBuf::from("a4.kbm")), cues[1].kbm_path);
+        assert_eq!(Trigger::ControlChange(20, 64), cues[2].trigger);
+        assert!(timeline.has_midi_triggers());
+        assert!(!"at:0 12edo.scl\nat:5 31edo.scl"
+            .parse::<Timeline>()?
+            .has_midi_triggers());
+        Ok(())
+    }
+}
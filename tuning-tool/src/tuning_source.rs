@@ -0,0 +1,94 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::keyboard_mapping_source::KeyboardMappingSource;
+use crate::scala_files::read_scl_file;
+use crate::tuning_tool_args::KeyboardMappingSourceGroup;
+use anyhow::Result;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+use tuning_tool_core::frequency::Frequency;
+use tuning_tool_core::frequency_table::FrequencyTable;
+use tuning_tool_core::tuning_format::Tuning;
+
+pub(crate) enum TuningSource {
+    Scale {
+        scl_path: PathBuf,
+        keyboard_mapping_source: KeyboardMappingSource,
+    },
+    FrequencyTable(PathBuf),
+}
+
+impl TuningSource {
+    pub(crate) fn new(
+        scl_path: Option<PathBuf>,
+        keyboard_mapping_source: KeyboardMappingSourceGroup,
+        table_path: Option<PathBuf>,
+    ) -> Self {
+        match (scl_path, table_path) {
+            (Some(scl_path), None) => Self::Scale {
+                scl_path,
+                keyboard_mapping_source: keyboard_mapping_source.into(),
+            },
+            (None, Some(table_path)) => Self::FrequencyTable(table_path),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Frequency of each of the 128 MIDI keys, or `None` for unmapped keys
+    pub(crate) fn frequencies(&self) -> Result<Vec<Option<Frequency>>> {
+        match self {
+            Self::Scale {
+                scl_path,
+                keyboard_mapping_source,
+            } => {
+                let scl_file = read_scl_file(scl_path)?;
+                let scale = scl_file.scale();
+                let keyboard_mapping = keyboard_mapping_source.make_keyboard_mapping(scale)?;
+                Tuning::new(scl_file.description(), scale.clone(), keyboard_mapping).frequencies()
+            }
+            Self::FrequencyTable(table_path) => {
+                Ok(FrequencyTable::read(table_path)?.frequencies().to_vec())
+            }
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        match self {
+            Self::Scale { scl_path, .. } => scl_path,
+            Self::FrequencyTable(table_path) => table_path,
+        }
+    }
+}
+
+impl Display for TuningSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Scale { scl_path, .. } => {
+                write!(f, "Scale file: {path}", path = scl_path.display())
+            }
+            Self::FrequencyTable(table_path) => {
+                write!(f, "Frequency table: {path}", path = table_path.display())
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use anyhow::{bail, Error, Result};
+use log::trace;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::{read_to_string, File};
+use std::io::Write;
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+use tuning_tool_core::bulk_dump_reply::BulkDumpReply;
+use tuning_tool_core::mts_entry::MtsEntry;
+use tuning_tool_core::note_change_entry::NoteChangeEntry;
+use tuning_tool_core::note_number::NoteNumber;
+use tuning_tool_core::types::{DeviceId, Lsb, Msb, Preset};
+
+const KEY_COUNT: usize = 128;
+
+type PresetEntries = [Option<MtsEntry>; KEY_COUNT];
+
+// Last tuning sent to each device/preset so that subsequent sends can be
+// restricted to the keys that actually changed
+#[derive(Debug, Default)]
+pub(crate) struct TuningState {
+    presets: BTreeMap<(u8, u8), PresetEntries>,
+}
+
+impl TuningState {
+    pub(crate) fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            trace!("No tuning state file {path}", path = path.display());
+            return Ok(Self::default());
+        }
+
+        trace!("Reading tuning state file {path}", path = path.display());
+        read_to_string(path)?.parse()
+    }
+
+    pub(crate) fn from_bulk_dump_reply(bulk_dump_reply: &BulkDumpReply) -> Self {
+        let mut state = Self::default();
+        state.update(
+            bulk_dump_reply.device_id(),
+            bulk_dump_reply.preset(),
+            &bulk_dump_reply
+                .entries()
+                .iter()
+                .enumerate()
+                .filter_map(|(i, mts)| {
+                    mts.map(|mts| NoteChangeEntry {
+                        key_number: (i as u8).try_into().expect("Must be a valid key number"),
+                        mts,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        );
+        state
+    }
+
+    pub(crate) fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut f = File::create(path)?;
+        write!(f, "{self}")?;
+        Ok(())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    pub(crate) fn has_preset(&self, device_id: DeviceId, preset: Preset) -> bool {
+        self.presets
+            .contains_key(&Self::make_key(device_id, preset))
+    }
+
+    pub(crate) fn changed_entries(
+        &self,
+        device_id: DeviceId,
+        preset: Preset,
+        entries: &[NoteChangeEntry],
+    ) -> Vec<NoteChangeEntry> {
+        let previous = self.presets.get(&Self::make_key(device_id, preset));
+        entries
+            .iter()
+            .filter(|e| {
+                !e.mts.is_no_change()
+                    && previous.is_none_or(|previous| {
+                        previous[e.key_number.to_u8() as usize] != Some(e.mts)
+                    })
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        device_id: DeviceId,
+        preset: Preset,
+        entries: &[NoteChangeEntry],
+    ) {
+        let previous = self
+            .presets
+            .entry(Self::make_key(device_id, preset))
+            .or_insert([None; KEY_COUNT]);
+        for e in entries.iter().filter(|e| !e.mts.is_no_change()) {
+            previous[e.key_number.to_u8() as usize] = Some(e.mts);
+        }
+    }
+
+    const fn make_key(device_id: DeviceId, preset: Preset) -> (u8, u8) {
+        (device_id.to_u8(), preset.to_u8())
+    }
+}
+
+impl Display for TuningState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "! device preset key note msb lsb")?;
+        for ((device_id, preset), entries) in &self.presets {
+            for (key, entry) in entries.iter().enumerate() {
+                if let Some(entry) = entry {
+                    writeln!(
+                        f,
+                        "{device_id} {preset} {key} {note} {msb} {lsb}",
+                        note = entry.note_number,
+                        msb = entry.msb,
+                        lsb = entry.lsb
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TuningState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        let mut state = Self::default();
+        for line in s.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with("!") {
+                continue;
+            }
+
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            let [device_id, preset, key, note_number, msb, lsb] = parts[..] else {
+                bail!("Invalid tuning state line {line}")
+            };
+
+            state.update(
+                device_id.parse()?,
+                preset.parse()?,
+                &[NoteChangeEntry {
+                    key_number: key.parse()?,
+                    mts: MtsEntry {
+                        note_number: note_number.parse::<NoteNumber>()?,
+                        msb: msb.parse::<Msb>()?,
+                        lsb: lsb.parse::<Lsb>()?,
+                    },
+                }],
+            );
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tuning_state::TuningState;
+    use anyhow::Result;
+    use tuning_tool_core::frequency::Frequency;
+    use tuning_tool_core::mts_entry::MtsEntry;
+    use tuning_tool_core::note_change_entry::NoteChangeEntry;
+    use tuning_tool_core::types::{DeviceId, KeyNumber, Preset};
+
+    fn make_entries(frequencies: &[f64]) -> Result<Vec<NoteChangeEntry>> {
+        frequencies
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                Ok(NoteChangeEntry {
+                    key_number: KeyNumber::try_from(i as u8)?,
+                    mts: Frequency(*f).to_mts_entry()?,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn changed_entries() -> Result<()> {
+        let device_id = DeviceId::ZERO;
+        let preset = Preset::constant::<8>();
+        let mut state = TuningState::default();
+
+        let entries = make_entries(&[440f64, 450f64, 460f64])?;
+        assert_eq!(3, state.changed_entries(device_id, preset, &entries).len());
+        state.update(device_id, preset, &entries);
+        assert!(state
+            .changed_entries(device_id, preset, &entries)
+            .is_empty());
+        assert_eq!(
+            3,
+            state
+                .changed_entries(device_id, Preset::ZERO, &entries)
+                .len()
+        );
+
+        let entries = make_entries(&[440f64, 451f64, 460f64])?;
+        let changed = state.changed_entries(device_id, preset, &entries);
+        assert_eq!(1, changed.len());
+        assert_eq!(1, changed[0].key_number.to_u8());
+        Ok(())
+    }
+
+    #[test]
+    fn no_change_entries() -> Result<()> {
+        let device_id = DeviceId::ZERO;
+        let preset = Preset::constant::<8>();
+        let mut entries = make_entries(&[440f64, 450f64])?;
+        entries[1].mts = MtsEntry::NO_CHANGE;
+
+        let mut state = TuningState::default();
+        let changed = state.changed_entries(device_id, preset, &entries);
+        assert_eq!(1, changed.len());
+        assert_eq!(0, changed[0].key_number.to_u8());
+
+        state.update(device_id, preset, &entries);
+        let entries = make_entries(&[440f64, 450f64])?;
+        let changed = state.changed_entries(device_id, preset, &entries);
+        assert_eq!(1, changed.len());
+        assert_eq!(1, changed[0].key_number.to_u8());
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let device_id = DeviceId::ONE;
+        let preset = Preset::constant::<8>();
+        let entries = make_entries(&[261.625565f64, 440f64, 13289.656616f64])?;
+
+        let mut state = TuningState::default();
+        state.update(device_id, preset, &entries);
+
+        let state = state.to_string().parse::<TuningState>()?;
+        assert!(state
+            .changed_entries(device_id, preset, &entries)
+            .is_empty());
+        Ok(())
+    }
+}
@@ -20,12 +20,20 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 
-use crate::cli::{parse_absolute_path, parse_reference};
-use crate::reference::Reference;
-use crate::types::{ChunkSize, DeviceId, Preset};
+use crate::cli::{
+    parse_absolute_path, parse_chord, parse_key_number, parse_positive_f64, parse_reference,
+};
+use crate::preview::Chord;
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 use std::str::FromStr;
+#[cfg(feature = "midi-io")]
+use tuning_tool_core::decoded_message::MessageKind;
+use tuning_tool_core::interval::Interval;
+use tuning_tool_core::out_of_range::OutOfRangePolicy;
+use tuning_tool_core::reference::Reference;
+use tuning_tool_core::tuning_format::TuningFormat;
+use tuning_tool_core::types::{ChunkSize, DeviceId, KeyNumber, MidiValue, Preset};
 
 const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
 const PACKAGE_DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
@@ -51,6 +59,233 @@ pub(crate) struct TuningToolArgs {
 
 #[derive(Debug, Subcommand)]
 pub(crate) enum Command {
+    #[cfg(feature = "midi-io")]
+    #[command(
+        name = "adapt",
+        about = "Retune incoming notes towards just intonation and forward them to MIDI device"
+    )]
+    Adapt {
+        #[arg(help = "MIDI input port name, list-ports index, alias or pattern")]
+        input_port: String,
+
+        #[arg(help = "MIDI output port name, list-ports index, alias or pattern")]
+        output_port: String,
+
+        #[arg(
+            help = "Path to .scl file of tuning to adapt [default: 12-EDO]",
+            value_parser = parse_absolute_path
+        )]
+        scl_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        keyboard_mapping_source: KeyboardMappingSourceGroup,
+
+        #[arg(
+            long = "table",
+            help = "Path to frequency table with one frequency (Hz), cents value (e.g. 6900c) or x per key instead of .scl file",
+            conflicts_with_all = ["scl_path", "kbm_path", "REFERENCE0", "REFERENCE1"],
+            value_parser = parse_absolute_path
+        )]
+        table_path: Option<PathBuf>,
+
+        #[arg(
+            long = "virtual-input",
+            help = "Create virtual MIDI input port named after input port argument instead of connecting to existing port"
+        )]
+        virtual_input: bool,
+
+        #[arg(
+            long = "virtual-output",
+            help = "Create virtual MIDI output port named after output port argument instead of connecting to existing port"
+        )]
+        virtual_output: bool,
+
+        #[arg(
+            help = "Device ID",
+            long = "device",
+            short = 'd',
+            value_parser = <DeviceId as FromStr>::from_str,
+            default_value_t = DeviceId::ZERO
+        )]
+        device_id: DeviceId,
+
+        #[arg(
+            help = "Preset",
+            long = "preset",
+            short = 'p',
+            value_parser = <Preset as FromStr>::from_str,
+            default_value_t = Preset::constant::<8>()
+        )]
+        preset: Preset,
+
+        #[arg(
+            long = "max-drift",
+            help = "Maximum deviation from tuning in cents",
+            default_value_t = 50f64
+        )]
+        max_drift: f64,
+
+        #[arg(
+            long = "pitch-bend",
+            help = "Send MPE pitch bends with given range in semitones on member channels 2-16 instead of MTS note changes",
+            value_parser = clap::value_parser!(u8).range(1..=96)
+        )]
+        pitch_bend_range: Option<u8>,
+
+        #[command(flatten)]
+        out_of_range: OutOfRangeGroup,
+
+        #[command(flatten)]
+        pacing: PacingGroup,
+    },
+
+    #[command(
+        name = "analyze-audio",
+        about = "Estimate pitches of notes in WAV files and write them as frequency table or .scl file"
+    )]
+    AnalyzeAudio {
+        #[arg(
+            help = "Paths to WAV files, each holding a single note unless --onsets is given",
+            required = true,
+            value_parser = parse_absolute_path
+        )]
+        wav_paths: Vec<PathBuf>,
+
+        #[arg(
+            long = "onsets",
+            help = "Split each WAV file into notes at onsets",
+            default_value_t = false
+        )]
+        onsets: bool,
+
+        #[arg(
+            long = "table",
+            help = "Path to new frequency table to write notes to",
+            value_parser = parse_absolute_path
+        )]
+        table_path: Option<PathBuf>,
+
+        #[arg(
+            long = "start-key",
+            help = "MIDI key of first note in frequency table",
+            value_parser = <KeyNumber as FromStr>::from_str,
+            default_value_t = KeyNumber::constant::<60>()
+        )]
+        start_key: KeyNumber,
+
+        #[arg(
+            long = "scl",
+            help = "Path to new .scl file to write notes relative to root to",
+            value_parser = parse_absolute_path
+        )]
+        scl_path: Option<PathBuf>,
+
+        #[arg(
+            long = "root",
+            help = "Index of note to use as root of .scl file",
+            default_value_t = 0
+        )]
+        root: usize,
+
+        #[arg(
+            long = "equave",
+            help = "Interval to fold notes into and write as the period of the .scl file, as a ratio or in cents",
+            value_parser = <Interval as FromStr>::from_str,
+            default_value = "2/1"
+        )]
+        equave: Interval,
+    },
+
+    #[command(
+        name = "batch",
+        about = "Convert directory or glob of .scl files into one .syx, frequency table or JSON file per scale"
+    )]
+    Batch {
+        #[arg(help = "Directory of .scl files or glob pattern such as 'scales/**/*.scl'")]
+        input: String,
+
+        #[arg(
+            long = "output",
+            short = 'o',
+            help = "Output directory",
+            value_parser = parse_absolute_path
+        )]
+        output_dir: PathBuf,
+
+        #[command(flatten)]
+        keyboard_mapping_source: KeyboardMappingSourceGroup,
+
+        #[arg(
+            long = "format",
+            short = 'f',
+            help = "Output format",
+            default_value = "syx"
+        )]
+        format: BatchFormat,
+
+        #[arg(
+            help = "Device ID",
+            long = "device",
+            short = 'd',
+            value_parser = <DeviceId as FromStr>::from_str,
+            default_value_t = DeviceId::ZERO
+        )]
+        device_id: DeviceId,
+
+        #[arg(
+            help = "Preset",
+            long = "preset",
+            short = 'p',
+            value_parser = <Preset as FromStr>::from_str,
+            default_value_t = Preset::constant::<8>()
+        )]
+        preset: Preset,
+
+        #[command(flatten)]
+        out_of_range: OutOfRangeGroup,
+
+        #[arg(long = "force", help = "Overwrite existing output files")]
+        force: bool,
+    },
+
+    #[command(
+        name = "convert",
+        about = "Convert tuning between .scl, .tun, .ascl, Surge XT patch and frequency list formats"
+    )]
+    Convert {
+        #[arg(help = "Path to input tuning file", value_parser = parse_absolute_path)]
+        input_path: PathBuf,
+
+        #[arg(help = "Path to new output tuning file", value_parser = parse_absolute_path)]
+        output_path: PathBuf,
+
+        #[arg(
+            long = "kbm",
+            help = "Path to .kbm file for .scl input (default: linear mapping with A4 at 440 Hz)",
+            value_parser = parse_absolute_path
+        )]
+        kbm_path: Option<PathBuf>,
+
+        #[arg(
+            long = "output-kbm",
+            help = "Path to new .kbm file to write keyboard mapping to",
+            value_parser = parse_absolute_path
+        )]
+        output_kbm_path: Option<PathBuf>,
+
+        #[arg(
+            long = "from",
+            help = "Input format (scl, tun, ascl, surge, frequencies) if not implied by extension"
+        )]
+        from: Option<TuningFormat>,
+
+        #[arg(
+            long = "to",
+            help = "Output format (scl, tun, ascl, surge, frequencies) if not implied by extension"
+        )]
+        to: Option<TuningFormat>,
+    },
+
     #[command(
         name = "decode-bulk-dump",
         about = "Decode MIDI bulk tuning dump reply"
@@ -67,13 +302,22 @@ pub(crate) enum Command {
     DumpTuningTable {
         #[arg(
             help = "Path to .scl file",
+            required_unless_present = "table_path",
             value_parser = parse_absolute_path
         )]
-        scl_path: PathBuf,
+        scl_path: Option<PathBuf>,
 
         #[command(flatten)]
         keyboard_mapping_source: KeyboardMappingSourceGroup,
 
+        #[arg(
+            long = "table",
+            help = "Path to frequency table with one frequency (Hz), cents value (e.g. 6900c) or x per key instead of .scl file",
+            conflicts_with_all = ["scl_path", "kbm_path", "REFERENCE0", "REFERENCE1"],
+            value_parser = parse_absolute_path
+        )]
+        table_path: Option<PathBuf>,
+
         #[arg(
             long = "output",
             short = 'o',
@@ -96,43 +340,352 @@ pub(crate) enum Command {
             default_value_t = false
         )]
         sympy: bool,
+
+        #[command(flatten)]
+        out_of_range: OutOfRangeGroup,
     },
 
     #[command(name = "experimental", about = "Experimental stuff")]
     Experimental,
 
+    #[command(
+        name = "import-dump",
+        about = "Convert MIDI bulk tuning dump reply into .scl and .kbm files"
+    )]
+    ImportDump {
+        #[arg(
+            help = "Path to .syx file containing bulk dump",
+            value_parser = parse_absolute_path
+        )]
+        syx_path: PathBuf,
+
+        #[arg(
+            help = "Path to new .scl file",
+            value_parser = parse_absolute_path
+        )]
+        scl_path: PathBuf,
+
+        #[arg(
+            help = "Path to new .kbm file",
+            value_parser = parse_absolute_path
+        )]
+        kbm_path: PathBuf,
+    },
+
+    #[command(
+        name = "inspect-syx",
+        about = "Identify and decode each message in a .syx file"
+    )]
+    InspectSyx {
+        #[arg(
+            help = "Path to .syx file",
+            value_parser = parse_absolute_path
+        )]
+        syx_path: PathBuf,
+
+        #[arg(long = "json", help = "Print decoded messages as JSON lines")]
+        json: bool,
+    },
+
+    #[command(
+        name = "lint",
+        about = "Check .scl and .kbm files and report warnings and errors with line and column"
+    )]
+    Lint {
+        #[arg(
+            help = "Paths, directories or glob patterns of .scl and .kbm files",
+            required = true
+        )]
+        inputs: Vec<String>,
+
+        #[arg(
+            long = "deny-warnings",
+            help = "Fail if any file has warnings",
+            default_value_t = false
+        )]
+        deny_warnings: bool,
+    },
+
+    #[command(name = "list-device-profiles", about = "List built-in device profiles")]
+    ListDeviceProfiles,
+
+    #[cfg(feature = "midi-io")]
     #[command(name = "list-ports", about = "List MIDI input and output ports")]
     ListPorts,
 
+    #[cfg(feature = "midi-io")]
     #[command(name = "monitor-port", about = "Monitor MIDI input port")]
     MonitorPort {
-        #[arg(help = "MIDI input port name")]
+        #[arg(help = "MIDI input port name, list-ports index, alias or pattern")]
         input_port: String,
+
+        #[arg(
+            long = "virtual",
+            help = "Create virtual MIDI input port named after input port argument instead of connecting to existing port"
+        )]
+        virtual_port: bool,
+
+        #[arg(
+            long = "filter",
+            help = "Only show messages of given kinds (note-change, bulk-dump, dump-request, scale-octave, handshake, sysex, channel, system)",
+            value_delimiter = ',',
+            value_parser = <MessageKind as FromStr>::from_str
+        )]
+        filter: Vec<MessageKind>,
+
+        #[arg(long = "json", help = "Print decoded messages as JSON lines")]
+        json: bool,
+
+        #[arg(
+            long = "record",
+            help = "Record received messages to .mid file with timing or to .syx file (SysEx messages only, without timing)",
+            value_parser = parse_absolute_path
+        )]
+        record_path: Option<PathBuf>,
+    },
+
+    #[cfg(feature = "midi-io")]
+    #[command(
+        name = "perform",
+        about = "Send tunings from a timeline file as timed or MIDI-triggered cues fire"
+    )]
+    Perform {
+        #[arg(
+            help = "Path to timeline file",
+            value_parser = parse_absolute_path
+        )]
+        timeline_path: PathBuf,
+
+        #[arg(help = "MIDI output port name, list-ports index, alias or pattern")]
+        output_port: String,
+
+        #[arg(
+            long = "input",
+            short = 'i',
+            help = "MIDI input port (name, index, alias or pattern) to listen on for program change and control change triggers"
+        )]
+        input_port: Option<String>,
+
+        #[arg(
+            long = "virtual-input",
+            requires = "input_port",
+            help = "Create virtual MIDI input port named after --input instead of connecting to existing port"
+        )]
+        virtual_input: bool,
+
+        #[arg(
+            long = "virtual-output",
+            help = "Create virtual MIDI output port named after output port argument instead of connecting to existing port"
+        )]
+        virtual_output: bool,
+
+        #[arg(
+            help = "Device ID",
+            long = "device",
+            short = 'd',
+            value_parser = <DeviceId as FromStr>::from_str,
+            default_value_t = DeviceId::ZERO
+        )]
+        device_id: DeviceId,
+
+        #[arg(
+            help = "Preset",
+            long = "preset",
+            short = 'p',
+            value_parser = <Preset as FromStr>::from_str,
+            default_value_t = Preset::constant::<8>()
+        )]
+        preset: Preset,
+
+        #[arg(
+            help = "Chunk size",
+            long = "chunk",
+            short = 'c',
+            value_parser = <ChunkSize as FromStr>::from_str,
+            default_value_t = ChunkSize::ONE
+        )]
+        chunk_size: ChunkSize,
+
+        #[arg(
+            long = "diff",
+            help = "Send only keys changed since the previous cue",
+            default_value_t = false
+        )]
+        diff: bool,
+
+        #[command(flatten)]
+        out_of_range: OutOfRangeGroup,
+
+        #[command(flatten)]
+        pacing: PacingGroup,
+    },
+
+    #[cfg(feature = "midi-io")]
+    #[command(
+        name = "replay",
+        about = "Send messages recorded by monitor-port to MIDI output port with their original timing"
+    )]
+    Replay {
+        #[arg(
+            help = "Path to .mid or .syx recording",
+            value_parser = parse_absolute_path
+        )]
+        recording_path: PathBuf,
+
+        #[arg(help = "MIDI output port name, list-ports index, alias or pattern")]
+        output_port: String,
+
+        #[arg(
+            long = "virtual-output",
+            help = "Create virtual MIDI output port named after output port argument instead of connecting to existing port"
+        )]
+        virtual_output: bool,
+
+        #[command(flatten)]
+        pacing: PacingGroup,
     },
 
+    #[cfg(feature = "midi-io")]
     #[command(
         name = "save-tunings",
-        about = "Save tuning tables on Novation Bass Station II"
+        about = "Save tuning tables to device memory (Novation Bass Station II by default)"
     )]
     SaveTunings {
-        #[arg(help = "MIDI output port name")]
+        #[arg(help = "MIDI output port name, list-ports index, alias or pattern")]
         output_port: String,
+
+        #[arg(
+            long = "virtual-output",
+            help = "Create virtual MIDI output port named after output port argument instead of connecting to existing port"
+        )]
+        virtual_output: bool,
+
+        #[arg(
+            long = "device-profile",
+            help = "Name of built-in device profile or path to device profile file",
+            default_value = "bass-station-ii"
+        )]
+        device_profile: String,
+
+        #[command(flatten)]
+        pacing: PacingGroup,
+    },
+
+    #[command(
+        name = "preview",
+        about = "Render scale as ascending run, chords or MIDI file to WAV file"
+    )]
+    Preview {
+        #[arg(
+            help = "Path to .scl file",
+            required_unless_present = "table_path",
+            value_parser = parse_absolute_path
+        )]
+        scl_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        keyboard_mapping_source: KeyboardMappingSourceGroup,
+
+        #[arg(
+            long = "table",
+            help = "Path to frequency table with one frequency (Hz), cents value (e.g. 6900c) or x per key instead of .scl file",
+            conflicts_with_all = ["scl_path", "kbm_path", "REFERENCE0", "REFERENCE1"],
+            value_parser = parse_absolute_path
+        )]
+        table_path: Option<PathBuf>,
+
+        #[arg(
+            long = "output",
+            short = 'o',
+            help = "Path to new WAV file",
+            value_parser = parse_absolute_path
+        )]
+        wav_path: PathBuf,
+
+        #[arg(
+            long = "start-key",
+            help = "First key of ascending run [default: zero key of keyboard mapping or 60]",
+            value_parser = parse_key_number
+        )]
+        start_key: Option<KeyNumber>,
+
+        #[arg(
+            long = "count",
+            help = "Number of mapped keys in ascending run [default: one equave or 13]"
+        )]
+        count: Option<usize>,
+
+        #[arg(
+            long = "chord",
+            help = "Comma-separated keys to play together instead of ascending run, e.g. c4,e4,g4 (repeat for sequence of chords)",
+            conflicts_with_all = ["start_key", "count"],
+            value_parser = parse_chord
+        )]
+        chords: Vec<Chord>,
+
+        #[arg(
+            long = "midi",
+            help = "Path to .mid file to play instead of ascending run",
+            conflicts_with_all = ["start_key", "count", "chords"],
+            value_parser = parse_absolute_path
+        )]
+        midi_path: Option<PathBuf>,
+
+        #[arg(
+            long = "note-length",
+            help = "Length of each note or chord in seconds",
+            value_parser = parse_positive_f64,
+            default_value_t = 0.5f64
+        )]
+        note_length: f64,
+
+        #[arg(
+            long = "sample-rate",
+            help = "Sample rate in Hz",
+            value_parser = clap::value_parser!(u32).range(1..),
+            default_value_t = 44100
+        )]
+        sample_rate: u32,
+
+        #[arg(
+            long = "sine",
+            help = "Render pure sine tones instead of harmonic-rich tones",
+            default_value_t = false
+        )]
+        sine: bool,
     },
 
     #[command(name = "send-tuning", about = "Send tuning SysEx to MIDI device")]
     SendTuning {
         #[arg(
             help = "Path to .scl file",
+            required_unless_present = "table_path",
             value_parser = parse_absolute_path
         )]
-        scl_path: PathBuf,
+        scl_path: Option<PathBuf>,
 
         #[command(flatten)]
         keyboard_mapping_source: KeyboardMappingSourceGroup,
 
+        #[arg(
+            long = "table",
+            help = "Path to frequency table with one frequency (Hz), cents value (e.g. 6900c) or x per key instead of .scl file",
+            conflicts_with_all = ["scl_path", "kbm_path", "REFERENCE0", "REFERENCE1"],
+            value_parser = parse_absolute_path
+        )]
+        table_path: Option<PathBuf>,
+
         #[command(flatten)]
         output: SendTuningOutputGroup,
 
+        #[arg(
+            long = "virtual-output",
+            requires = "output_port",
+            conflicts_with = "syx_path",
+            help = "Create virtual MIDI output port named after --output instead of connecting to existing port"
+        )]
+        virtual_output: bool,
+
         #[arg(
             help = "Device ID",
             long = "device",
@@ -143,13 +696,20 @@ pub(crate) enum Command {
         device_id: DeviceId,
 
         #[arg(
-            help = "Preset",
+            help = "Preset [default: 8 or device profile's default preset]",
             long = "preset",
             short = 'p',
-            value_parser = <Preset as FromStr>::from_str,
-            default_value_t = Preset::constant::<8>()
+            value_parser = <Preset as FromStr>::from_str
         )]
-        preset: Preset,
+        preset: Option<Preset>,
+
+        #[arg(
+            help = "Bank (sends single note tuning changes with bank select)",
+            long = "bank",
+            short = 'b',
+            value_parser = <MidiValue as FromStr>::from_str
+        )]
+        bank: Option<MidiValue>,
 
         #[arg(
             help = "Chunk size",
@@ -159,19 +719,148 @@ pub(crate) enum Command {
             default_value_t = ChunkSize::ONE
         )]
         chunk_size: ChunkSize,
+
+        #[command(flatten)]
+        out_of_range: OutOfRangeGroup,
+
+        #[command(flatten)]
+        diff: DiffSourceGroup,
+
+        #[arg(
+            long = "device-profile",
+            help = "Name of built-in device profile or path to device profile file"
+        )]
+        device_profile: Option<String>,
+
+        #[command(flatten)]
+        pacing: PacingGroup,
+    },
+
+    #[command(
+        name = "verify",
+        about = "Report error introduced by converting tuning to MTS and fail if it exceeds limit"
+    )]
+    Verify {
+        #[arg(
+            help = "Path to .scl file",
+            required_unless_present = "table_path",
+            value_parser = parse_absolute_path
+        )]
+        scl_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        keyboard_mapping_source: KeyboardMappingSourceGroup,
+
+        #[arg(
+            long = "table",
+            help = "Path to frequency table with one frequency (Hz), cents value (e.g. 6900c) or x per key instead of .scl file",
+            conflicts_with_all = ["scl_path", "kbm_path", "REFERENCE0", "REFERENCE1"],
+            value_parser = parse_absolute_path
+        )]
+        table_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        out_of_range: OutOfRangeGroup,
+
+        #[arg(
+            long = "max-error",
+            help = "Maximum error in cents before failing",
+            default_value_t = 0.01f64
+        )]
+        max_error: f64,
     },
 }
 
 #[derive(Args, Debug)]
 #[group(required = false, multiple = false)]
 pub(crate) struct SendTuningOutputGroup {
-    #[arg(long = "output", short = 'o', help = "MIDI output port name")]
+    #[arg(
+        long = "output",
+        short = 'o',
+        help = "MIDI output port name, list-ports index, alias or pattern"
+    )]
     pub(crate) output_port: Option<String>,
 
     #[arg(long = "file", short = 'f', help = "Path to SysEx file")]
     pub(crate) syx_path: Option<PathBuf>,
 }
 
+#[derive(Args, Debug)]
+pub(crate) struct PacingGroup {
+    #[arg(
+        long = "delay",
+        help = "Delay between SysEx messages in milliseconds",
+        default_value_t = 0
+    )]
+    pub(crate) delay_ms: u64,
+
+    #[arg(
+        long = "rate",
+        help = "Maximum SysEx transmission rate in bytes per second",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub(crate) rate: Option<u32>,
+
+    #[arg(
+        long = "ack-port",
+        help = "MIDI input port (name, index, alias or pattern) to wait on for device ACK after each SysEx message"
+    )]
+    pub(crate) ack_port: Option<String>,
+
+    #[arg(
+        long = "ack-timeout",
+        help = "Time to wait for device ACK in milliseconds",
+        default_value_t = 1000
+    )]
+    pub(crate) ack_timeout_ms: u64,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct OutOfRangeGroup {
+    #[arg(
+        long = "out-of-range",
+        help = "Treatment of frequencies outside MTS range (clamp, fold, unchanged, fail)",
+        value_parser = <OutOfRangePolicy as FromStr>::from_str,
+        default_value_t = OutOfRangePolicy::Clamp
+    )]
+    pub(crate) policy: OutOfRangePolicy,
+}
+
+#[derive(Args, Debug)]
+#[group(required = false, multiple = false)]
+pub(crate) struct DiffSourceGroup {
+    #[arg(
+        long = "state",
+        help = "Path to tuning state file: send only keys changed since the last tuning sent to the device and preset",
+        value_parser = parse_absolute_path
+    )]
+    pub(crate) state_path: Option<PathBuf>,
+
+    #[arg(
+        long = "baseline",
+        help = "MIDI input port (name, index, alias or pattern) to receive bulk dump of preset requested via --output: send only keys that differ from the device's tuning",
+        conflicts_with = "virtual_output"
+    )]
+    pub(crate) baseline_port: Option<String>,
+
+    #[arg(
+        long = "baseline-file",
+        help = "Path to bulk dump .syx file saved from device: send only keys that differ from the dumped tuning",
+        value_parser = parse_absolute_path
+    )]
+    pub(crate) bulk_dump_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+pub(crate) enum BatchFormat {
+    #[clap(name = "syx")]
+    Syx,
+    #[clap(name = "table")]
+    Table,
+    #[clap(name = "json")]
+    Json,
+}
+
 #[derive(Clone, Debug, ValueEnum)]
 pub(crate) enum DumpTuningTableFormat {
     #[clap(name = "brief")]
@@ -203,3 +892,69 @@ pub(crate) struct KeyboardMappingSourceGroup {
     )]
     pub(crate) white_keys: Option<Reference>,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::tuning_tool_args::{Command, TuningToolArgs};
+    use clap::Parser;
+    use rstest::rstest;
+    use tuning_tool_core::out_of_range::OutOfRangePolicy;
+
+    #[rstest]
+    #[case(true, "1")]
+    #[case(true, "31250")]
+    #[case(false, "0")]
+    #[case(false, "-1")]
+    fn rate(#[case] expected: bool, #[case] input: &str) {
+        assert_eq!(
+            expected,
+            TuningToolArgs::try_parse_from([
+                "tuning-tool",
+                "send-tuning",
+                "scale.scl",
+                "--rate",
+                input
+            ])
+            .is_ok()
+        );
+    }
+
+    #[rstest]
+    #[case(true, &[])]
+    #[case(true, &["--note-length", "0.25", "--sample-rate", "22050"])]
+    #[case(false, &["--note-length", "0"])]
+    #[case(false, &["--note-length=-1"])]
+    #[case(false, &["--sample-rate", "0"])]
+    fn preview(#[case] expected: bool, #[case] args: &[&str]) {
+        let mut all_args = vec!["tuning-tool", "preview", "scale.scl", "-o", "out.wav"];
+        all_args.extend(args);
+        assert_eq!(expected, TuningToolArgs::try_parse_from(all_args).is_ok());
+    }
+
+    #[rstest]
+    #[case(true, &["--output", "synth"])]
+    #[case(true, &["--output", "synth", "--virtual-output"])]
+    #[case(false, &["--virtual-output"])]
+    #[case(false, &["--file", "out.syx", "--virtual-output"])]
+    fn virtual_output(#[case] expected: bool, #[case] args: &[&str]) {
+        let mut all_args = vec!["tuning-tool", "send-tuning", "scale.scl"];
+        all_args.extend(args);
+        assert_eq!(expected, TuningToolArgs::try_parse_from(all_args).is_ok());
+    }
+
+    #[rstest]
+    #[case(Some(OutOfRangePolicy::Clamp), &[])]
+    #[case(Some(OutOfRangePolicy::Fold), &["--out-of-range", "fold"])]
+    #[case(None, &["--out-of-range", "wrap"])]
+    fn out_of_range(#[case] expected: Option<OutOfRangePolicy>, #[case] args: &[&str]) {
+        let mut all_args = vec!["tuning-tool", "verify", "scale.scl"];
+        all_args.extend(args);
+        let actual = TuningToolArgs::try_parse_from(all_args)
+            .ok()
+            .map(|args| match args.command {
+                Command::Verify { out_of_range, .. } => out_of_range.policy,
+                _ => unreachable!(),
+            });
+        assert_eq!(expected, actual);
+    }
+}
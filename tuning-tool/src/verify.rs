@@ -0,0 +1,120 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+use crate::tuning_source::TuningSource;
+use anyhow::{bail, Result};
+use tuning_tool_core::midi_note::MidiNote;
+use tuning_tool_core::mts_verification::verify_round_trip;
+use tuning_tool_core::out_of_range::{OutOfRange, OutOfRangePolicy};
+
+pub(crate) fn verify(
+    tuning_source: &TuningSource,
+    out_of_range: OutOfRangePolicy,
+    max_error: f64,
+) -> Result<()> {
+    let keys = verify_round_trip(&tuning_source.frequencies()?, out_of_range)?;
+
+    println!("{tuning_source}");
+    println!("Key  Note  Frequency (Hz)  MTS bytes  MTS frequency (Hz)  Error (cents)");
+    for key in &keys {
+        let note = MidiNote::ALL[key.key.to_u8() as usize];
+        match &key.round_trip {
+            Some(round_trip) => println!(
+                "{key:>3}  {name:<4}  {frequency:>14.6}  {note_number:02X} {msb:02X} {lsb:02X}   {mts_frequency:>18}  {error:>13}{treatment}",
+                key = key.key.to_u8(),
+                name = note.name(),
+                frequency = round_trip.frequency.0,
+                note_number = round_trip.mts_entry.note_number.to_u8(),
+                msb = round_trip.mts_entry.msb.to_u8(),
+                lsb = round_trip.mts_entry.lsb.to_u8(),
+                mts_frequency = round_trip
+                    .mts_frequency
+                    .map(|f| format!("{f:.6}", f = f.0))
+                    .unwrap_or_default(),
+                error = round_trip
+                    .error_cents
+                    .map(|e| format!("{e:+.4}"))
+                    .unwrap_or_default(),
+                treatment = round_trip
+                    .out_of_range
+                    .map(|o| format!("  {o}"))
+                    .unwrap_or_default()
+            ),
+            None => println!(
+                "{key:>3}  {name:<4}  unmapped",
+                key = key.key.to_u8(),
+                name = note.name()
+            ),
+        }
+    }
+
+    let mut unmapped = Vec::new();
+    let mut clamped_low = Vec::new();
+    let mut clamped_high = Vec::new();
+    let mut folded = Vec::new();
+    let mut unchanged = Vec::new();
+    for key in &keys {
+        let list = match key.round_trip.as_ref().map(|r| r.out_of_range) {
+            None => &mut unmapped,
+            Some(Some(OutOfRange::ClampedLow)) => &mut clamped_low,
+            Some(Some(OutOfRange::ClampedHigh)) => &mut clamped_high,
+            Some(Some(OutOfRange::Folded { .. })) => &mut folded,
+            Some(Some(OutOfRange::Unchanged)) => &mut unchanged,
+            Some(None) => continue,
+        };
+        list.push(key.key.to_u8().to_string());
+    }
+
+    println!();
+    println!("Mapped keys: {count}", count = keys.len() - unmapped.len());
+    for (label, list) in [
+        ("Unmapped keys", &unmapped),
+        ("Keys clamped to 0", &clamped_low),
+        ("Keys clamped to 127.99", &clamped_high),
+        ("Keys folded into range", &folded),
+        ("Keys left unchanged", &unchanged),
+    ] {
+        if !list.is_empty() {
+            println!("{label}: {keys}", keys = list.join(", "));
+        }
+    }
+
+    let Some((key, error)) = keys
+        .iter()
+        .filter_map(|k| {
+            k.round_trip
+                .as_ref()
+                .and_then(|r| r.error_cents)
+                .map(|e| (k.key, e.abs()))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+    else {
+        bail!("Tuning has no retuned keys")
+    };
+
+    println!("Maximum error: {error:.4} cents at key {key}");
+    if error > max_error {
+        bail!("Maximum error of {error:.4} cents exceeds limit of {max_error} cents")
+    }
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+
+// Loopback tests that send tunings through virtual MIDI ports instead of
+// hardware: they need a running MIDI system such as the ALSA sequencer so
+// run them with cargo test -- --ignored
+#![cfg(all(unix, feature = "midi-io"))]
+
+use anyhow::{anyhow, bail, Result};
+use midir::os::unix::VirtualInput;
+use midir::{MidiInput, MidiInputConnection};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+type Connection = MidiInputConnection<Sender<Vec<u8>>>;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Real-time single note tuning change for device 0, preset 8
+const NOTE_CHANGE_PREFIX: [u8; 6] = [0xf0, 0x7f, 0x00, 0x08, 0x02, 0x08];
+
+fn scl_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("resources")
+        .join("test")
+        .join("22edo2.scl")
+}
+
+fn tuning_tool() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_tuning-tool"))
+}
+
+fn callback(_timestamp: u64, bytes: &[u8], tx: &mut Sender<Vec<u8>>) {
+    _ = tx.send(bytes.to_vec());
+}
+
+#[test]
+#[ignore = "needs a running MIDI system"]
+fn send_tuning_to_virtual_input() -> Result<()> {
+    let (tx, rx) = channel();
+    let _conn = MidiInput::new("tuning-tool-loopback-test")?
+        .create_virtual("tuning-tool-loopback-in", callback, tx)
+        .map_err(|e| anyhow!("{e}"))?;
+
+    let status = tuning_tool()
+        .arg("send-tuning")
+        .arg(scl_path())
+        .args(["--output", "tuning-tool-loopback-in"])
+        .status()?;
+    assert!(status.success());
+
+    let message = rx.recv_timeout(TIMEOUT)?;
+    assert_eq!(NOTE_CHANGE_PREFIX, message[..NOTE_CHANGE_PREFIX.len()]);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a running MIDI system"]
+fn send_tuning_from_virtual_output() -> Result<()> {
+    let mut child = tuning_tool()
+        .arg("send-tuning")
+        .arg(scl_path())
+        .args(["--output", "tuning-tool-loopback-out", "--virtual-output"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    let (_conn, rx) = match connect_when_created("tuning-tool-loopback-out") {
+        Ok(result) => result,
+        Err(e) => {
+            child.kill()?;
+            return Err(e);
+        }
+    };
+
+    // send-tuning waits for Enter before sending to a virtual port
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("No stdin"))?
+        .write_all(b"\n")?;
+    assert!(child.wait()?.success());
+
+    let message = rx.recv_timeout(TIMEOUT)?;
+    assert_eq!(NOTE_CHANGE_PREFIX, message[..NOTE_CHANGE_PREFIX.len()]);
+    Ok(())
+}
+
+// Connects to the first input port whose name contains the given name
+// once the process under test has created it
+fn connect_when_created(name: &str) -> Result<(Connection, Receiver<Vec<u8>>)> {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        let midi_input = MidiInput::new("tuning-tool-loopback-test")?;
+        let port = midi_input.ports().into_iter().find(|p| {
+            midi_input
+                .port_name(p)
+                .is_ok_and(|port_name| port_name.contains(name))
+        });
+        if let Some(port) = port {
+            let (tx, rx) = channel();
+            let conn = midi_input
+                .connect(&port, "tuning-tool-loopback-test", callback, tx)
+                .map_err(|e| anyhow!("{e}"))?;
+            return Ok((conn, rx));
+        }
+
+        if Instant::now() > deadline {
+            bail!("Timed out waiting for port {name}")
+        }
+        sleep(Duration::from_millis(50));
+    }
+}